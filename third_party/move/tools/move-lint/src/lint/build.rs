@@ -1,13 +1,18 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use anyhow::Result;
+use super::compatibility::PublishedPackage;
+use anyhow::{anyhow, Result};
+use move_binary_format::file_format::CompiledModule;
 use move_model::{
     metadata::{CompilerVersion, LanguageVersion},
     model::GlobalEnv,
 };
 use move_package::{source_package::layout::SourcePackageLayout, BuildConfig, ModelConfig};
-use std::path::{Path, PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 pub enum ModelVersion {
     V1,
@@ -93,3 +98,72 @@ fn compile_ast(path: &Path) -> Result<(CompiledModel, CompiledModel)> {
 pub fn build_ast(path: Option<PathBuf>) -> Result<(CompiledModel, CompiledModel)> {
     handle_reroot_path(path, |rerooted_path| compile_ast(&rerooted_path))
 }
+
+/// Loads a previously published version of a package to check upgrade compatibility against.
+/// The path is either a Move package, a directory of compiled `.mv` modules (such as
+/// `build/<package>/bytecode_modules`) or a single `.mv` module.
+///
+/// # Arguments
+/// * `path` - Path to the published package or its bytecode.
+///
+/// # Returns
+/// The compiled modules of the package, together with its `#[view]` functions when known.
+pub fn load_published_package(path: PathBuf) -> Result<PublishedPackage> {
+    if path.join(SourcePackageLayout::Manifest.path()).is_file() {
+        let env = handle_reroot_path(Some(path), |rerooted_path| {
+            BuildConfig::default().move_model_for_package(
+                &rerooted_path,
+                ModelConfig {
+                    target_filter: None,
+                    all_files_as_targets: false,
+                    compiler_version: CompilerVersion::V2_0,
+                    language_version: LanguageVersion::V2_0,
+                },
+            )
+        })?;
+        let modules = env
+            .get_target_modules()
+            .iter()
+            .filter_map(|module_env| module_env.get_verified_module().cloned())
+            .collect();
+        return Ok(PublishedPackage {
+            modules,
+            view_functions: Some(PublishedPackage::collect_view_functions(&env)),
+        });
+    }
+    let mut modules = vec![];
+    for file in find_bytecode_files(&path)? {
+        let bytes = fs::read(&file)?;
+        let module = CompiledModule::deserialize(&bytes)
+            .map_err(|e| anyhow!("Unable to deserialize `{}`: {:?}", file.display(), e))?;
+        modules.push(module);
+    }
+    Ok(PublishedPackage {
+        modules,
+        view_functions: None,
+    })
+}
+
+/// Returns the `.mv` files at the given path, searching directories recursively. Compiled
+/// dependencies are skipped as they are not part of the package.
+fn find_bytecode_files(path: &Path) -> Result<Vec<PathBuf>> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files = vec![];
+    for entry in fs::read_dir(path)? {
+        let entry_path = entry?.path();
+        if entry_path.is_dir() {
+            if entry_path
+                .file_name()
+                .map_or(false, |name| name == "dependencies")
+            {
+                continue;
+            }
+            files.extend(find_bytecode_files(&entry_path)?);
+        } else if entry_path.extension().map_or(false, |ext| ext == "mv") {
+            files.push(entry_path);
+        }
+    }
+    Ok(files)
+}
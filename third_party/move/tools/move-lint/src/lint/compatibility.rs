@@ -0,0 +1,353 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks a package against a previously published version of it and reports the changes which
+//! the `compatible` upgrade policy rejects: removed modules, structs and public or entry functions,
+//! changed struct layouts, dropped abilities, changed public, entry and `#[view]` signatures and
+//! removed friend declarations.
+use super::utils::{add_diagnostic_and_emit, add_unlocated_diagnostic_and_emit};
+use codespan::FileId;
+use codespan_reporting::diagnostic::{Diagnostic, Severity};
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{CompiledModule, Visibility},
+    normalized,
+};
+use move_core_types::{identifier::Identifier, language_storage::ModuleId};
+use move_model::model::{GlobalEnv, Loc, ModuleEnv};
+use std::collections::{BTreeMap, BTreeSet};
+
+const VIEW_ATTRIBUTE: &str = "view";
+
+/// The previously published version of a package.
+pub struct PublishedPackage {
+    pub modules: Vec<CompiledModule>,
+    /// The `#[view]` functions of the package. Only known when the package was given as source,
+    /// as the attribute is not part of the bytecode.
+    pub view_functions: Option<BTreeSet<(ModuleId, Identifier)>>,
+}
+
+impl PublishedPackage {
+    /// Collects the `#[view]` functions of the target modules of a package.
+    pub fn collect_view_functions(env: &GlobalEnv) -> BTreeSet<(ModuleId, Identifier)> {
+        let mut view_functions = BTreeSet::new();
+        for module_env in env.get_target_modules() {
+            let module_id = match module_env.get_verified_module() {
+                Some(compiled_module) => compiled_module.self_id(),
+                None => continue,
+            };
+            for func_env in module_env.get_functions() {
+                if is_view_function(&module_env, &func_env.get_name_str()) {
+                    view_functions.insert((
+                        module_id.clone(),
+                        Identifier::new(func_env.get_name_str()).expect("valid identifier"),
+                    ));
+                }
+            }
+        }
+        view_functions
+    }
+}
+
+/// Normalized form of a module, including its private functions.
+struct NormalizedModule {
+    module: normalized::Module,
+    functions: BTreeMap<Identifier, normalized::Function>,
+}
+
+impl NormalizedModule {
+    fn new(compiled_module: &CompiledModule) -> Self {
+        Self {
+            module: normalized::Module::new(compiled_module),
+            functions: compiled_module
+                .function_defs()
+                .iter()
+                .map(|def| normalized::Function::new(compiled_module, def))
+                .collect(),
+        }
+    }
+}
+
+fn is_view_function(module_env: &ModuleEnv, func_name: &str) -> bool {
+    let func_sym = module_env.symbol_pool().make(func_name);
+    module_env
+        .find_function(func_sym)
+        .map_or(false, |func_env| {
+            func_env.has_attribute(|attr| {
+                func_env.symbol_pool().string(attr.name()).as_str() == VIEW_ATTRIBUTE
+            })
+        })
+}
+
+fn is_exposed(function: &normalized::Function) -> bool {
+    function.visibility == Visibility::Public || function.is_entry
+}
+
+fn format_signature(name: &Identifier, function: &normalized::Function) -> String {
+    let format_types = |types: &[normalized::Type]| {
+        types
+            .iter()
+            .map(|ty| ty.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    format!(
+        "{}({}): ({})",
+        name,
+        format_types(&function.parameters),
+        format_types(&function.return_)
+    )
+}
+
+/// Returns true if code compiled against the old signature can still call the new one.
+fn is_signature_compatible(old: &normalized::Function, new: &normalized::Function) -> bool {
+    old.parameters == new.parameters
+        && old.return_ == new.return_
+        && old.type_parameters.len() == new.type_parameters.len()
+        && old
+            .type_parameters
+            .iter()
+            .zip(new.type_parameters.iter())
+            .all(|(old_constraints, new_constraints)| new_constraints.is_subset(*old_constraints))
+}
+
+/// Compares the target modules of `env`, which must have bytecode attached, against the
+/// published package and reports the breaking changes.
+pub fn check_compatibility(
+    published: &PublishedPackage,
+    env: &GlobalEnv,
+    diags: &mut Vec<Diagnostic<FileId>>,
+) {
+    let new_modules: BTreeMap<ModuleId, (ModuleEnv, NormalizedModule)> = env
+        .get_target_modules()
+        .into_iter()
+        .filter_map(|module_env| {
+            let compiled_module = module_env.get_verified_module()?;
+            let module_id = compiled_module.self_id();
+            let normalized_module = NormalizedModule::new(compiled_module);
+            Some((module_id, (module_env, normalized_module)))
+        })
+        .collect();
+    for old_compiled_module in &published.modules {
+        let old = NormalizedModule::new(old_compiled_module);
+        let module_id = old.module.module_id();
+        match new_modules.get(&module_id) {
+            Some((module_env, new)) => {
+                let checker = ModuleChecker {
+                    env,
+                    module_env,
+                    old: &old,
+                    new,
+                    old_view_functions: published.view_functions.as_ref(),
+                };
+                checker.check_structs(diags);
+                checker.check_functions(diags);
+                checker.check_view_functions(diags);
+                checker.check_friends(diags);
+            },
+            None => {
+                let message = format!(
+                    "Module `{}` of the published package was removed. Modules cannot be removed in a compatible upgrade.",
+                    module_id.short_str_lossless()
+                );
+                add_unlocated_diagnostic_and_emit(&message, Severity::Warning, env, diags);
+            },
+        }
+    }
+}
+
+struct ModuleChecker<'a> {
+    env: &'a GlobalEnv,
+    module_env: &'a ModuleEnv<'a>,
+    old: &'a NormalizedModule,
+    new: &'a NormalizedModule,
+    old_view_functions: Option<&'a BTreeSet<(ModuleId, Identifier)>>,
+}
+
+impl<'a> ModuleChecker<'a> {
+    fn warn(&self, loc: &Loc, message: &str, diags: &mut Vec<Diagnostic<FileId>>) {
+        add_diagnostic_and_emit(loc, message, Severity::Warning, self.env, diags);
+    }
+
+    fn module_name(&self) -> String {
+        self.old.module.name.to_string()
+    }
+
+    fn struct_loc(&self, name: &Identifier) -> Loc {
+        self.module_env
+            .find_struct(self.env.symbol_pool().make(name.as_str()))
+            .map_or_else(
+                || self.module_env.get_loc(),
+                |struct_env| struct_env.get_loc(),
+            )
+    }
+
+    fn function_loc(&self, name: &Identifier) -> Loc {
+        self.module_env
+            .find_function(self.env.symbol_pool().make(name.as_str()))
+            .map_or_else(
+                || self.module_env.get_loc(),
+                |func_env| func_env.get_id_loc(),
+            )
+    }
+
+    /// Structs cannot be removed, change their fields, drop abilities or tighten type parameters.
+    fn check_structs(&self, diags: &mut Vec<Diagnostic<FileId>>) {
+        for (name, old_struct) in &self.old.module.structs {
+            let new_struct = match self.new.module.structs.get(name) {
+                Some(new_struct) => new_struct,
+                None => {
+                    let message = format!(
+                        "Struct `{}::{}` was removed. Structs cannot be removed in a compatible upgrade.",
+                        self.module_name(),
+                        name
+                    );
+                    self.warn(&self.module_env.get_loc(), &message, diags);
+                    continue;
+                },
+            };
+            let loc = self.struct_loc(name);
+            if !old_struct.abilities.is_subset(new_struct.abilities) {
+                let message = format!(
+                    "Struct `{}` dropped abilities: it had `{}` and now has `{}`.",
+                    name, old_struct.abilities, new_struct.abilities
+                );
+                self.warn(&loc, &message, diags);
+            }
+            if old_struct.fields != new_struct.fields {
+                let message = format!(
+                    "The layout of struct `{}` changed. Fields cannot be added, removed, renamed, reordered or retyped in a compatible upgrade.",
+                    name
+                );
+                self.warn(&loc, &message, diags);
+            }
+            let type_params_compatible = old_struct.type_parameters.len()
+                == new_struct.type_parameters.len()
+                && old_struct
+                    .type_parameters
+                    .iter()
+                    .zip(new_struct.type_parameters.iter())
+                    .all(|(old_param, new_param)| {
+                        old_param.is_phantom == new_param.is_phantom
+                            && new_param.constraints.is_subset(old_param.constraints)
+                    });
+            if !type_params_compatible {
+                let message = format!(
+                    "The type parameters of struct `{}` changed incompatibly.",
+                    name
+                );
+                self.warn(&loc, &message, diags);
+            }
+        }
+    }
+
+    /// Public and entry functions cannot be removed, lose visibility or change signature.
+    fn check_functions(&self, diags: &mut Vec<Diagnostic<FileId>>) {
+        for (name, old_function) in &self.old.functions {
+            if !is_exposed(old_function) {
+                continue;
+            }
+            let kind = if old_function.visibility == Visibility::Public {
+                "Public"
+            } else {
+                "Entry"
+            };
+            let new_function = match self.new.functions.get(name) {
+                Some(new_function) => new_function,
+                None => {
+                    let message = format!(
+                        "{} function `{}::{}` was removed. It cannot be removed in a compatible upgrade.",
+                        kind,
+                        self.module_name(),
+                        name
+                    );
+                    self.warn(&self.module_env.get_loc(), &message, diags);
+                    continue;
+                },
+            };
+            let loc = self.function_loc(name);
+            if old_function.visibility == Visibility::Public
+                && new_function.visibility != Visibility::Public
+            {
+                let message = format!(
+                    "Public function `{}` is no longer public. Public functions cannot lose visibility in a compatible upgrade.",
+                    name
+                );
+                self.warn(&loc, &message, diags);
+            }
+            if old_function.is_entry && !new_function.is_entry {
+                let message = format!(
+                    "Function `{}` is no longer an entry function. Existing transactions calling it would fail.",
+                    name
+                );
+                self.warn(&loc, &message, diags);
+            }
+            if !is_signature_compatible(old_function, new_function) {
+                let message = format!(
+                    "{} function signature changed from `{}` to `{}`.",
+                    kind,
+                    format_signature(name, old_function),
+                    format_signature(name, new_function)
+                );
+                self.warn(&loc, &message, diags);
+            }
+        }
+    }
+
+    /// `#[view]` functions are called from off-chain clients, so they must keep their attribute
+    /// and signature even when they are not public.
+    fn check_view_functions(&self, diags: &mut Vec<Diagnostic<FileId>>) {
+        let old_view_functions = match self.old_view_functions {
+            Some(old_view_functions) => old_view_functions,
+            None => return,
+        };
+        let module_id = self.old.module.module_id();
+        for (_, name) in old_view_functions.iter().filter(|(id, _)| *id == module_id) {
+            let (old_function, new_function) =
+                match (self.old.functions.get(name), self.new.functions.get(name)) {
+                    (Some(old_function), Some(new_function)) => (old_function, new_function),
+                    // Removed public and entry functions are already reported.
+                    (Some(old_function), None) => {
+                        if !is_exposed(old_function) {
+                            let message = format!(
+                                "View function `{}::{}` was removed.",
+                                self.module_name(),
+                                name
+                            );
+                            self.warn(&self.module_env.get_loc(), &message, diags);
+                        }
+                        continue;
+                    },
+                    _ => continue,
+                };
+            let loc = self.function_loc(name);
+            if !is_view_function(self.module_env, name.as_str()) {
+                let message = format!("Function `{}` is no longer a `#[view]` function.", name);
+                self.warn(&loc, &message, diags);
+            }
+            // Signatures of public and entry functions are already checked.
+            if !is_exposed(old_function) && !is_signature_compatible(old_function, new_function) {
+                let message = format!(
+                    "View function signature changed from `{}` to `{}`.",
+                    format_signature(name, old_function),
+                    format_signature(name, new_function)
+                );
+                self.warn(&loc, &message, diags);
+            }
+        }
+    }
+
+    /// Removing a friend declaration breaks the published friend module.
+    fn check_friends(&self, diags: &mut Vec<Diagnostic<FileId>>) {
+        for friend in &self.old.module.friends {
+            if !self.new.module.friends.contains(friend) {
+                let message = format!(
+                    "Friend declaration of `{}` was removed. The friend module may no longer link against `{}`.",
+                    friend.short_str_lossless(),
+                    self.module_name()
+                );
+                self.warn(&self.module_env.get_loc(), &message, diags);
+            }
+        }
+    }
+}
@@ -0,0 +1,545 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! An interval domain for the unsigned integer temporaries of a function, computed over its
+//! stackless bytecode with the `dataflow_analysis` framework. Ranges start from constants and are
+//! propagated through assignments and arithmetic, covering every width from `u8` to `u256`.
+//! Comparisons narrow the ranges on the branch they guard, so `if (x < 10)` and `assert!(x < 10)`
+//! bound `x` in the code they protect. Arithmetic in Move aborts instead of wrapping, so the
+//! range of a result only covers the executions which continue past the operation.
+//!
+//! Temporaries without an entry in the state may hold any value of their type. Ranges which keep
+//! growing at a join point, such as loop counters, are widened to the full range of their type
+//! after a few iterations so that the analysis terminates.
+use move_binary_format::file_format::CodeOffset;
+use move_model::{
+    ast::TempIndex,
+    model::FunctionEnv,
+    ty::{PrimitiveType, Type},
+};
+use move_stackless_bytecode::{
+    dataflow_analysis::{DataflowAnalysis, TransferFunctions},
+    dataflow_domains::{AbstractDomain, JoinResult},
+    function_target::FunctionTarget,
+    stackless_bytecode::{Bytecode, Constant, Label, Operation},
+    stackless_bytecode_generator::StacklessBytecodeGenerator,
+    stackless_control_flow_graph::StacklessControlFlowGraph,
+};
+use num::{One, ToPrimitive, Zero};
+use num_bigint::{BigInt, Sign};
+use std::{
+    cmp::{max, min},
+    collections::BTreeMap,
+};
+
+/// Number of changing joins at a program point after which growing ranges are widened.
+const WIDENING_THRESHOLD: usize = 3;
+
+/// An inclusive range of integer values. Results of arithmetic on intervals are not bounded by
+/// any type, so they can be compared against the range of the result type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Interval {
+    pub lo: BigInt,
+    pub hi: BigInt,
+}
+
+impl Interval {
+    pub fn new(lo: BigInt, hi: BigInt) -> Self {
+        Self { lo, hi }
+    }
+
+    pub fn point(value: BigInt) -> Self {
+        Self::new(value.clone(), value)
+    }
+
+    /// The range of all values of a type with the given maximum.
+    pub fn full(type_max: &BigInt) -> Self {
+        Self::new(BigInt::zero(), type_max.clone())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lo > self.hi
+    }
+
+    pub fn join(&self, other: &Interval) -> Interval {
+        Interval::new(
+            min(&self.lo, &other.lo).clone(),
+            max(&self.hi, &other.hi).clone(),
+        )
+    }
+
+    pub fn meet(&self, other: &Interval) -> Interval {
+        Interval::new(
+            max(&self.lo, &other.lo).clone(),
+            min(&self.hi, &other.hi).clone(),
+        )
+    }
+
+    /// Restricts the interval to the values of a type, returning `None` if no value fits.
+    pub fn clamp(&self, type_max: &BigInt) -> Option<Interval> {
+        Some(self.meet(&Interval::full(type_max))).filter(|interval| !interval.is_empty())
+    }
+
+    pub fn add(&self, other: &Interval) -> Interval {
+        Interval::new(&self.lo + &other.lo, &self.hi + &other.hi)
+    }
+
+    pub fn sub(&self, other: &Interval) -> Interval {
+        Interval::new(&self.lo - &other.hi, &self.hi - &other.lo)
+    }
+
+    pub fn mul(&self, other: &Interval) -> Interval {
+        Interval::new(&self.lo * &other.lo, &self.hi * &other.hi)
+    }
+
+    /// Division by the non-zero values of `other`, or `None` if it is always zero.
+    pub fn div(&self, other: &Interval) -> Option<Interval> {
+        let divisor = other.meet(&Interval::new(BigInt::one(), other.hi.clone()));
+        if divisor.is_empty() {
+            return None;
+        }
+        Some(Interval::new(
+            &self.lo / &divisor.hi,
+            &self.hi / &divisor.lo,
+        ))
+    }
+
+    /// Remainder of the division by the non-zero values of `other`, or `None` if it is always
+    /// zero.
+    pub fn rem(&self, other: &Interval) -> Option<Interval> {
+        if other.hi.is_zero() {
+            return None;
+        }
+        if self.hi < other.lo {
+            return Some(self.clone());
+        }
+        Some(Interval::new(
+            BigInt::zero(),
+            min(&self.hi, &(&other.hi - 1)).clone(),
+        ))
+    }
+
+    /// Left shift without truncation to any width.
+    pub fn shl(&self, amount: &Interval) -> Interval {
+        let lo = amount.lo.to_usize().unwrap_or(usize::MAX);
+        let hi = amount.hi.to_usize().unwrap_or(usize::MAX);
+        Interval::new(&self.lo << min(lo, 256), &self.hi << min(hi, 256))
+    }
+
+    pub fn shr(&self, amount: &Interval) -> Interval {
+        let lo = amount.lo.to_usize().unwrap_or(usize::MAX);
+        let hi = amount.hi.to_usize().unwrap_or(usize::MAX);
+        Interval::new(&self.lo >> min(hi, 256), &self.hi >> min(lo, 256))
+    }
+}
+
+/// Returns the maximal value of an unsigned integer type.
+pub fn get_type_max(ty: &Type) -> Option<BigInt> {
+    match ty {
+        Type::Primitive(
+            prim @ (PrimitiveType::U8
+            | PrimitiveType::U16
+            | PrimitiveType::U32
+            | PrimitiveType::U64
+            | PrimitiveType::U128
+            | PrimitiveType::U256),
+        ) => prim.get_max_value(),
+        _ => None,
+    }
+}
+
+/// Returns the target type of a cast operation.
+pub fn get_cast_type(op: &Operation) -> Option<Type> {
+    let prim = match op {
+        Operation::CastU8 => PrimitiveType::U8,
+        Operation::CastU16 => PrimitiveType::U16,
+        Operation::CastU32 => PrimitiveType::U32,
+        Operation::CastU64 => PrimitiveType::U64,
+        Operation::CastU128 => PrimitiveType::U128,
+        Operation::CastU256 => PrimitiveType::U256,
+        _ => return None,
+    };
+    Some(Type::Primitive(prim))
+}
+
+/// Returns the number of bits of a type with the given maximum.
+pub fn get_bit_width(type_max: &BigInt) -> u64 {
+    type_max.bits()
+}
+
+/// Computes the range of the result of an operation, given the ranges of its arguments and the
+/// maximum of its result type. Only executions which do not abort are considered, so `None` is
+/// returned if the operation always aborts.
+pub fn eval_operation(op: &Operation, args: &[Interval], result_max: &BigInt) -> Option<Interval> {
+    let full = Interval::full(result_max);
+    match (op, args) {
+        (Operation::Add, [lhs, rhs]) => lhs.add(rhs).clamp(result_max),
+        (Operation::Sub, [lhs, rhs]) => lhs.sub(rhs).clamp(result_max),
+        (Operation::Mul, [lhs, rhs]) => lhs.mul(rhs).clamp(result_max),
+        (Operation::Div, [lhs, rhs]) => lhs.div(rhs),
+        (Operation::Mod, [lhs, rhs]) => lhs.rem(rhs),
+        (Operation::Shl | Operation::Shr, [value, amount]) => {
+            // Shifting by the bit width or more aborts.
+            let amount = amount.clamp(&BigInt::from(get_bit_width(result_max) - 1))?;
+            if matches!(op, Operation::Shr) {
+                return Some(value.shr(&amount));
+            }
+            // Bits shifted out are dropped rather than aborting.
+            let shifted = value.shl(&amount);
+            Some(if &shifted.hi <= result_max {
+                shifted
+            } else {
+                full
+            })
+        },
+        (Operation::BitAnd, [lhs, rhs]) => {
+            Some(Interval::new(BigInt::zero(), min(&lhs.hi, &rhs.hi).clone()))
+        },
+        (Operation::BitOr, [lhs, rhs]) => Some(Interval::new(
+            max(&lhs.lo, &rhs.lo).clone(),
+            result_max.clone(),
+        )),
+        (op, [arg]) if get_cast_type(op).is_some() => arg.clamp(result_max),
+        _ => Some(full),
+    }
+}
+
+/// A comparison between two temporaries whose result is held by a boolean temporary.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Condition {
+    op: Operation,
+    lhs: TempIndex,
+    rhs: TempIndex,
+}
+
+impl Condition {
+    fn negate(&self) -> Condition {
+        let op = match self.op {
+            Operation::Lt => Operation::Ge,
+            Operation::Le => Operation::Gt,
+            Operation::Gt => Operation::Le,
+            Operation::Ge => Operation::Lt,
+            Operation::Eq => Operation::Neq,
+            _ => Operation::Eq,
+        };
+        Condition { op, ..self.clone() }
+    }
+}
+
+/// The ranges known at a program point.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IntervalState {
+    ranges: BTreeMap<TempIndex, Interval>,
+    /// Temporaries holding a copy of another temporary which was not modified since.
+    copies: BTreeMap<TempIndex, TempIndex>,
+    /// Boolean temporaries holding the result of a comparison.
+    conditions: BTreeMap<TempIndex, Condition>,
+    joins: usize,
+}
+
+impl IntervalState {
+    /// Returns the range of a temporary if the analysis bounded it, e.g. through a constant or a
+    /// guard.
+    pub fn get(&self, temp: TempIndex) -> Option<&Interval> {
+        self.ranges.get(&temp)
+    }
+
+    /// Returns the range of an integer temporary, which is the full range of its type if the
+    /// analysis could not bound it.
+    pub fn range(&self, temp: TempIndex, target: &FunctionTarget) -> Option<Interval> {
+        match self.ranges.get(&temp) {
+            Some(interval) => Some(interval.clone()),
+            None => get_type_max(target.get_local_type(temp)).map(|max| Interval::full(&max)),
+        }
+    }
+
+    /// Forgets everything known about a temporary, which is about to be redefined.
+    fn invalidate(&mut self, temp: TempIndex) {
+        self.ranges.remove(&temp);
+        self.copies.remove(&temp);
+        self.copies.retain(|_, src| *src != temp);
+        self.conditions.remove(&temp);
+        self.conditions
+            .retain(|_, cond| cond.lhs != temp && cond.rhs != temp);
+    }
+
+    /// Narrows the range of a temporary, and of the temporaries it is a copy of.
+    fn narrow(&mut self, temp: TempIndex, bound: &Interval, target: &FunctionTarget) {
+        let mut current = Some(temp);
+        let mut steps = 0;
+        while let Some(temp) = current {
+            if let Some(range) = self.range(temp, target) {
+                let narrowed = range.meet(bound);
+                if !narrowed.is_empty() {
+                    self.ranges.insert(temp, narrowed);
+                }
+            }
+            current = self.copies.get(&temp).copied();
+            steps += 1;
+            if steps > self.copies.len() {
+                break;
+            }
+        }
+    }
+
+    /// Narrows the ranges of the compared temporaries given that the comparison held by `cond`
+    /// evaluates to `holds`.
+    fn refine(&mut self, cond: TempIndex, holds: bool, target: &FunctionTarget) {
+        let condition = match self.conditions.get(&cond) {
+            Some(condition) if holds => condition.clone(),
+            Some(condition) => condition.negate(),
+            None => return,
+        };
+        let (lhs, rhs) = match (
+            self.range(condition.lhs, target),
+            self.range(condition.rhs, target),
+        ) {
+            (Some(lhs), Some(rhs)) => (lhs, rhs),
+            _ => return,
+        };
+        let unbounded = max(&lhs.hi, &rhs.hi).clone();
+        let (lhs_bound, rhs_bound) = match condition.op {
+            Operation::Lt => (
+                Interval::new(BigInt::zero(), &rhs.hi - 1),
+                Interval::new(&lhs.lo + 1, unbounded),
+            ),
+            Operation::Le => (
+                Interval::new(BigInt::zero(), rhs.hi.clone()),
+                Interval::new(lhs.lo.clone(), unbounded),
+            ),
+            Operation::Gt => (
+                Interval::new(&rhs.lo + 1, unbounded),
+                Interval::new(BigInt::zero(), &lhs.hi - 1),
+            ),
+            Operation::Ge => (
+                Interval::new(rhs.lo.clone(), unbounded),
+                Interval::new(BigInt::zero(), lhs.hi.clone()),
+            ),
+            Operation::Eq => (rhs.clone(), lhs.clone()),
+            // `x != c` only narrows `x` if `c` is one of its bounds.
+            _ => {
+                for (temp, range, other) in
+                    [(condition.lhs, &lhs, &rhs), (condition.rhs, &rhs, &lhs)]
+                {
+                    if other.lo == other.hi && range.lo == other.lo {
+                        self.narrow(
+                            temp,
+                            &Interval::new(&range.lo + 1, range.hi.clone()),
+                            target,
+                        );
+                    } else if other.lo == other.hi && range.hi == other.hi {
+                        self.narrow(
+                            temp,
+                            &Interval::new(range.lo.clone(), &range.hi - 1),
+                            target,
+                        );
+                    }
+                }
+                return;
+            },
+        };
+        self.narrow(condition.lhs, &lhs_bound, target);
+        self.narrow(condition.rhs, &rhs_bound, target);
+    }
+}
+
+impl AbstractDomain for IntervalState {
+    fn join(&mut self, other: &Self) -> JoinResult {
+        let widen = self.joins >= WIDENING_THRESHOLD;
+        let ranges = self
+            .ranges
+            .iter()
+            .filter_map(|(temp, range)| {
+                let joined = range.join(other.ranges.get(temp)?);
+                // Ranges which keep growing are dropped, meaning they may hold any value.
+                (!widen || joined == *range).then_some((*temp, joined))
+            })
+            .collect();
+        let copies = self
+            .copies
+            .iter()
+            .filter(|(temp, src)| other.copies.get(temp) == Some(src))
+            .map(|(temp, src)| (*temp, *src))
+            .collect();
+        let conditions = self
+            .conditions
+            .iter()
+            .filter(|(temp, cond)| other.conditions.get(temp) == Some(cond))
+            .map(|(temp, cond)| (*temp, cond.clone()))
+            .collect();
+        if ranges == self.ranges && copies == self.copies && conditions == self.conditions {
+            return JoinResult::Unchanged;
+        }
+        self.ranges = ranges;
+        self.copies = copies;
+        self.conditions = conditions;
+        self.joins += 1;
+        JoinResult::Changed
+    }
+}
+
+struct IntervalAnalysis<'a> {
+    target: &'a FunctionTarget<'a>,
+    /// Labels only reachable from one side of a branch, with the condition and the side.
+    guarded_labels: BTreeMap<Label, (TempIndex, bool)>,
+}
+
+impl<'a> IntervalAnalysis<'a> {
+    fn new(target: &'a FunctionTarget<'a>) -> Self {
+        let code = target.get_bytecode();
+        let mut references: BTreeMap<Label, usize> = BTreeMap::new();
+        for instr in code {
+            for label in instr.branch_dests() {
+                *references.entry(label).or_default() += 1;
+            }
+        }
+        // A label is only guarded by a branch if it can neither be reached through another jump
+        // nor by falling through from the preceding instruction.
+        let is_exclusive = |label: &Label| {
+            references.get(label) == Some(&1)
+                && code.iter().enumerate().any(|(offset, instr)| {
+                    matches!(instr, Bytecode::Label(_, l) if l == label)
+                        && (offset == 0 || code[offset - 1].is_always_branching())
+                })
+        };
+        let mut guarded_labels = BTreeMap::new();
+        for instr in code {
+            if let Bytecode::Branch(_, then_label, else_label, cond) = instr {
+                if then_label == else_label {
+                    continue;
+                }
+                if is_exclusive(then_label) {
+                    guarded_labels.insert(*then_label, (*cond, true));
+                }
+                if is_exclusive(else_label) {
+                    guarded_labels.insert(*else_label, (*cond, false));
+                }
+            }
+        }
+        Self {
+            target,
+            guarded_labels,
+        }
+    }
+
+    fn get_constant_value(&self, constant: &Constant) -> Option<BigInt> {
+        match constant {
+            Constant::U8(value) => Some(BigInt::from(*value)),
+            Constant::U16(value) => Some(BigInt::from(*value)),
+            Constant::U32(value) => Some(BigInt::from(*value)),
+            Constant::U64(value) => Some(BigInt::from(*value)),
+            Constant::U128(value) => Some(BigInt::from(*value)),
+            Constant::U256(value) => Some(BigInt::from_bytes_le(Sign::Plus, &value.to_le_bytes())),
+            _ => None,
+        }
+    }
+}
+
+impl TransferFunctions for IntervalAnalysis<'_> {
+    type State = IntervalState;
+
+    const BACKWARD: bool = false;
+
+    fn execute(&self, state: &mut IntervalState, instr: &Bytecode, _offset: CodeOffset) {
+        match instr {
+            Bytecode::Label(_, label) => {
+                if let Some((cond, holds)) = self.guarded_labels.get(label) {
+                    state.refine(*cond, *holds, self.target);
+                }
+            },
+            Bytecode::Load(_, dst, constant) => {
+                state.invalidate(*dst);
+                if let Some(value) = self.get_constant_value(constant) {
+                    state.ranges.insert(*dst, Interval::point(value));
+                }
+            },
+            Bytecode::Assign(_, dst, src, _) => {
+                let range = state.ranges.get(src).cloned();
+                state.invalidate(*dst);
+                if let Some(range) = range {
+                    state.ranges.insert(*dst, range);
+                }
+                state.copies.insert(*dst, *src);
+            },
+            Bytecode::Call(_, dsts, op, srcs, _) => {
+                let args: Option<Vec<_>> = srcs
+                    .iter()
+                    .map(|src| state.range(*src, self.target))
+                    .collect();
+                let condition = match (op, srcs.as_slice()) {
+                    (
+                        Operation::Lt
+                        | Operation::Le
+                        | Operation::Gt
+                        | Operation::Ge
+                        | Operation::Eq
+                        | Operation::Neq,
+                        [lhs, rhs],
+                    ) => Some(Condition {
+                        op: op.clone(),
+                        lhs: *lhs,
+                        rhs: *rhs,
+                    }),
+                    (Operation::Not, [src]) => state.conditions.get(src).map(|cond| cond.negate()),
+                    _ => None,
+                };
+                for dst in instr.dests() {
+                    state.invalidate(dst);
+                }
+                if let Operation::BorrowLoc = op {
+                    // The borrowed local may be written through the reference.
+                    for src in srcs {
+                        state.invalidate(*src);
+                    }
+                }
+                if let ([dst], Some(args)) = (dsts.as_slice(), args) {
+                    let result_max = get_type_max(self.target.get_local_type(*dst));
+                    if let Some(max) = result_max {
+                        // Results which may hold any value of their type are left unbounded.
+                        match eval_operation(op, &args, &max) {
+                            Some(range) if range != Interval::full(&max) => {
+                                state.ranges.insert(*dst, range);
+                            },
+                            _ => {},
+                        }
+                    }
+                    if let Some(condition) = condition {
+                        state.conditions.insert(*dst, condition);
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+}
+
+impl DataflowAnalysis for IntervalAnalysis<'_> {}
+
+/// Computes the ranges known before each instruction of the function. Unreachable instructions
+/// have no entry.
+pub fn analyze_intervals(target: &FunctionTarget) -> BTreeMap<CodeOffset, IntervalState> {
+    let code = target.get_bytecode();
+    let cfg = StacklessControlFlowGraph::new_forward(code);
+    let analysis = IntervalAnalysis::new(target);
+    let state_map = analysis.analyze_function(IntervalState::default(), code, &cfg);
+    analysis.state_per_instruction(state_map, code, &cfg, |before, _| before.clone())
+}
+
+/// Runs the interval analysis on the bytecode of a function and calls `check` on each reachable
+/// instruction with the ranges known before it. Inline and native functions are skipped.
+pub fn for_each_instruction_with_intervals(
+    func_env: &FunctionEnv,
+    mut check: impl FnMut(&FunctionTarget, &Bytecode, &IntervalState),
+) {
+    if func_env.is_inline() || func_env.is_native() {
+        return;
+    }
+    let data = StacklessBytecodeGenerator::new(func_env).generate_function();
+    let target = FunctionTarget::new(func_env, &data);
+    let states = analyze_intervals(&target);
+    for (offset, instr) in target.get_bytecode().iter().enumerate() {
+        if let Some(state) = states.get(&(offset as CodeOffset)) {
+            check(&target, instr, state);
+        }
+    }
+}
@@ -18,21 +18,29 @@ pub mod utils;
 
 /// Focused on the compiling move code into GlobalEnv
 pub mod build;
+
+/// Checks upgrade compatibility against a previously published version of the package.
+pub mod compatibility;
+
+/// Interval analysis of integer values over stackless bytecode, shared by the arithmetic lints.
+pub mod interval_analysis;
 use self::{
     manager::VisitorManager,
     rules::{
-        absurd_extreme_comparisons::LikelyComparisonMistake,
-        bool_comparison::BoolComparisonVisitor,
+        abort_code::AbortCodeVisitor, absurd_extreme_comparisons::LikelyComparisonMistake,
+        bool_comparison::BoolComparisonVisitor, cast_truncation::CastTruncationVisitor,
         check_redundant_boolean_expressions::RedundantBooleanExpressions,
         combinable_bool_conditions::CombinableBoolVisitor,
         complex_inline_function::ComplexInlineFunctionVisitor,
         constant_naming::ConstantNamingVisitor, deep_nesting::DeepNestingVisitor,
+        division_by_zero::DivisionByZeroVisitor, duplicated_code::DuplicatedCodeVisitor,
         empty_loop::EmptyLoopVisitor, event_attribute_ability::EventAttributeAbility,
-        exceed_blocks::ExceedBlocksVisitor, exceed_fields::ExceedFieldsVisitor,
-        exceed_params::ExceedParamsVisitor,
+        event_emission::EventEmissionVisitor, exceed_blocks::ExceedBlocksVisitor,
+        exceed_fields::ExceedFieldsVisitor, exceed_params::ExceedParamsVisitor,
         explicit_self_assignments::ExplicitSelfAssignmentsVisitor,
-        getter_method_field_match::GetterMethodFieldMatchLint, ifs_same_cond::IfsSameCondVisitor,
-        infinite_loop_detector::InfiniteLoopDetectorVisitor,
+        front_running::FrontRunningVisitor, getter_method_field_match::GetterMethodFieldMatchLint,
+        ifs_same_cond::IfsSameCondVisitor, ignored_return_value::IgnoredReturnValueVisitor,
+        infinite_loop_detector::InfiniteLoopDetectorVisitor, init_module::InitModuleVisitor,
         meaningless_math_operations::MeaninglessMathOperationsVisitor,
         multiplication_before_division::MultiplicationBeforeDivisionVisitor,
         needless_bool::NeedlessBoolVisitor,
@@ -41,13 +49,22 @@ use self::{
         randomness_public_entry::RandomnessPublicEntry,
         redundant_deref_ref::RedundantDerefRefVisitor,
         redundant_ref_deref::RedundantRefDerefVisitor,
+        reentrancy_dispatch::ReentrancyDispatchVisitor, resource_group::ResourceGroupVisitor,
         return_at_end_of_block::ReturnAtEndOfBlockVisitor, shift_overflow::ShiftOverflowVisitor,
-        sorted_imports::SortedImportsLint, unconditional_exit_loop::UnconditionalExitLoopVisitor,
+        sorted_imports::SortedImportsLint, spot_price_oracle::SpotPriceOracleVisitor,
+        subtraction_underflow::SubtractionUnderflowVisitor,
+        timestamp_dependence::TimestampDependenceVisitor,
+        unbounded_iteration::UnboundedIterationVisitor,
+        unconditional_exit_loop::UnconditionalExitLoopVisitor,
         unmodified_mutable_argument::UnmodifiedMutableArgumentLint,
         unnecessary_mutable_reference::UnnecessaryMutableReferenceLint,
         unnecessary_type_conversion::UnnecessaryTypeConversionVisitor,
         unnecessary_while_true::UnnecessaryWhileTrueVisitor,
-        unused_borrow_global_mut::UnusedBorrowGlobalMutVisitor, use_mul_div::UseMulDivLint,
+        unreachable_code::UnreachableCodeVisitor,
+        unused_borrow_global_mut::UnusedBorrowGlobalMutVisitor,
+        unused_declarations::UnusedDeclarationsVisitor, unused_locals::UnusedLocalsVisitor,
+        use_mul_div::UseMulDivLint, view_function_purity::ViewFunctionPurityVisitor,
+        visibility_overexposure::VisibilityOverexposureVisitor,
     },
     utils::read_config_or_default,
 };
@@ -65,6 +82,11 @@ pub struct Args {
 
     #[clap(short, long, value_enum, default_value_t=LintLevel::Default)]
     pub level: LintLevel,
+
+    /// Previously published version of the package (package directory, directory of `.mv`
+    /// files or single `.mv` file) to check upgrade compatibility against
+    #[clap(long, value_parser)]
+    pub compat_against: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
@@ -79,6 +101,12 @@ pub fn main(args: Args) -> (Vec<Diagnostic<FileId>>, Files<String>) {
     let lint_config = read_config_or_default(&path).unwrap_or_else(|_e| LintConfig::default());
     let env = build::build_ast(Some(path))
         .expect("Failed to initialize environment. Expected a valid path with necessary data.");
+    let mut diags = Vec::new();
+    if let Some(compat_path) = args.compat_against {
+        let published = build::load_published_package(compat_path)
+            .expect("Failed to load the published package to check compatibility against.");
+        compatibility::check_compatibility(&published, &env.0.model, &mut diags);
+    }
 
     let linters = match args.level {
         LintLevel::Default => vec![
@@ -91,10 +119,8 @@ pub fn main(args: Args) -> (Vec<Diagnostic<FileId>>, Files<String>) {
             GetterMethodFieldMatchLint::visitor(),
             IfsSameCondVisitor::visitor(),
             MultiplicationBeforeDivisionVisitor::visitor(),
-            MultiplicationBeforeDivisionVisitor::visitor(),
             RedundantDerefRefVisitor::visitor(),
             ShiftOverflowVisitor::visitor(),
-            ShiftOverflowVisitor::visitor(),
             UnconditionalExitLoopVisitor::visitor(),
             UnmodifiedMutableArgumentLint::visitor(),
             UnnecessaryMutableReferenceLint::visitor(),
@@ -117,6 +143,25 @@ pub fn main(args: Args) -> (Vec<Diagnostic<FileId>>, Files<String>) {
             RandomnessPublicEntry::visitor(),
             EventAttributeAbility::visitor(),
             LikelyComparisonMistake::visitor(),
+            ReentrancyDispatchVisitor::visitor(),
+            InitModuleVisitor::visitor(),
+            ViewFunctionPurityVisitor::visitor(),
+            EventEmissionVisitor::visitor(),
+            UnboundedIterationVisitor::visitor(),
+            ResourceGroupVisitor::visitor(),
+            VisibilityOverexposureVisitor::visitor(),
+            AbortCodeVisitor::visitor(),
+            UnusedDeclarationsVisitor::visitor(),
+            DivisionByZeroVisitor::visitor(),
+            SubtractionUnderflowVisitor::visitor(),
+            CastTruncationVisitor::visitor(),
+            TimestampDependenceVisitor::visitor(),
+            SpotPriceOracleVisitor::visitor(),
+            FrontRunningVisitor::visitor(),
+            DuplicatedCodeVisitor::visitor(),
+            UnusedLocalsVisitor::visitor(),
+            UnreachableCodeVisitor::visitor(),
+            IgnoredReturnValueVisitor::visitor(),
         ],
         LintLevel::All => {
             vec![
@@ -129,10 +174,8 @@ pub fn main(args: Args) -> (Vec<Diagnostic<FileId>>, Files<String>) {
                 GetterMethodFieldMatchLint::visitor(),
                 IfsSameCondVisitor::visitor(),
                 MultiplicationBeforeDivisionVisitor::visitor(),
-                MultiplicationBeforeDivisionVisitor::visitor(),
                 RedundantDerefRefVisitor::visitor(),
                 ShiftOverflowVisitor::visitor(),
-                ShiftOverflowVisitor::visitor(),
                 SortedImportsLint::visitor(),
                 UnconditionalExitLoopVisitor::visitor(),
                 UnmodifiedMutableArgumentLint::visitor(),
@@ -156,11 +199,31 @@ pub fn main(args: Args) -> (Vec<Diagnostic<FileId>>, Files<String>) {
                 RandomnessPublicEntry::visitor(),
                 EventAttributeAbility::visitor(),
                 LikelyComparisonMistake::visitor(),
+                ReentrancyDispatchVisitor::visitor(),
+                InitModuleVisitor::visitor(),
+                ViewFunctionPurityVisitor::visitor(),
+                EventEmissionVisitor::visitor(),
+                UnboundedIterationVisitor::visitor(),
+                ResourceGroupVisitor::visitor(),
+                VisibilityOverexposureVisitor::visitor(),
+                AbortCodeVisitor::visitor(),
+                UnusedDeclarationsVisitor::visitor(),
+                DivisionByZeroVisitor::visitor(),
+                SubtractionUnderflowVisitor::visitor(),
+                CastTruncationVisitor::visitor(),
+                TimestampDependenceVisitor::visitor(),
+                SpotPriceOracleVisitor::visitor(),
+                FrontRunningVisitor::visitor(),
+                DuplicatedCodeVisitor::visitor(),
+                UnusedLocalsVisitor::visitor(),
+                UnreachableCodeVisitor::visitor(),
+                IgnoredReturnValueVisitor::visitor(),
             ]
         },
     };
     let mut manager = VisitorManager::new(linters);
     let files = env.0.model.get_source_files();
     manager.run(env, &lint_config);
-    (manager.diagnostics(), files)
+    diags.extend(manager.diagnostics());
+    (diags, files)
 }
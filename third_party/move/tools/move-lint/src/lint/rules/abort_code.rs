@@ -0,0 +1,202 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Check the hygiene of abort codes, building on the error constant convention of
+//! `constant_naming`. This lint warns about `abort` and `assert!` sites using raw numeric
+//! literals instead of named `E*` constants, `E*` constants of a module sharing the same value,
+//! `E*` constants which are never used, and abort codes not wrapped in a `std::error` category
+//! function when other abort sites of the package use them.
+use super::constant_naming::is_error_constant_name;
+use crate::lint::{
+    utils::{add_diagnostic_and_emit, get_used_constant_names, is_framework_module},
+    visitor::ExpressionAnalysisVisitor,
+};
+use codespan::FileId;
+use codespan_reporting::diagnostic::Diagnostic;
+use move_model::{
+    ast::{ExpData, Operation, Value},
+    model::{GlobalEnv, ModuleEnv, NodeId},
+};
+use std::collections::HashMap;
+
+/// How the code of an abort site is written.
+enum AbortCode {
+    /// A numeric literal, such as `abort 1`.
+    Literal(NodeId),
+    /// A named constant, such as `abort E_NOT_OWNER`.
+    Constant(NodeId),
+    /// A call to a `std::error` category function, such as `error::not_found(E_NOT_OWNER)`.
+    Wrapped,
+    /// Any other expression.
+    Other,
+}
+
+#[derive(Debug)]
+pub struct AbortCodeVisitor {
+    /// Whether any abort site of the package uses a `std::error` category function, computed on
+    /// first use.
+    uses_error_categories: Option<bool>,
+}
+
+impl Default for AbortCodeVisitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AbortCodeVisitor {
+    pub fn new() -> Self {
+        Self {
+            uses_error_categories: None,
+        }
+    }
+
+    pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
+        Box::new(Self::new())
+    }
+
+    /// Named constants are replaced by their value in the AST, so the source text at the
+    /// location of the value tells whether a literal or a constant was written.
+    fn get_value_source(&self, node_id: NodeId, env: &GlobalEnv) -> Option<String> {
+        env.get_source(&env.get_node_loc(node_id))
+            .ok()
+            .map(|source| source.trim().to_string())
+    }
+
+    fn is_literal_source(&self, source: &str) -> bool {
+        source.starts_with(|c: char| c.is_ascii_digit())
+    }
+
+    fn classify_abort_code(&self, code: &ExpData, env: &GlobalEnv) -> AbortCode {
+        match code {
+            ExpData::Value(node_id, Value::Number(_)) => {
+                match self.get_value_source(*node_id, env) {
+                    Some(source) if self.is_literal_source(&source) => AbortCode::Literal(*node_id),
+                    Some(_) => AbortCode::Constant(*node_id),
+                    None => AbortCode::Other,
+                }
+            },
+            ExpData::Call(_, Operation::MoveFunction(mid, _), _)
+                if is_framework_module(&env.get_module(*mid), "error") =>
+            {
+                AbortCode::Wrapped
+            },
+            _ => AbortCode::Other,
+        }
+    }
+
+    /// Collects the abort codes of all abort sites in the module.
+    fn collect_abort_codes(&self, module: &ModuleEnv, env: &GlobalEnv) -> Vec<AbortCode> {
+        let mut codes = vec![];
+        for func_env in module.get_functions() {
+            if let Some(def) = func_env.get_def() {
+                def.visit_pre_post(&mut |post: bool, exp: &ExpData| {
+                    if post {
+                        if let ExpData::Call(_, Operation::Abort, args) = exp {
+                            if let Some(code) = args.first() {
+                                codes.push(self.classify_abort_code(code, env));
+                            }
+                        }
+                    }
+                    true
+                });
+            }
+        }
+        codes
+    }
+
+    /// Returns true if any abort site of the package uses a `std::error` category function.
+    fn package_uses_error_categories(&self, env: &GlobalEnv) -> bool {
+        env.get_target_modules().iter().any(|module| {
+            self.collect_abort_codes(module, env)
+                .iter()
+                .any(|code| matches!(code, AbortCode::Wrapped))
+        })
+    }
+
+    fn check_abort_sites(
+        &self,
+        module: &ModuleEnv,
+        requires_categories: bool,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        for code in self.collect_abort_codes(module, env) {
+            let (node_id, message) = match code {
+                AbortCode::Literal(node_id) => (
+                    node_id,
+                    "Abort code is a raw numeric literal. Use a named `E*` error constant instead.",
+                ),
+                AbortCode::Constant(node_id) if requires_categories => (
+                    node_id,
+                    "Abort code is not wrapped in a `std::error` category function, unlike other abort codes of this package.",
+                ),
+                _ => continue,
+            };
+            add_diagnostic_and_emit(
+                &env.get_node_loc(node_id),
+                message,
+                codespan_reporting::diagnostic::Severity::Warning,
+                env,
+                diags,
+            );
+        }
+    }
+
+    fn check_error_constants(
+        &self,
+        module: &ModuleEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let used = get_used_constant_names(module);
+        let mut names_by_value: HashMap<Value, String> = HashMap::new();
+        for constant in module.get_named_constants() {
+            let name = env.symbol_pool().string(constant.get_name()).to_string();
+            if !is_error_constant_name(&name) {
+                continue;
+            }
+            if let Some(other) = names_by_value.get(&constant.get_value()) {
+                let message = format!(
+                    "Error constant `{}` has the same value as `{}`. Each error should have a distinct code.",
+                    name, other
+                );
+                add_diagnostic_and_emit(
+                    &constant.get_loc(),
+                    &message,
+                    codespan_reporting::diagnostic::Severity::Warning,
+                    env,
+                    diags,
+                );
+            } else {
+                names_by_value.insert(constant.get_value(), name.clone());
+            }
+            if !used.contains(&name) {
+                let message = format!("Error constant `{}` is never used.", name);
+                add_diagnostic_and_emit(
+                    &constant.get_loc(),
+                    &message,
+                    codespan_reporting::diagnostic::Severity::Warning,
+                    env,
+                    diags,
+                );
+            }
+        }
+    }
+}
+
+impl ExpressionAnalysisVisitor for AbortCodeVisitor {
+    fn visit_module(
+        &mut self,
+        module: &ModuleEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        if self.uses_error_categories.is_none() {
+            self.uses_error_categories = Some(self.package_uses_error_categories(env));
+        }
+        let requires_categories = self.uses_error_categories.expect("error category usage");
+        self.check_abort_sites(module, requires_categories, env, diags);
+        self.check_error_constants(module, env, diags);
+    }
+}
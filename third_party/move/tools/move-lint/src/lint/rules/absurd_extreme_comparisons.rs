@@ -1,9 +1,12 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-//! Lint to check for structs with event attribute but does not have drop and store ability.
-use std::collections::BTreeMap;
-
+//! Lint to check for comparisons of an integer against the minimum or maximum value of its type,
+//! such as `x >= 0`, `x < 0`, `MAX_U64 < x` or `x <= 255` for a `u8`, which are always true or
+//! always false. The bounds come from the type of the compared operand, so every width from `u8`
+//! to `u256` is covered, and the extreme value may be a literal, a named constant or a local which
+//! is only ever assigned that literal. Comparisons are checked wherever they appear, including
+//! `if`, `assert!` and `while` conditions.
 use crate::lint::{
     utils::{add_diagnostic_and_emit, LintConfig},
     visitor::ExpressionAnalysisVisitor,
@@ -12,16 +15,14 @@ use codespan::FileId;
 use codespan_reporting::diagnostic::Diagnostic;
 use move_model::{
     ast::{Exp, ExpData, Operation, Pattern, Value},
-    model::{FunctionEnv, GlobalEnv, NodeId, Parameter},
+    model::{FunctionEnv, GlobalEnv},
     symbol::Symbol,
     ty::Type,
 };
-use num::{integer, BigInt, ToPrimitive};
+use num::{BigInt, Zero};
+use std::collections::BTreeMap;
 
-pub struct LikelyComparisonMistake {
-    max_var_list: BTreeMap<Symbol, BigInt>,
-    min_var_list: BTreeMap<Symbol, BigInt>,
-}
+pub struct LikelyComparisonMistake;
 
 impl Default for LikelyComparisonMistake {
     fn default() -> Self {
@@ -31,136 +32,167 @@ impl Default for LikelyComparisonMistake {
 
 impl LikelyComparisonMistake {
     pub fn new() -> Self {
-        Self {
-            max_var_list: BTreeMap::new(),
-            min_var_list: BTreeMap::new(),
-        }
+        Self {}
     }
 
     pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
         Box::new(Self::new())
     }
 
-    fn collect_variable_types(
-        &mut self,
-        exp: &ExpData,
-        env: &GlobalEnv,
-        diags: &mut Vec<Diagnostic<FileId>>,
-    ) {
-        match exp {
-            ExpData::Block(_, Pattern::Var(_, sym), Some(bind_exp), _) => {
-                if let ExpData::Value(_, Value::Number(num)) = bind_exp.as_ref() {
-                    self.classify_variable(sym, num.clone())
+    /// Collects the locals which are only ever assigned one and the same number.
+    fn collect_constant_locals(&self, body: &Exp) -> BTreeMap<Symbol, BigInt> {
+        let mut values: BTreeMap<Symbol, Option<BigInt>> = BTreeMap::new();
+        body.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            if post {
+                let (pattern, value) = match e {
+                    ExpData::Block(_, pattern, Some(value), _) => (pattern, value),
+                    ExpData::Assign(_, pattern, value) => (pattern, value),
+                    _ => return true,
+                };
+                let number = match (pattern, value.as_ref()) {
+                    (Pattern::Var(..), ExpData::Value(_, Value::Number(num))) => Some(num.clone()),
+                    _ => None,
+                };
+                for (_, sym) in pattern.vars() {
+                    let merged = match values.get(&sym) {
+                        Some(previous) if previous != &number => None,
+                        _ => number.clone(),
+                    };
+                    values.insert(sym, merged);
                 }
-            },
+            }
+            true
+        });
+        values
+            .into_iter()
+            .filter_map(|(sym, value)| value.map(|value| (sym, value)))
+            .collect()
+    }
 
-            ExpData::Assign(_, Pattern::Var(_, sym), bind_exp) => {
-                if let ExpData::Value(_, Value::Number(num)) = bind_exp.as_ref() {
-                    self.classify_variable(sym, num.clone())
-                }
-            },
-            ExpData::IfElse(node_id, cond, _, _) => {
-                if let ExpData::Call(_, op, exp_vec) = cond.as_ref() {
-                    self.check_comparison_operations(op, exp_vec, node_id, env, diags);
-                }
+    /// Returns the number an operand always evaluates to, with the way it is written.
+    fn get_constant(
+        &self,
+        exp: &Exp,
+        func_env: &FunctionEnv,
+        constant_locals: &BTreeMap<Symbol, BigInt>,
+    ) -> Option<(BigInt, String)> {
+        let env = func_env.module_env.env;
+        match exp.as_ref() {
+            ExpData::Value(node_id, Value::Number(num)) => {
+                // Named constants are inlined, so look for one written at this location.
+                let source = env
+                    .get_source(&env.get_node_loc(*node_id))
+                    .ok()?
+                    .trim()
+                    .to_string();
+                let is_named = func_env.module_env.get_named_constants().any(|constant| {
+                    constant.get_name().display(env.symbol_pool()).to_string() == source
+                });
+                Some((num.clone(), if is_named { source } else { num.to_string() }))
             },
-            _ => {},
+            ExpData::LocalVar(_, sym) => constant_locals
+                .get(sym)
+                .map(|num| (num.clone(), sym.display(env.symbol_pool()).to_string())),
+            _ => None,
         }
     }
 
-    fn classify_variable(&mut self, sym: &Symbol, value: BigInt) {
-        if let Some(integer_value) = value.to_u128() {
-            let u128_max = u128::MAX as u128;
-            let u128_min = u128::MIN as u128;
-
-            if integer_value == u128_max {
-                self.max_var_list.insert(*sym, value);
-            } else if integer_value == u128_min {
-                self.min_var_list.insert(*sym, value);
-            }
+    /// Returns whether `value op bound` always holds or always fails for every value of the type,
+    /// together with the extreme the bound is.
+    fn evaluate(&self, op: &Operation, bound: &BigInt, max: &BigInt) -> Option<(bool, &str)> {
+        let is_min = bound.is_zero();
+        let is_max = bound == max;
+        match op {
+            Operation::Lt if is_min => Some((false, "minimum")),
+            Operation::Ge if is_min => Some((true, "minimum")),
+            Operation::Gt if is_max => Some((false, "maximum")),
+            Operation::Le if is_max => Some((true, "maximum")),
+            _ => None,
         }
     }
 
-    fn check_comparison_operations(
-        &mut self,
-        op: &Operation,
-        exp_vec: &Vec<Exp>,
-        node_id: &NodeId,
-        env: &GlobalEnv,
-        diags: &mut Vec<Diagnostic<FileId>>,
-    ) {
-        let lhs = exp_vec.get(0);
-        let rhs = exp_vec.get(1);
-        if lhs.is_none() || rhs.is_none() {
-            return;
-        }
-        match (op, lhs.unwrap().as_ref()) {
-            (Operation::Lt, ExpData::Value(_, Value::Number(value))) => {
-                if value == &BigInt::from(u128::MAX) {
-                    let message = "Cannot compare parameter with max value";
-                    add_diagnostic_and_emit(
-                        &env.get_node_loc(*node_id),
-                        message,
-                        codespan_reporting::diagnostic::Severity::Warning,
-                        env,
-                        diags,
-                    );
-                }
-            },
-            (Operation::Gt, ExpData::Value(_, Value::Number(value))) => {
-                if value == &BigInt::from(u128::MIN) {
-                    let message = "Cannot compare parameter with min value";
-                    add_diagnostic_and_emit(
-                        &env.get_node_loc(*node_id),
-                        message,
-                        codespan_reporting::diagnostic::Severity::Warning,
-                        env,
-                        diags,
-                    );
-                }
-            },
-            _ => {},
+    /// Returns the comparison with its operands swapped.
+    fn flip(&self, op: &Operation) -> Operation {
+        match op {
+            Operation::Lt => Operation::Gt,
+            Operation::Le => Operation::Ge,
+            Operation::Gt => Operation::Lt,
+            Operation::Ge => Operation::Le,
+            _ => op.clone(),
         }
+    }
 
-        match (op, rhs.unwrap().as_ref()) {
-            (Operation::Gt, ExpData::Value(_, Value::Number(value))) => {
-                if value == &BigInt::from(u128::MAX) {
-                    let message = "Cannot compare parameter with max value";
-                    add_diagnostic_and_emit(
-                        &env.get_node_loc(*node_id),
-                        message,
-                        codespan_reporting::diagnostic::Severity::Warning,
-                        env,
-                        diags,
-                    );
-                }
-            },
-            (Operation::Lt, ExpData::Value(_, Value::Number(value))) => {
-                if value == &BigInt::from(u128::MIN) {
-                    let message = "Cannot compare parameter with min value";
-                    add_diagnostic_and_emit(
-                        &env.get_node_loc(*node_id),
-                        message,
-                        codespan_reporting::diagnostic::Severity::Warning,
-                        env,
-                        diags,
-                    );
-                }
-            },
-            _ => {},
-        }
+    fn check_comparison(
+        &self,
+        op: &Operation,
+        args: &[Exp],
+        func_env: &FunctionEnv,
+        constant_locals: &BTreeMap<Symbol, BigInt>,
+    ) -> Option<String> {
+        let (lhs, rhs) = match args {
+            [lhs, rhs] => (lhs, rhs),
+            _ => return None,
+        };
+        let lhs_constant = self.get_constant(lhs, func_env, constant_locals);
+        let rhs_constant = self.get_constant(rhs, func_env, constant_locals);
+        // Normalize to `operand op bound`.
+        let (operand, op, (bound, bound_text)) = match (lhs_constant, rhs_constant) {
+            (None, Some(bound)) => (lhs, op.clone(), bound),
+            (Some(bound), None) => (rhs, self.flip(op), bound),
+            _ => return None,
+        };
+        let env = func_env.module_env.env;
+        let ty = env.get_node_type(operand.node_id());
+        let max = match &ty {
+            Type::Primitive(prim) => prim.get_max_value()?,
+            _ => return None,
+        };
+        let (result, extreme) = self.evaluate(&op, &bound, &max)?;
+        Some(format!(
+            "Comparison is always {}: `{}` is the {} value of `{}`.",
+            result,
+            bound_text,
+            extreme,
+            ty.display(&env.get_type_display_ctx())
+        ))
     }
 }
 
 impl ExpressionAnalysisVisitor for LikelyComparisonMistake {
-    fn post_visit_expression(
+    fn visit_function_custom(
         &mut self,
-        exp: &ExpData,
         func_env: &FunctionEnv,
         env: &GlobalEnv,
         _: &LintConfig,
         diags: &mut Vec<Diagnostic<FileId>>,
     ) {
-        self.collect_variable_types(exp, env, diags)
+        let body = match func_env.get_def() {
+            Some(body) => body,
+            None => return,
+        };
+        let constant_locals = self.collect_constant_locals(body);
+        body.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            if post {
+                if let ExpData::Call(
+                    node_id,
+                    op @ (Operation::Lt | Operation::Le | Operation::Gt | Operation::Ge),
+                    args,
+                ) = e
+                {
+                    if let Some(message) =
+                        self.check_comparison(op, args, func_env, &constant_locals)
+                    {
+                        add_diagnostic_and_emit(
+                            &env.get_node_loc(*node_id),
+                            &message,
+                            codespan_reporting::diagnostic::Severity::Warning,
+                            env,
+                            diags,
+                        );
+                    }
+                }
+            }
+            true
+        });
     }
 }
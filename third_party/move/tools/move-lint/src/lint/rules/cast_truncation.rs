@@ -0,0 +1,127 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Detect casts whose operand may not fit into the target type. Move aborts instead of truncating
+//! such values. The lint warns about casts which can never succeed, narrowing casts from a wider
+//! type which are not preceded by a bounds check, and casts of intermediate arithmetic results,
+//! such as `((a * b) as u64)` where `a * b` is computed in `u128`. Operand ranges come from the
+//! interval analysis, which follows constants, locals, arithmetic and guards for every integer
+//! type, so a value checked with `assert!` or `if` against the target maximum is not reported.
+use crate::lint::{
+    interval_analysis::{for_each_instruction_with_intervals, get_cast_type, get_type_max},
+    utils::add_diagnostic_and_emit,
+    visitor::ExpressionAnalysisVisitor,
+};
+use codespan::FileId;
+use codespan_reporting::diagnostic::Diagnostic;
+use move_model::{
+    ast::TempIndex,
+    model::{FunctionEnv, GlobalEnv},
+};
+use move_stackless_bytecode::stackless_bytecode::{Bytecode, Operation};
+use std::collections::BTreeMap;
+
+#[derive(Debug)]
+pub struct CastTruncationVisitor;
+
+impl Default for CastTruncationVisitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CastTruncationVisitor {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
+        Box::new(Self::new())
+    }
+
+    fn get_arithmetic_name(&self, op: &Operation) -> Option<&'static str> {
+        match op {
+            Operation::Add => Some("addition"),
+            Operation::Mul => Some("multiplication"),
+            Operation::Div => Some("division"),
+            Operation::Shl => Some("shift"),
+            _ => None,
+        }
+    }
+}
+
+impl ExpressionAnalysisVisitor for CastTruncationVisitor {
+    fn requires_bytecode_inspection(&self) -> bool {
+        true
+    }
+
+    fn visit_function_with_bytecode(
+        &mut self,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        // Temporaries holding the result of an arithmetic operation, by the name of the operation.
+        let mut arithmetic_results: BTreeMap<TempIndex, &str> = BTreeMap::new();
+        for_each_instruction_with_intervals(func_env, |target, instr, state| {
+            let (attr_id, op, src) = match instr {
+                Bytecode::Call(attr_id, dsts, op, srcs, _) => {
+                    for dst in dsts {
+                        match self.get_arithmetic_name(op) {
+                            Some(name) => arithmetic_results.insert(*dst, name),
+                            None => arithmetic_results.remove(dst),
+                        };
+                    }
+                    match srcs.as_slice() {
+                        [src] => (attr_id, op, *src),
+                        _ => return,
+                    }
+                },
+                _ => {
+                    for dst in instr.dests() {
+                        arithmetic_results.remove(&dst);
+                    }
+                    return;
+                },
+            };
+            let (cast_type, type_max, range) = match get_cast_type(op) {
+                Some(cast_type) => match (get_type_max(&cast_type), state.range(src, target)) {
+                    (Some(type_max), Some(range)) => (cast_type, type_max, range),
+                    _ => return,
+                },
+                None => return,
+            };
+            let type_ctx = env.get_type_display_ctx();
+            let message = if range.lo > type_max {
+                format!(
+                    "Cast truncation detected. The value never fits into `{}`, so this cast always aborts.",
+                    cast_type.display(&type_ctx)
+                )
+            } else if range.hi > type_max {
+                let src_type = target.get_local_type(src).display(&type_ctx);
+                match arithmetic_results.get(&src) {
+                    Some(name) => format!(
+                        "The {} result computed in `{}` may not fit into `{}`. Bound the intermediate result before casting it down.",
+                        name,
+                        src_type,
+                        cast_type.display(&type_ctx)
+                    ),
+                    None => format!(
+                        "Narrowing cast from `{}` to `{}` is not preceded by a bounds check. The cast aborts if the value does not fit.",
+                        src_type,
+                        cast_type.display(&type_ctx)
+                    ),
+                }
+            } else {
+                return;
+            };
+            add_diagnostic_and_emit(
+                &target.get_bytecode_loc(*attr_id),
+                &message,
+                codespan_reporting::diagnostic::Severity::Warning,
+                env,
+                diags,
+            );
+        });
+    }
+}
@@ -3,15 +3,18 @@
 
 //! Comparisons where a value is compared exactly twice with different relational operators
 //! inside a logical OR operation. For example, expressions like `a == b || a < b` or `x != y || x > y`
-//! can potentially be combined to simplify the code.
+//! can potentially be combined to simplify the code. Operands are compared in normalized form, so
+//! `x > y || y != x` is found as well, and conditions with side effects are skipped.
 use crate::lint::{
-    utils::{add_diagnostic_and_emit, LintConfig},
+    utils::{
+        add_diagnostic_and_emit, is_side_effect_free, normalize_exp, LintConfig, NormalizedExp,
+    },
     visitor::ExpressionAnalysisVisitor,
 };
 use codespan::FileId;
 use codespan_reporting::diagnostic::Diagnostic;
 use move_model::{
-    ast::{ExpData, Operation, Value},
+    ast::{ExpData, Operation},
     model::{FunctionEnv, GlobalEnv},
 };
 
@@ -36,90 +39,72 @@ impl CombinableBoolVisitor {
     fn find_combinable_comparison(
         &mut self,
         cond: &ExpData,
+        func_env: &FunctionEnv,
         env: &GlobalEnv,
         diags: &mut Vec<Diagnostic<FileId>>,
     ) {
-        if let ExpData::Call(_, Operation::Or, args) = cond {
-            if let (ExpData::Call(_, op1, args1), ExpData::Call(_, op2, args2)) =
-                (&args[0].as_ref(), &args[1].as_ref())
-            {
-                if args1.len() == 2 && args2.len() == 2 {
-                    let right_operand_1 = &args1[1];
-                    let right_operand_2 = &args2[1];
-                    if !self.is_number(right_operand_1.as_ref())
-                        || !self.is_number(right_operand_2.as_ref())
-                    {
-                        return;
-                    }
-                    if let (
-                        ExpData::Value(_, Value::Number(num1)),
-                        ExpData::Value(_, Value::Number(num2)),
-                    ) = (right_operand_1.as_ref(), right_operand_2.as_ref())
-                    {
-                        if num1 != num2 {
-                            return;
-                        }
-                    }
-                }
-                let left = &mut args[0].used_temporaries();
-                let right = &mut args[1].used_temporaries();
-                // left.sort();
-                // right.sort();
-                if left == right {
-                    let operation_pairs = [
-                        (
-                            (&Operation::Eq, &Operation::Lt),
-                            "Simplify comparison by using <= instead.",
-                        ),
-                        (
-                            (&Operation::Lt, &Operation::Eq),
-                            "Simplify comparison by using <= instead.",
-                        ),
-                        (
-                            (&Operation::Eq, &Operation::Gt),
-                            "Simplify comparison by using >= instead.",
-                        ),
-                        (
-                            (&Operation::Gt, &Operation::Eq),
-                            "Simplify comparison by using >= instead.",
-                        ),
-                        (
-                            (&Operation::Neq, &Operation::Lt),
-                            "Unequal (!=) condition is unnecessary and can be removed",
-                        ),
-                        (
-                            (&Operation::Lt, &Operation::Neq),
-                            "Unequal (!=) condition is unnecessary and can be removed",
-                        ),
-                        (
-                            (&Operation::Neq, &Operation::Gt),
-                            "Unequal (!=) condition is unnecessary and can be removed",
-                        ),
-                        (
-                            (&Operation::Gt, &Operation::Neq),
-                            "Unequal (!=) condition is unnecessary and can be removed",
-                        ),
-                    ];
-                    if let Some(message) = operation_pairs
-                        .iter()
-                        .find(|(pair, _)| pair == &(op1, op2))
-                        .map(|&(_, message)| message)
-                    {
-                        add_diagnostic_and_emit(
-                            &env.get_node_loc(cond.node_id()),
-                            message,
-                            codespan_reporting::diagnostic::Severity::Warning,
-                            env,
-                            diags,
-                        );
-                    }
-                }
-            }
+        let args = match cond {
+            ExpData::Call(_, Operation::Or, args) if args.len() == 2 => args,
+            _ => return,
+        };
+        if !is_side_effect_free(cond, env) {
+            return;
         }
+        let (op1, operands1) = match self.get_comparison(&args[0], func_env) {
+            Some(comparison) => comparison,
+            None => return,
+        };
+        let (op2, operands2) = match self.get_comparison(&args[1], func_env) {
+            Some(comparison) => comparison,
+            None => return,
+        };
+        if operands1 != operands2 {
+            return;
+        }
+        let message = match (op1, op2) {
+            (Operation::Eq, Operation::Lt) | (Operation::Lt, Operation::Eq) => {
+                "Simplify comparison by using <= instead."
+            },
+            (Operation::Eq, Operation::Gt) | (Operation::Gt, Operation::Eq) => {
+                "Simplify comparison by using >= instead."
+            },
+            (Operation::Neq, Operation::Lt | Operation::Gt)
+            | (Operation::Lt | Operation::Gt, Operation::Neq) => {
+                "Unequal (!=) condition is unnecessary and can be removed"
+            },
+            _ => return,
+        };
+        add_diagnostic_and_emit(
+            &env.get_node_loc(cond.node_id()),
+            message,
+            codespan_reporting::diagnostic::Severity::Warning,
+            env,
+            diags,
+        );
     }
 
-    fn is_number(&mut self, exp: &ExpData) -> bool {
-        matches!(exp, ExpData::Value(_, Value::Number(_)))
+    // Returns the operation of a comparison together with its normalized operands, in an order
+    // independent of the way they are written, so `x > y` and `y != x` compare the same values.
+    fn get_comparison<'a>(
+        &self,
+        exp: &'a ExpData,
+        func_env: &FunctionEnv,
+    ) -> Option<(&'a Operation, Vec<NormalizedExp>)> {
+        match exp {
+            ExpData::Call(
+                _,
+                op @ (Operation::Eq | Operation::Neq | Operation::Lt | Operation::Gt),
+                args,
+            ) if args.len() == 2 => {
+                let mut operands: Vec<NormalizedExp> = args
+                    .iter()
+                    .map(|arg| normalize_exp(arg, func_env))
+                    .collect();
+                operands.sort();
+                Some((op, operands))
+            },
+            _ => None,
+        }
     }
 }
 
@@ -127,13 +112,13 @@ impl ExpressionAnalysisVisitor for CombinableBoolVisitor {
     fn post_visit_expression(
         &mut self,
         exp: &ExpData,
-        _func_env: &FunctionEnv,
+        func_env: &FunctionEnv,
         env: &GlobalEnv,
         _: &LintConfig,
         diags: &mut Vec<Diagnostic<FileId>>,
     ) {
         if let ExpData::IfElse(_, cond, _, _) = exp {
-            self.find_combinable_comparison(cond.as_ref(), env, diags);
+            self.find_combinable_comparison(cond.as_ref(), func_env, env, diags);
         }
     }
 }
@@ -70,3 +70,12 @@ fn is_all_caps_snake_case(s: &str) -> bool {
     let has_letters = s.chars().any(char::is_alphabetic);
     is_upper_snake_case && has_letters
 }
+
+/// Checks if the given constant name follows the error code convention, such as `E_NOT_OWNER`
+/// or `ENOT_OWNER`.
+pub fn is_error_constant_name(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next() == Some('E')
+        && chars.next().map_or(false, |c| c.is_uppercase() || c == '_')
+        && is_all_caps_snake_case(s)
+}
@@ -0,0 +1,168 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Detect divisions and modulo operations whose divisor may be zero. Divisor ranges come from the
+//! interval analysis, which starts from literals and named constants, propagates them through
+//! locals and arithmetic and narrows them with the conditions of `if`, `while`, `&&`, `||` and
+//! `assert!`. A `/` or `%` is reported when the range of its divisor still includes zero. Divisors
+//! read from fields or returned by calls are evaluated again for the division, so a guard on the
+//! same expression earlier in the function, like `assert!(pool.total_shares > 0, ..)`, also
+//! counts.
+use crate::lint::{
+    interval_analysis::{for_each_instruction_with_intervals, get_type_max},
+    utils::add_diagnostic_and_emit,
+    visitor::ExpressionAnalysisVisitor,
+};
+use codespan::FileId;
+use codespan_reporting::diagnostic::Diagnostic;
+use move_model::{
+    ast::TempIndex,
+    model::{FunctionEnv, GlobalEnv, Loc},
+};
+use move_stackless_bytecode::{
+    function_target::FunctionTarget,
+    stackless_bytecode::{Bytecode, Operation},
+};
+use num::Zero;
+use std::collections::BTreeMap;
+
+#[derive(Debug)]
+pub struct DivisionByZeroVisitor;
+
+impl Default for DivisionByZeroVisitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DivisionByZeroVisitor {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
+        Box::new(Self::new())
+    }
+
+    /// Collects the integer temporaries of the function by the source of the code computing
+    /// them, so that a guard on an expression can be found for another evaluation of it.
+    fn collect_temps_by_source(
+        &self,
+        target: &FunctionTarget,
+        env: &GlobalEnv,
+    ) -> BTreeMap<String, Vec<TempIndex>> {
+        let mut temps: BTreeMap<String, Vec<TempIndex>> = BTreeMap::new();
+        for instr in target.get_bytecode() {
+            let loc = target.get_bytecode_loc(instr.get_attr_id());
+            let source = match env.get_source(&loc) {
+                Ok(source) => source.trim().to_string(),
+                Err(_) => continue,
+            };
+            for dest in instr.dests() {
+                if get_type_max(target.get_local_type(dest)).is_some() {
+                    temps.entry(source.clone()).or_default().push(dest);
+                }
+            }
+        }
+        temps
+    }
+
+    /// Returns the location of the code computing the divisor within the operation, if any.
+    fn get_divisor_loc(
+        &self,
+        target: &FunctionTarget,
+        divisor: TempIndex,
+        operation_loc: &Loc,
+    ) -> Option<Loc> {
+        target
+            .get_bytecode()
+            .iter()
+            .filter(|instr| instr.dests().contains(&divisor))
+            .map(|instr| target.get_bytecode_loc(instr.get_attr_id()))
+            .filter(|loc| loc != operation_loc && operation_loc.is_enclosing(loc))
+            .last()
+    }
+}
+
+impl ExpressionAnalysisVisitor for DivisionByZeroVisitor {
+    fn requires_bytecode_inspection(&self) -> bool {
+        true
+    }
+
+    fn visit_function_with_bytecode(
+        &mut self,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let mut temps_by_source: Option<BTreeMap<String, Vec<TempIndex>>> = None;
+        for_each_instruction_with_intervals(func_env, |target, instr, state| {
+            let (attr_id, op, divisor) = match instr {
+                Bytecode::Call(attr_id, _, op @ (Operation::Div | Operation::Mod), srcs, _) => {
+                    match srcs.as_slice() {
+                        [_, divisor] => (attr_id, op, *divisor),
+                        _ => return,
+                    }
+                },
+                _ => return,
+            };
+            let interval = match state.range(divisor, target) {
+                Some(interval) => interval,
+                None => return,
+            };
+            if !interval.lo.is_zero() {
+                return;
+            }
+            let operation = if matches!(op, Operation::Div) {
+                "Division"
+            } else {
+                "Modulo"
+            };
+            let loc = target.get_bytecode_loc(*attr_id);
+            let divisor_source = self
+                .get_divisor_loc(target, divisor, &loc)
+                .and_then(|divisor_loc| env.get_source(&divisor_loc).ok())
+                .map(|source| source.trim().to_string());
+            // A field or call result is evaluated again for the division, so a guard like
+            // `assert!(pool.total_shares > 0, ..)` bounds another temporary of the same source.
+            if let Some(source) = &divisor_source {
+                let temps_by_source = temps_by_source
+                    .get_or_insert_with(|| self.collect_temps_by_source(target, env));
+                let is_guarded = temps_by_source.get(source).map_or(false, |temps| {
+                    temps.iter().any(|temp| {
+                        *temp != divisor
+                            && state.get(*temp).map_or(false, |range| !range.lo.is_zero())
+                    })
+                });
+                if is_guarded {
+                    return;
+                }
+            }
+            let (divisor_name, guard) = match &divisor_source {
+                Some(source) => (
+                    format!("`{}`", source),
+                    format!("`assert!({} != 0, ...)`", source),
+                ),
+                None => ("a divisor".to_string(), "an `assert!`".to_string()),
+            };
+            let message = if interval.hi.is_zero() {
+                format!(
+                    "{} by {}, which is always zero. This operation always aborts.",
+                    operation, divisor_name
+                )
+            } else {
+                format!(
+                    "{} by {}, which may be zero. Guard it with {} or an `if` check.",
+                    operation, divisor_name, guard
+                )
+            };
+            add_diagnostic_and_emit(
+                &loc,
+                &message,
+                codespan_reporting::diagnostic::Severity::Warning,
+                env,
+                diags,
+            );
+        });
+    }
+}
@@ -0,0 +1,212 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Lint to detect duplicated code: `if`/`else` whose two branches are identical, `if`/`else if`
+//! chains with several branches sharing the same body, and functions of the package whose bodies
+//! are near duplicates of each other. Branches are compared in normalized form. Function bodies are
+//! compared by a fingerprint of their expression tree which ignores the names of parameters and
+//! locals and the values of literals, so functions differing only in those are found as well.
+//! Both the duplicate and the code it duplicates are labelled.
+use crate::lint::{
+    utils::{add_diagnostic_with_labels_and_emit, normalize_exp, LintConfig, NormalizedExp},
+    visitor::ExpressionAnalysisVisitor,
+};
+use codespan::FileId;
+use codespan_reporting::diagnostic::Diagnostic;
+use move_model::{
+    ast::{Exp, ExpData, Operation},
+    model::{FunctionEnv, GlobalEnv, Loc, NodeId},
+    symbol::Symbol,
+};
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet},
+    hash::{Hash, Hasher},
+    mem,
+};
+
+/// Function bodies with fewer expression nodes are too small to be worth deduplicating.
+const MIN_FUNCTION_SIZE: usize = 20;
+
+#[derive(Default)]
+pub struct DuplicatedCodeVisitor {
+    /// Fingerprints of the function bodies seen so far, with the name and location of the first
+    /// function having that fingerprint.
+    functions: BTreeMap<u64, (String, Loc)>,
+}
+
+impl DuplicatedCodeVisitor {
+    pub fn new() -> Self {
+        Self {
+            functions: BTreeMap::new(),
+        }
+    }
+
+    pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
+        Box::new(Self::new())
+    }
+
+    /// Checks the branches of the `if`/`else if` chain starting at `exp` for identical bodies and
+    /// records the nested `if`s of the chain, so the rest of the chain is not checked again.
+    fn check_branches(
+        &self,
+        exp: &ExpData,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        chained: &mut BTreeSet<NodeId>,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        if let ExpData::IfElse(_, cond, then, els) = exp {
+            // `if (c) c else c` is reported by the meaningless operations check.
+            let cond = normalize_exp(cond, func_env);
+            if normalize_exp(then, func_env) == cond && normalize_exp(els, func_env) == cond {
+                return;
+            }
+        }
+        let mut branches: Vec<&Exp> = Vec::new();
+        let mut current = exp;
+        while let ExpData::IfElse(_, _, then, els) = current {
+            branches.push(then);
+            current = els.as_ref();
+            match current {
+                ExpData::IfElse(node_id, ..) => {
+                    chained.insert(*node_id);
+                },
+                _ => branches.push(els),
+            }
+        }
+        let message = if branches.len() == 2 {
+            "Detected if/else with identical branches. Consider removing the condition."
+        } else {
+            "Detected identical branches in an if/else if chain. Consider combining their conditions."
+        };
+        let mut seen: Vec<(NormalizedExp, &Exp)> = Vec::new();
+        for branch in branches {
+            if self.is_empty(branch) {
+                continue;
+            }
+            let normalized = normalize_exp(branch, func_env);
+            match seen.iter().find(|(other, _)| other == &normalized) {
+                Some((_, first)) => add_diagnostic_with_labels_and_emit(
+                    &env.get_node_loc(branch.node_id()),
+                    message,
+                    &[(
+                        env.get_node_loc(first.node_id()),
+                        "identical to this branch".to_string(),
+                    )],
+                    codespan_reporting::diagnostic::Severity::Warning,
+                    env,
+                    diags,
+                ),
+                None => seen.push((normalized, branch)),
+            }
+        }
+    }
+
+    fn is_empty(&self, exp: &ExpData) -> bool {
+        match exp {
+            ExpData::Sequence(_, exps) => exps.is_empty(),
+            ExpData::Call(_, Operation::Tuple, args) => args.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Computes a fingerprint of the expression tree which ignores the names of parameters and
+    /// locals and the values of literals, together with the number of nodes in the tree.
+    fn fingerprint(&self, body: &ExpData) -> (u64, usize) {
+        let mut hasher = DefaultHasher::new();
+        let mut locals: BTreeMap<Symbol, usize> = BTreeMap::new();
+        let mut size = 0;
+        body.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            if post {
+                ')'.hash(&mut hasher);
+                return true;
+            }
+            size += 1;
+            mem::discriminant(e).hash(&mut hasher);
+            // Locals are numbered in the order they appear in.
+            let mut hash_local = |sym: Symbol, hasher: &mut DefaultHasher| {
+                let next = locals.len();
+                locals.entry(sym).or_insert(next).hash(hasher);
+            };
+            match e {
+                ExpData::LocalVar(_, sym) => hash_local(*sym, &mut hasher),
+                ExpData::Temporary(_, idx) => idx.hash(&mut hasher),
+                ExpData::Call(_, op, _) => format!("{:?}", op).hash(&mut hasher),
+                ExpData::Block(_, pattern, ..)
+                | ExpData::Assign(_, pattern, _)
+                | ExpData::Lambda(_, pattern, _) => {
+                    for (_, sym) in pattern.vars() {
+                        hash_local(sym, &mut hasher);
+                    }
+                },
+                ExpData::LoopCont(_, is_continue) => is_continue.hash(&mut hasher),
+                _ => {},
+            }
+            true
+        });
+        (hasher.finish(), size)
+    }
+
+    /// Checks whether the body of the function duplicates the body of a function seen before.
+    fn check_function(
+        &mut self,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let body = match func_env.get_def() {
+            Some(body) if !func_env.is_test_only() => body,
+            _ => return,
+        };
+        let (fingerprint, size) = self.fingerprint(body);
+        if size < MIN_FUNCTION_SIZE {
+            return;
+        }
+        let name = func_env.get_full_name_str();
+        match self.functions.get(&fingerprint) {
+            Some((first_name, first_loc)) => {
+                let message = format!(
+                    "Function `{}` is a near duplicate of `{}`. Consider extracting the shared code into one function.",
+                    name, first_name
+                );
+                add_diagnostic_with_labels_and_emit(
+                    &func_env.get_id_loc(),
+                    &message,
+                    &[(first_loc.clone(), format!("`{}` defined here", first_name))],
+                    codespan_reporting::diagnostic::Severity::Warning,
+                    env,
+                    diags,
+                );
+            },
+            None => {
+                self.functions
+                    .insert(fingerprint, (name, func_env.get_id_loc()));
+            },
+        }
+    }
+}
+
+impl ExpressionAnalysisVisitor for DuplicatedCodeVisitor {
+    fn visit_function_custom(
+        &mut self,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        _: &LintConfig,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        self.check_function(func_env, env, diags);
+        let body = match func_env.get_def() {
+            Some(body) => body,
+            None => return,
+        };
+        let mut chained = BTreeSet::new();
+        body.visit_pre_post(&mut |post: bool, exp: &ExpData| {
+            if let ExpData::IfElse(node_id, ..) = exp {
+                if !post && !chained.contains(node_id) {
+                    self.check_branches(exp, func_env, env, &mut chained, diags);
+                }
+            }
+            true
+        });
+    }
+}
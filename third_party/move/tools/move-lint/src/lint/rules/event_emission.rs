@@ -0,0 +1,275 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Check that events are emitted consistently, complementing `event_attribute_ability`. Similar to
+//! the framework's `check_emit_event_call`, this lint warns about `event::emit` calls whose type
+//! is not a struct with the `#[event]` attribute defined in the same module. It also warns about
+//! `#[event]` structs that are never emitted anywhere in the package, about uses of the deprecated
+//! `EventHandle`/`emit_event` API, and about entry functions that modify global state without
+//! emitting any event.
+use crate::lint::{
+    utils::{
+        add_diagnostic_and_emit, get_package_call_closure, is_framework_function,
+        is_framework_struct_type,
+    },
+    visitor::ExpressionAnalysisVisitor,
+};
+use codespan::FileId;
+use codespan_reporting::diagnostic::Diagnostic;
+use move_model::{
+    ast::{ExpData, Operation},
+    model::{FunctionEnv, GlobalEnv, ModuleEnv, QualifiedId, StructEnv, StructId},
+    ty::{ReferenceKind, Type},
+};
+use std::collections::BTreeSet;
+
+const EVENT_ATTRIBUTE: &str = "event";
+
+#[derive(Debug)]
+pub struct EventEmissionVisitor {
+    /// Struct types emitted through `event::emit` anywhere in the package, collected on first
+    /// use.
+    emitted: Option<BTreeSet<QualifiedId<StructId>>>,
+}
+
+impl Default for EventEmissionVisitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventEmissionVisitor {
+    pub fn new() -> Self {
+        Self { emitted: None }
+    }
+
+    pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
+        Box::new(Self::new())
+    }
+
+    fn has_event_attribute(&self, struct_env: &StructEnv) -> bool {
+        struct_env.has_attribute(|attr| {
+            struct_env.symbol_pool().string(attr.name()).as_str() == EVENT_ATTRIBUTE
+        })
+    }
+
+    /// Checks `event::emit` and `event::emit_event` call sites in the module.
+    fn check_emit_calls(
+        &self,
+        module: &ModuleEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        for func_env in module.get_functions() {
+            if let Some(def) = func_env.get_def() {
+                def.visit_pre_post(&mut |post: bool, exp: &ExpData| {
+                    if post {
+                        if let ExpData::Call(node_id, Operation::MoveFunction(mid, fid), _) = exp {
+                            let callee = env.get_function(mid.qualified(*fid));
+                            if is_framework_function(&callee, "event", &["emit"]) {
+                                let inst = env.get_node_instantiation(*node_id);
+                                if let Some(event_type) = inst.first() {
+                                    if !self.is_event_struct_of_module(event_type, module, env) {
+                                        let message = format!(
+                                            "`event::emit` called with type `{}` which is not a struct defined in this module with the `#[event]` attribute.",
+                                            event_type.display(&env.get_type_display_ctx())
+                                        );
+                                        add_diagnostic_and_emit(
+                                            &env.get_node_loc(*node_id),
+                                            &message,
+                                            codespan_reporting::diagnostic::Severity::Warning,
+                                            env,
+                                            diags,
+                                        );
+                                    }
+                                }
+                            } else if is_framework_function(&callee, "event", &["emit_event"]) {
+                                let message = "`event::emit_event` with an `EventHandle` is deprecated. Use `event::emit` with an `#[event]` struct instead.";
+                                add_diagnostic_and_emit(
+                                    &env.get_node_loc(*node_id),
+                                    message,
+                                    codespan_reporting::diagnostic::Severity::Warning,
+                                    env,
+                                    diags,
+                                );
+                            }
+                        }
+                    }
+                    true
+                });
+            }
+        }
+    }
+
+    fn is_event_struct_of_module(&self, ty: &Type, module: &ModuleEnv, env: &GlobalEnv) -> bool {
+        match ty {
+            Type::Struct(mid, sid, _) => {
+                *mid == module.get_id()
+                    && self.has_event_attribute(&env.get_struct(mid.qualified(*sid)))
+            },
+            _ => false,
+        }
+    }
+
+    /// Warns about struct fields using the deprecated `EventHandle` type.
+    fn check_event_handle_fields(
+        &self,
+        module: &ModuleEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        for struct_env in module.get_structs() {
+            for field_env in struct_env.get_fields() {
+                if is_framework_struct_type(&field_env.get_type(), env, "event", "EventHandle") {
+                    let message = format!(
+                        "Field `{}` uses the deprecated `EventHandle`. Use `event::emit` with an `#[event]` struct instead.",
+                        field_env.get_name().display(env.symbol_pool())
+                    );
+                    add_diagnostic_and_emit(
+                        field_env.get_loc(),
+                        &message,
+                        codespan_reporting::diagnostic::Severity::Warning,
+                        env,
+                        diags,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Collects the struct types emitted through `event::emit` anywhere in the package.
+    fn collect_emitted_structs(&self, env: &GlobalEnv) -> BTreeSet<QualifiedId<StructId>> {
+        let mut emitted = BTreeSet::new();
+        for module in env.get_target_modules() {
+            for func_env in module.get_functions() {
+                if let Some(def) = func_env.get_def() {
+                    def.visit_pre_post(&mut |post: bool, exp: &ExpData| {
+                        if post {
+                            if let ExpData::Call(node_id, Operation::MoveFunction(mid, fid), _) =
+                                exp
+                            {
+                                let callee = env.get_function(mid.qualified(*fid));
+                                if is_framework_function(&callee, "event", &["emit"]) {
+                                    if let Some(Type::Struct(emid, esid, _)) =
+                                        env.get_node_instantiation(*node_id).first()
+                                    {
+                                        emitted.insert(emid.qualified(*esid));
+                                    }
+                                }
+                            }
+                        }
+                        true
+                    });
+                }
+            }
+        }
+        emitted
+    }
+
+    /// Warns about `#[event]` structs which are never emitted in the package.
+    fn check_unused_events(
+        &self,
+        module: &ModuleEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let emitted = self.emitted.as_ref().expect("emitted structs");
+        for struct_env in module.get_structs() {
+            if self.has_event_attribute(&struct_env)
+                && !struct_env.is_test_only()
+                && !emitted.contains(&struct_env.get_qualified_id())
+            {
+                let message = format!(
+                    "Event struct `{}` is never emitted in this package.",
+                    struct_env.get_name().display(env.symbol_pool())
+                );
+                add_diagnostic_and_emit(
+                    &struct_env.get_loc(),
+                    &message,
+                    codespan_reporting::diagnostic::Severity::Warning,
+                    env,
+                    diags,
+                );
+            }
+        }
+    }
+
+    /// Warns about entry functions which modify global state but never emit an event.
+    fn check_silent_entry_functions(
+        &self,
+        module: &ModuleEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        for func_env in module.get_functions() {
+            if !func_env.is_entry() || func_env.is_test_only() {
+                continue;
+            }
+            let (modifies_state, emits_event) = self.get_state_and_event_usage(&func_env, env);
+            if modifies_state && !emits_event {
+                let message = format!(
+                    "Entry function `{}` modifies global state but does not emit any event.",
+                    func_env.get_name_str()
+                );
+                add_diagnostic_and_emit(
+                    &func_env.get_id_loc(),
+                    &message,
+                    codespan_reporting::diagnostic::Severity::Warning,
+                    env,
+                    diags,
+                );
+            }
+        }
+    }
+
+    /// Returns whether the function, or any package function it calls, modifies global state
+    /// and whether it emits an event.
+    fn get_state_and_event_usage(&self, func_env: &FunctionEnv, env: &GlobalEnv) -> (bool, bool) {
+        let mut modifies_state = false;
+        let mut emits_event = false;
+        for fun_id in get_package_call_closure(func_env) {
+            if let Some(def) = env.get_function(fun_id).get_def() {
+                def.visit_pre_post(&mut |post: bool, exp: &ExpData| {
+                    if post {
+                        match exp {
+                            ExpData::Call(_, Operation::MoveTo, _)
+                            | ExpData::Call(_, Operation::MoveFrom, _)
+                            | ExpData::Call(
+                                _,
+                                Operation::BorrowGlobal(ReferenceKind::Mutable),
+                                _,
+                            ) => modifies_state = true,
+                            ExpData::Call(_, Operation::MoveFunction(mid, fid), _) => {
+                                let callee = env.get_function(mid.qualified(*fid));
+                                if is_framework_function(&callee, "event", &["emit", "emit_event"])
+                                {
+                                    emits_event = true;
+                                }
+                            },
+                            _ => {},
+                        }
+                    }
+                    true
+                });
+            }
+        }
+        (modifies_state, emits_event)
+    }
+}
+
+impl ExpressionAnalysisVisitor for EventEmissionVisitor {
+    fn visit_module(
+        &mut self,
+        module: &ModuleEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        self.check_emit_calls(module, env, diags);
+        self.check_event_handle_fields(module, env, diags);
+        if self.emitted.is_none() {
+            self.emitted = Some(self.collect_emitted_structs(env));
+        }
+        self.check_unused_events(module, env, diags);
+        self.check_silent_entry_functions(module, env, diags);
+    }
+}
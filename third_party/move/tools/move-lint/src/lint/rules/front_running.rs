@@ -0,0 +1,363 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Detect logic whose outcome depends on transaction ordering. Two patterns are reported:
+//! entry functions which check a global resource, e.g. `if (!exists<Claimed>(addr))`, and then
+//! write that same resource and move funds under the check, so the first caller wins and
+//! anyone watching pending transactions can front-run it; and public or entry functions which
+//! accept a minimum output or slippage parameter, e.g. `min_amount_out`, but never compare
+//! anything against it, leaving callers without the protection they asked for.
+use super::timestamp_dependence::{is_fund_moving_function, moves_funds};
+use crate::lint::{
+    utils::{add_diagnostic_and_emit, get_package_call_closure, LintConfig},
+    visitor::ExpressionAnalysisVisitor,
+};
+use codespan::FileId;
+use codespan_reporting::diagnostic::Diagnostic;
+use move_model::{
+    ast::{Exp, ExpData, Operation, TempIndex},
+    model::{FunctionEnv, GlobalEnv, NodeId, QualifiedId, StructId, Visibility},
+    symbol::Symbol,
+    ty::ReferenceKind,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+const SLIPPAGE_MARKER: &str = "slippage";
+const MIN_MARKER: &str = "min";
+const OUTPUT_MARKERS: [&str; 3] = ["out", "amount", "receive"];
+
+#[derive(Debug)]
+pub struct FrontRunningVisitor;
+
+impl Default for FrontRunningVisitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrontRunningVisitor {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
+        Box::new(Self::new())
+    }
+
+    /// Returns the resource a global storage operation works on.
+    fn get_resource(&self, node_id: NodeId, env: &GlobalEnv) -> QualifiedId<StructId> {
+        let (mid, sid, _) = env.get_node_instantiation(node_id)[0].require_struct();
+        mid.qualified(sid)
+    }
+
+    /// Collects the resources an expression reads, directly or through locals.
+    fn collect_reads(
+        &self,
+        exp: &Exp,
+        tainted: &BTreeMap<Symbol, BTreeSet<QualifiedId<StructId>>>,
+        env: &GlobalEnv,
+    ) -> BTreeSet<QualifiedId<StructId>> {
+        let mut reads = BTreeSet::new();
+        exp.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            if post {
+                match e {
+                    ExpData::Call(
+                        node_id,
+                        Operation::Exists(_) | Operation::BorrowGlobal(_),
+                        _,
+                    ) => {
+                        reads.insert(self.get_resource(*node_id, env));
+                    },
+                    ExpData::LocalVar(_, sym) => {
+                        if let Some(resources) = tainted.get(sym) {
+                            reads.extend(resources.iter().cloned());
+                        }
+                    },
+                    _ => {},
+                }
+            }
+            true
+        });
+        reads
+    }
+
+    /// Collects the locals holding values read from global storage, with the resources read.
+    fn collect_tainted_locals(
+        &self,
+        body: &Exp,
+        env: &GlobalEnv,
+    ) -> BTreeMap<Symbol, BTreeSet<QualifiedId<StructId>>> {
+        let mut tainted: BTreeMap<Symbol, BTreeSet<QualifiedId<StructId>>> = BTreeMap::new();
+        loop {
+            let mut changed = false;
+            let mut updates = vec![];
+            body.visit_pre_post(&mut |post: bool, e: &ExpData| {
+                if post {
+                    if let ExpData::Block(_, pattern, Some(value), _)
+                    | ExpData::Assign(_, pattern, value) = e
+                    {
+                        let reads = self.collect_reads(value, &tainted, env);
+                        if !reads.is_empty() {
+                            for (_, sym) in pattern.vars() {
+                                updates.push((sym, reads.clone()));
+                            }
+                        }
+                    }
+                }
+                true
+            });
+            for (sym, reads) in updates {
+                let entry = tainted.entry(sym).or_default();
+                let len = entry.len();
+                entry.extend(reads);
+                changed |= entry.len() != len;
+            }
+            if !changed {
+                return tainted;
+            }
+        }
+    }
+
+    /// Returns true if an expression always aborts.
+    fn is_abort(&self, exp: &ExpData) -> bool {
+        match exp {
+            ExpData::Call(_, Operation::Abort, _) => true,
+            ExpData::Sequence(_, items) => items.last().map_or(false, |last| self.is_abort(last)),
+            _ => false,
+        }
+    }
+
+    /// Returns true if a function writes the given resource, directly or through package functions.
+    fn function_writes(
+        &self,
+        func_env: &FunctionEnv,
+        resource: QualifiedId<StructId>,
+        env: &GlobalEnv,
+    ) -> bool {
+        get_package_call_closure(func_env)
+            .into_iter()
+            .any(|fun_id| {
+                env.get_function(fun_id)
+                    .get_def()
+                    .map_or(false, |def| self.writes_directly(def, resource, env))
+            })
+    }
+
+    fn writes_directly(&self, exp: &Exp, resource: QualifiedId<StructId>, env: &GlobalEnv) -> bool {
+        let mut found = false;
+        exp.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            if post && !found {
+                if let ExpData::Call(
+                    node_id,
+                    Operation::MoveTo
+                    | Operation::MoveFrom
+                    | Operation::BorrowGlobal(ReferenceKind::Mutable),
+                    _,
+                ) = e
+                {
+                    found = self.get_resource(*node_id, env) == resource;
+                }
+            }
+            true
+        });
+        found
+    }
+
+    /// Returns true if the guarded code writes the resource and moves funds.
+    fn is_first_come_write(
+        &self,
+        guarded: &[&Exp],
+        resource: QualifiedId<StructId>,
+        env: &GlobalEnv,
+    ) -> bool {
+        let mut writes = false;
+        let mut pays = false;
+        for exp in guarded {
+            writes |= self.writes_directly(exp, resource, env);
+            exp.visit_pre_post(&mut |post: bool, e: &ExpData| {
+                if post {
+                    if let ExpData::Call(_, Operation::MoveFunction(mid, fid), _) = e {
+                        let callee = env.get_function(mid.qualified(*fid));
+                        if callee.module_env.is_target() {
+                            writes = writes || self.function_writes(&callee, resource, env);
+                            pays = pays || moves_funds(&callee);
+                        } else {
+                            pays = pays || is_fund_moving_function(&callee);
+                        }
+                    }
+                }
+                true
+            });
+        }
+        writes && pays
+    }
+
+    fn check_first_come(&self, body: &Exp, env: &GlobalEnv, diags: &mut Vec<Diagnostic<FileId>>) {
+        let tainted = self.collect_tainted_locals(body, env);
+        body.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            if post {
+                match e {
+                    ExpData::IfElse(_, cond, then, els) => {
+                        if !self.is_abort(then) && !self.is_abort(els) {
+                            self.check_guarded(cond, &[then, els], &tainted, env, diags);
+                        }
+                    },
+                    ExpData::Sequence(_, items) => {
+                        // The rest of the sequence is only reached if an `assert!` holds.
+                        for (i, item) in items.iter().enumerate() {
+                            if let ExpData::IfElse(_, cond, then, els) = item.as_ref() {
+                                if self.is_abort(then) || self.is_abort(els) {
+                                    let rest: Vec<&Exp> = items[i + 1..].iter().collect();
+                                    self.check_guarded(cond, &rest, &tainted, env, diags);
+                                }
+                            }
+                        }
+                    },
+                    _ => {},
+                }
+            }
+            true
+        });
+    }
+
+    /// Reports a condition on a resource whose guarded code writes that resource and moves funds.
+    fn check_guarded(
+        &self,
+        cond: &Exp,
+        guarded: &[&Exp],
+        tainted: &BTreeMap<Symbol, BTreeSet<QualifiedId<StructId>>>,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        if let Some(resource) = self
+            .collect_reads(cond, tainted, env)
+            .into_iter()
+            .find(|resource| self.is_first_come_write(guarded, *resource, env))
+        {
+            let message = format!(
+                "First-come logic on `{}` in an entry function that moves funds. Whoever is included first wins, so pending transactions can be front-run; consider per-user allocations or a commit-reveal scheme.",
+                env.get_struct(resource).get_full_name_str()
+            );
+            add_diagnostic_and_emit(
+                &env.get_node_loc(cond.node_id()),
+                &message,
+                codespan_reporting::diagnostic::Severity::Warning,
+                env,
+                diags,
+            );
+        }
+    }
+
+    /// Returns true if a parameter name suggests a minimum output or slippage bound.
+    fn is_slippage_name(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        name.contains(SLIPPAGE_MARKER)
+            || (name.contains(MIN_MARKER) && OUTPUT_MARKERS.iter().any(|m| name.contains(m)))
+    }
+
+    /// Returns true if the parameter, or a local derived from it, is compared or passed on.
+    fn is_bound_checked(&self, body: &Exp, param: TempIndex) -> bool {
+        let mut derived = BTreeSet::new();
+        loop {
+            let mut updates = vec![];
+            body.visit_pre_post(&mut |post: bool, e: &ExpData| {
+                if post {
+                    if let ExpData::Block(_, pattern, Some(value), _)
+                    | ExpData::Assign(_, pattern, value) = e
+                    {
+                        if self.mentions(value, param, &derived) {
+                            updates.extend(
+                                pattern
+                                    .vars()
+                                    .into_iter()
+                                    .map(|(_, sym)| sym)
+                                    .filter(|sym| !derived.contains(sym)),
+                            );
+                        }
+                    }
+                }
+                true
+            });
+            if updates.is_empty() {
+                break;
+            }
+            derived.extend(updates);
+        }
+        let mut checked = false;
+        body.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            if post && !checked {
+                if let ExpData::Call(
+                    _,
+                    Operation::Lt
+                    | Operation::Le
+                    | Operation::Gt
+                    | Operation::Ge
+                    | Operation::Eq
+                    | Operation::Neq
+                    | Operation::MoveFunction(..),
+                    args,
+                ) = e
+                {
+                    checked = args.iter().any(|arg| self.mentions(arg, param, &derived));
+                }
+            }
+            true
+        });
+        checked
+    }
+
+    fn mentions(&self, exp: &Exp, param: TempIndex, derived: &BTreeSet<Symbol>) -> bool {
+        exp.used_temporaries().contains(&param)
+            || exp.free_vars().iter().any(|sym| derived.contains(sym))
+    }
+
+    fn check_slippage(
+        &self,
+        func_env: &FunctionEnv,
+        body: &Exp,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        for (idx, param) in func_env.get_parameters().iter().enumerate() {
+            let name = env.symbol_pool().string(param.0);
+            if !param.1.is_number()
+                || !self.is_slippage_name(&name)
+                || self.is_bound_checked(body, idx)
+            {
+                continue;
+            }
+            let message = format!(
+                "Parameter `{}` looks like a minimum output or slippage bound but the output is never compared against it. Callers are left unprotected against front-running and price movement.",
+                name
+            );
+            add_diagnostic_and_emit(
+                &param.2,
+                &message,
+                codespan_reporting::diagnostic::Severity::Warning,
+                env,
+                diags,
+            );
+        }
+    }
+}
+
+impl ExpressionAnalysisVisitor for FrontRunningVisitor {
+    fn visit_function_custom(
+        &mut self,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        _: &LintConfig,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let body = match func_env.get_def() {
+            Some(body) if !func_env.is_test_only() => body,
+            _ => return,
+        };
+        if func_env.is_entry() {
+            self.check_first_come(body, env, diags);
+        }
+        if func_env.is_entry() || func_env.visibility() == Visibility::Public {
+            self.check_slippage(func_env, body, env, diags);
+        }
+    }
+}
@@ -1,20 +1,46 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-//! Detect consecutive 'if' statements with identical conditions are usually redundant and can be
-//! refactored to improve code readability and maintainability.
+//! Detect `if` conditions which repeat an earlier condition and are therefore redundant: an
+//! `else if` whose condition already appeared earlier in the chain, an `if` nested in the `then`
+//! branch of an `if` with the same condition, or an `if` following another `if` with the same
+//! condition, when nothing in between, including the first `if`, can change the value of the
+//! condition. Conditions are compared in normalized form, so `a < b` and `b > a` are the same,
+//! and conditions calling functions with side effects are never reported.
 use crate::lint::{
-    utils::{add_diagnostic_and_emit, get_var_info_from_func_param, LintConfig},
+    utils::{
+        add_diagnostic_and_emit, get_sequence_statements, is_side_effect_free,
+        is_side_effect_free_function, normalize_exp, LintConfig, NormalizedExp,
+    },
     visitor::ExpressionAnalysisVisitor,
 };
 use codespan::FileId;
 use codespan_reporting::diagnostic::Diagnostic;
 use move_model::{
-    ast::{ExpData, Operation},
-    model::{FunctionEnv, GlobalEnv},
+    ast::{Exp, ExpData, Operation},
+    model::{FunctionEnv, GlobalEnv, NodeId},
+    symbol::Symbol,
+    ty::ReferenceKind,
 };
-pub struct IfsSameCondVisitor {
-}
+use std::collections::BTreeSet;
+
+const SAME_COND_MESSAGE: &str =
+    "Detected consecutive if conditions with the same expression. Consider refactoring to avoid redundancy.";
+
+/// Framework modules whose native functions have no side effects besides on the mutable
+/// references passed to them, so conditions calling them can be compared.
+pub const PURE_NATIVE_MODULES: [&str; 8] = [
+    "vector",
+    "signer",
+    "bcs",
+    "hash",
+    "aptos_hash",
+    "type_info",
+    "string",
+    "from_bcs",
+];
+
+pub struct IfsSameCondVisitor;
 
 impl Default for IfsSameCondVisitor {
     fn default() -> Self {
@@ -24,63 +50,250 @@ impl Default for IfsSameCondVisitor {
 
 impl IfsSameCondVisitor {
     pub fn new() -> Self {
-        Self {
-        }
+        Self {}
     }
 
     pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
         Box::new(Self::new())
     }
 
-    /// Checks if the current 'if' condition is a duplicate and sets the condition for future checks.
-    fn wrapper_condition_string(
-        &mut self,
+    /// Checks the conditions of the `if`/`else if` chain starting at `exp` for repetitions and
+    /// records the nested `if`s of the chain, so the rest of the chain is not checked again.
+    fn check_else_if_chain(
+        &self,
         exp: &ExpData,
         func_env: &FunctionEnv,
         env: &GlobalEnv,
-    ) -> Option<String> {
-        let vars = &mut Vec::new();
-        let opers = &mut Vec::new();
-        self.get_condition_string(exp, env, func_env, vars, opers)
+        chained: &mut BTreeSet<NodeId>,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let mut seen: Vec<NormalizedExp> = Vec::new();
+        let mut current = exp;
+        while let ExpData::IfElse(_, cond, _, els) = current {
+            if is_side_effect_free(cond, env) {
+                let normalized = normalize_exp(cond, func_env);
+                if seen.contains(&normalized) {
+                    self.report(cond, SAME_COND_MESSAGE, env, diags);
+                } else {
+                    seen.push(normalized);
+                }
+            } else {
+                // The earlier conditions may evaluate differently after this one.
+                seen.clear();
+            }
+            current = els.as_ref();
+            if let ExpData::IfElse(node_id, ..) = current {
+                chained.insert(*node_id);
+            }
+        }
+    }
 
+    /// Checks the statements of a sequence for an `if` repeating the condition of an earlier
+    /// `if` when nothing in between can change the value of the condition.
+    fn check_consecutive_ifs(
+        &self,
+        exps: &[Exp],
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let param_symbols: Vec<Symbol> = func_env.get_parameters().iter().map(|p| p.0).collect();
+        for (idx, exp) in exps.iter().enumerate() {
+            let (cond, then, els) = match exp.as_ref() {
+                ExpData::IfElse(_, cond, then, els) => (cond, then, els),
+                _ => continue,
+            };
+            if !is_side_effect_free(cond, env) {
+                continue;
+            }
+            let normalized = normalize_exp(cond, func_env);
+            let may_change = |exp: &Exp| self.may_change_condition(exp, cond, &param_symbols, env);
+            if may_change(then) || may_change(els) {
+                continue;
+            }
+            for later in &exps[idx + 1..] {
+                if let ExpData::IfElse(_, later_cond, _, _) = later.as_ref() {
+                    if normalize_exp(later_cond, func_env) == normalized {
+                        self.report(later_cond, SAME_COND_MESSAGE, env, diags);
+                        break;
+                    }
+                }
+                if may_change(later) {
+                    break;
+                }
+            }
+        }
     }
 
-    /// Constructs a string representation of the given condition for comparison purposes.
-    fn get_condition_string(
-        &mut self,
-        exp: &ExpData,
+    /// Checks the `then` branch of an `if` for a nested `if` repeating its condition when nothing
+    /// before the nested `if` can change the value of the condition.
+    fn check_nested_ifs(
+        &self,
+        cond: &Exp,
+        then: &Exp,
+        func_env: &FunctionEnv,
         env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let normalized = normalize_exp(cond, func_env);
+        self.check_nested_branch(cond, &normalized, then, func_env, env, diags);
+    }
+
+    /// Scans the statements of a branch for an `if` with the condition `normalized`, descending
+    /// into the branches of nested `if`s. Returns true once the condition is repeated or may
+    /// have changed, so the scan stops.
+    fn check_nested_branch(
+        &self,
+        cond: &Exp,
+        normalized: &NormalizedExp,
+        branch: &Exp,
         func_env: &FunctionEnv,
-        vars: &mut Vec<String>,
-        opers: &mut Vec<Operation>,
-    ) -> Option<String> {
-        if let ExpData::Call(_, Operation::Exists(_), _) = exp {
-            return None;
-        }
-        match exp {
-            ExpData::Call(_, oper, vec_exp) => {
-                opers.push(oper.clone());
-                vec_exp.iter().for_each(|e| {
-                    self.get_condition_string(e, env, func_env, vars, opers);
-                });
-            },
-            ExpData::Value(_, value) => vars.push(env.display(value).to_string()),
-            ExpData::LocalVar(_, symbol) => {
-                vars.push(env.symbol_pool().string(*symbol).to_string());
-            },
-            ExpData::Temporary(_, usize) => {
-                let parameters = func_env.get_parameters();
-                let param = get_var_info_from_func_param(*usize, &parameters);
-                if let Some(param) = param {
-                    vars.push(env.symbol_pool().string(param.0).to_string());
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) -> bool {
+        let param_symbols: Vec<Symbol> = func_env.get_parameters().iter().map(|p| p.0).collect();
+        let statements = match branch.as_ref() {
+            ExpData::Sequence(..) => get_sequence_statements(branch, &mut BTreeSet::new()),
+            _ => vec![branch.clone()],
+        };
+        for statement in statements {
+            if let ExpData::IfElse(_, inner_cond, then, els) = statement.as_ref() {
+                if normalize_exp(inner_cond, func_env) == *normalized {
+                    self.report(inner_cond, SAME_COND_MESSAGE, env, diags);
+                    return true;
                 }
-            },
-            _ => (),
+                if is_side_effect_free(inner_cond, env)
+                    && (self.check_nested_branch(cond, normalized, then, func_env, env, diags)
+                        || self.check_nested_branch(cond, normalized, els, func_env, env, diags))
+                {
+                    return true;
+                }
+            }
+            if self.may_change_condition(&statement, cond, &param_symbols, env) {
+                return true;
+            }
         }
+        false
+    }
+
+    /// Returns true if the expression may change the value of the condition, by modifying a
+    /// variable it reads or, if it depends on global state, by changing that state.
+    fn may_change_condition(
+        &self,
+        exp: &Exp,
+        cond: &ExpData,
+        param_symbols: &[Symbol],
+        env: &GlobalEnv,
+    ) -> bool {
+        let cond_vars = cond.free_vars_and_used_params(param_symbols);
+        (self.reads_state(cond) && self.may_write_state(exp, env))
+            || self
+                .collect_modified_vars(exp, param_symbols, env)
+                .iter()
+                .any(|var| cond_vars.contains(var))
+    }
 
-        Some(format!("{:?} {:?}", vars, opers))
+    /// Returns true if the expression depends on more than locals, by calling functions or
+    /// reading global storage.
+    fn reads_state(&self, exp: &ExpData) -> bool {
+        let mut reads_state = false;
+        exp.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            if post {
+                reads_state |= matches!(
+                    e,
+                    ExpData::Call(
+                        _,
+                        Operation::MoveFunction(..)
+                            | Operation::Exists(_)
+                            | Operation::BorrowGlobal(_),
+                        _
+                    )
+                );
+            }
+            true
+        });
+        reads_state
+    }
+
+    /// Returns true if the expression may change global storage or call a function which is
+    /// not side effect free.
+    fn may_write_state(&self, exp: &ExpData, env: &GlobalEnv) -> bool {
+        let mut writes_state = false;
+        exp.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            if post {
+                writes_state |= match e {
+                    ExpData::Call(_, Operation::MoveFunction(mid, fid), _) => {
+                        !is_side_effect_free_function(&env.get_function(mid.qualified(*fid)))
+                    },
+                    ExpData::Call(
+                        _,
+                        Operation::MoveTo
+                        | Operation::MoveFrom
+                        | Operation::BorrowGlobal(ReferenceKind::Mutable),
+                        _,
+                    )
+                    | ExpData::Invoke(..) => true,
+                    _ => false,
+                };
+            }
+            !writes_state
+        });
+        writes_state
+    }
+
+    /// Collects the locals and parameters the expression may modify: assigned, borrowed, written
+    /// through, or passed on as a mutable reference.
+    fn collect_modified_vars(
+        &self,
+        exp: &Exp,
+        param_symbols: &[Symbol],
+        env: &GlobalEnv,
+    ) -> BTreeSet<Symbol> {
+        let mut modified = BTreeSet::new();
+        exp.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            if post {
+                match e {
+                    ExpData::Assign(_, pattern, _) => {
+                        modified.extend(pattern.vars().into_iter().map(|(_, sym)| sym));
+                    },
+                    ExpData::Mutate(_, lhs, _) => {
+                        modified.extend(lhs.free_vars_and_used_params(param_symbols));
+                    },
+                    ExpData::Call(_, Operation::Borrow(_), args) => {
+                        for arg in args {
+                            modified.extend(arg.free_vars_and_used_params(param_symbols));
+                        }
+                    },
+                    ExpData::Call(_, Operation::MoveFunction(..), args) => {
+                        for arg in args {
+                            if env.get_node_type(arg.node_id()).is_mutable_reference() {
+                                modified.extend(arg.free_vars_and_used_params(param_symbols));
+                            }
+                        }
+                    },
+                    _ => {},
+                }
+            }
+            true
+        });
+        modified
     }
 
+    fn report(
+        &self,
+        exp: &ExpData,
+        message: &str,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        add_diagnostic_and_emit(
+            &env.get_node_loc(exp.node_id()),
+            message,
+            codespan_reporting::diagnostic::Severity::Warning,
+            env,
+            diags,
+        );
+    }
 }
 
 impl ExpressionAnalysisVisitor for IfsSameCondVisitor {
@@ -91,34 +304,31 @@ impl ExpressionAnalysisVisitor for IfsSameCondVisitor {
         _: &LintConfig,
         diags: &mut Vec<Diagnostic<FileId>>,
     ) {
-        let func = func_env.get_def();
-        if let Some(func) = func.as_ref() {
-            func.visit_pre_post(
-                &mut (|up: bool, exp: &ExpData| {
-                    if !up {
-                        if let ExpData::IfElse(_, cond, _, if_else) = exp {
-                            if let ExpData::IfElse(_, if_else_cond, _, _) = if_else.as_ref() {
-                                if self.wrapper_condition_string(cond.as_ref(), func_env, env).is_none() || self.wrapper_condition_string(if_else_cond.as_ref(), func_env, env).is_none(){
-                                    return true;
-                                };
-                                if self.wrapper_condition_string(cond.as_ref(), func_env, env) == self.wrapper_condition_string(if_else_cond.as_ref(), func_env, env){
-                                    let message =
-                                        "Detected consecutive if conditions with the same expression. Consider refactoring to avoid redundancy.";
-                                    add_diagnostic_and_emit(
-                                        &env.get_node_loc(exp.node_id()),
-                                        message,
-                                        codespan_reporting::diagnostic::Severity::Warning,
-                                        env,
-                                        diags,
-                                    );
-                                }
- 
-                            }
+        let func = match func_env.get_def() {
+            Some(func) => func,
+            None => return,
+        };
+        let mut chained = BTreeSet::new();
+        let mut nested = BTreeSet::new();
+        func.visit_pre_post(&mut |post: bool, exp: &ExpData| {
+            if !post {
+                match exp {
+                    ExpData::IfElse(node_id, cond, then, _) => {
+                        if !chained.contains(node_id) {
+                            self.check_else_if_chain(exp, func_env, env, &mut chained, diags);
                         }
-                    }
-                    true
-                }),
-            );
-        }
+                        if is_side_effect_free(cond, env) {
+                            self.check_nested_ifs(cond, then, func_env, env, diags);
+                        }
+                    },
+                    ExpData::Sequence(node_id, _) if !nested.contains(node_id) => {
+                        let statements = get_sequence_statements(exp, &mut nested);
+                        self.check_consecutive_ifs(&statements, func_env, env, diags);
+                    },
+                    _ => {},
+                }
+            }
+            true
+        });
     }
 }
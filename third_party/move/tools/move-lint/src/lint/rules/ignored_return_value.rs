@@ -0,0 +1,196 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Lint to check for calls whose result is ignored although it should be handled: values taken
+//! out of containers like `option::extract` or `vector::remove`, lookups like `table::contains`,
+//! and coins or fungible assets withdrawn only to be passed straight to `destroy_zero`, which
+//! aborts unless they are empty. A result is ignored when the call is a statement of a block.
+//! Discarding it explicitly with `let _ = ..` is not reported, and is only suggested for calls
+//! with side effects: for lookups and other calls without any, a discarded result means the call
+//! can be removed. Besides the framework functions known to the linter, functions listed in
+//! `must_use_functions` of `lint.toml` are checked.
+use crate::lint::{
+    utils::{
+        add_diagnostic_and_emit, get_sequence_statements, is_framework_function,
+        is_side_effect_free_function, LintConfig,
+    },
+    visitor::ExpressionAnalysisVisitor,
+};
+use codespan::FileId;
+use codespan_reporting::diagnostic::Diagnostic;
+use move_model::{
+    ast::{ExpData, Operation},
+    model::{FunctionEnv, GlobalEnv, NodeId},
+};
+use std::collections::BTreeSet;
+
+/// Framework functions whose result should not be ignored, by module.
+const MUST_USE_FUNCTIONS: [(&str, &[&str]); 7] = [
+    ("option", &["extract", "swap", "destroy_some", "contains"]),
+    ("vector", &["remove", "swap_remove", "contains", "index_of"]),
+    ("table", &["remove", "contains"]),
+    ("smart_table", &["remove", "contains"]),
+    ("coin", &["withdraw", "extract", "extract_all"]),
+    ("fungible_asset", &["withdraw", "extract"]),
+    ("primary_fungible_store", &["withdraw"]),
+];
+
+/// Must-use framework functions which only look up a value, by module.
+const LOOKUP_FUNCTIONS: [(&str, &[&str]); 4] = [
+    ("option", &["contains"]),
+    ("vector", &["contains", "index_of"]),
+    ("table", &["contains"]),
+    ("smart_table", &["contains"]),
+];
+
+#[derive(Default)]
+pub struct IgnoredReturnValueVisitor;
+
+impl IgnoredReturnValueVisitor {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
+        Box::new(Self::new())
+    }
+
+    /// Returns true if the result of the function should not be ignored, either because it is a
+    /// known framework function or because it is listed in `must_use_functions` of the config.
+    fn is_must_use_function(&self, func_env: &FunctionEnv, config: &LintConfig) -> bool {
+        MUST_USE_FUNCTIONS.iter().any(|(module_name, func_names)| {
+            is_framework_function(func_env, module_name, func_names)
+        }) || config.must_use_functions.iter().any(|name| {
+            name == &func_env.get_full_name_str() || name == &func_env.get_full_name_with_address()
+        })
+    }
+
+    /// Returns true if calling the function has no effect besides computing its result.
+    fn is_lookup_function(&self, func_env: &FunctionEnv) -> bool {
+        LOOKUP_FUNCTIONS.iter().any(|(module_name, func_names)| {
+            is_framework_function(func_env, module_name, func_names)
+        }) || is_side_effect_free_function(func_env)
+    }
+
+    /// Returns the callee of a call to a function whose result should not be ignored.
+    fn get_must_use_callee<'env>(
+        &self,
+        exp: &ExpData,
+        env: &'env GlobalEnv,
+        config: &LintConfig,
+    ) -> Option<FunctionEnv<'env>> {
+        if let ExpData::Call(node_id, Operation::MoveFunction(mid, fid), _) = exp {
+            let callee = env.get_function(mid.qualified(*fid));
+            if !env.get_node_type(*node_id).is_unit() && self.is_must_use_function(&callee, config)
+            {
+                return Some(callee);
+            }
+        }
+        None
+    }
+
+    /// Checks the statements of a block for calls whose result is dropped. The last statement
+    /// is the value of the block and is checked where the block is used.
+    fn check_statements(
+        &self,
+        exp: &ExpData,
+        env: &GlobalEnv,
+        config: &LintConfig,
+        nested: &mut BTreeSet<NodeId>,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let statements = get_sequence_statements(exp, nested);
+        let ignored = match statements.split_last() {
+            Some((_, ignored)) => ignored,
+            None => return,
+        };
+        for statement in ignored {
+            if let Some(callee) = self.get_must_use_callee(statement, env, config) {
+                // Discarding the result of a call without side effects with `let _` is reported
+                // by the unused locals lint.
+                let message = if self.is_lookup_function(&callee) {
+                    format!(
+                        "The result of `{}` is ignored, and the call has no side effects. Consider using its result or removing the call.",
+                        callee.get_full_name_str()
+                    )
+                } else {
+                    format!(
+                        "The result of `{}` is ignored. Consider handling it, or discard it explicitly with `let _ = ..` if this is intended.",
+                        callee.get_full_name_str()
+                    )
+                };
+                add_diagnostic_and_emit(
+                    &env.get_node_loc(statement.node_id()),
+                    &message,
+                    codespan_reporting::diagnostic::Severity::Warning,
+                    env,
+                    diags,
+                );
+            }
+        }
+    }
+
+    /// Checks for a withdrawn value passed straight to `destroy_zero`.
+    fn check_destroy_zero(
+        &self,
+        exp: &ExpData,
+        env: &GlobalEnv,
+        config: &LintConfig,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let (mid, fid, args) = match exp {
+            ExpData::Call(_, Operation::MoveFunction(mid, fid), args) if args.len() == 1 => {
+                (mid, fid, args)
+            },
+            _ => return,
+        };
+        let destroy = env.get_function(mid.qualified(*fid));
+        if !is_framework_function(&destroy, "coin", &["destroy_zero"])
+            && !is_framework_function(&destroy, "fungible_asset", &["destroy_zero"])
+        {
+            return;
+        }
+        if let Some(callee) = self.get_must_use_callee(&args[0], env, config) {
+            let message = format!(
+                "The result of `{}` is passed straight to `{}`, which aborts unless it is empty. Consider handling the withdrawn value.",
+                callee.get_full_name_str(),
+                destroy.get_full_name_str()
+            );
+            add_diagnostic_and_emit(
+                &env.get_node_loc(exp.node_id()),
+                &message,
+                codespan_reporting::diagnostic::Severity::Warning,
+                env,
+                diags,
+            );
+        }
+    }
+}
+
+impl ExpressionAnalysisVisitor for IgnoredReturnValueVisitor {
+    fn visit_function_custom(
+        &mut self,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        lint_config: &LintConfig,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let body = match func_env.get_def() {
+            Some(body) => body,
+            None => return,
+        };
+        let mut nested = BTreeSet::new();
+        body.visit_pre_post(&mut |post: bool, exp: &ExpData| {
+            if !post {
+                match exp {
+                    ExpData::Sequence(node_id, _) if !nested.contains(node_id) => {
+                        self.check_statements(exp, env, lint_config, &mut nested, diags);
+                    },
+                    ExpData::Call(..) => self.check_destroy_zero(exp, env, lint_config, diags),
+                    _ => {},
+                }
+            }
+            true
+        });
+    }
+}
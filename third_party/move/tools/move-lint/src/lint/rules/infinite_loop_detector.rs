@@ -1,8 +1,11 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-//! `InfiniteLoopDetectorVisitor` identifies potential infinite loops in Move programs by checking for loops without 'break' or 'return'.
-//! It warns about any `loop` constructs that may run indefinitely, promoting better control flow and program safety.
+//! `InfiniteLoopDetectorVisitor` identifies potential infinite loops in Move programs.
+//! It warns about `loop` constructs without any way out, i.e. without a `break` belonging to the loop itself,
+//! a `return`, an `abort` (including a failing `assert!`) or a call to a function which always aborts.
+//! It also warns about `while` loops whose condition only reads locals which the loop body never modifies,
+//! so that the loop either never runs or never ends.
 use crate::lint::{
     utils::{add_diagnostic_and_emit, LintConfig},
     visitor::ExpressionAnalysisVisitor,
@@ -10,9 +13,12 @@ use crate::lint::{
 use codespan::FileId;
 use codespan_reporting::diagnostic::Diagnostic;
 use move_model::{
-    ast::{Exp, ExpData},
-    model::{FunctionEnv, GlobalEnv},
+    ast::{Exp, ExpData, Operation},
+    model::{FunId, FunctionEnv, GlobalEnv, QualifiedId},
+    symbol::Symbol,
 };
+use std::collections::BTreeSet;
+
 pub struct InfiniteLoopDetectorVisitor;
 
 impl Default for InfiniteLoopDetectorVisitor {
@@ -30,7 +36,7 @@ impl InfiniteLoopDetectorVisitor {
         Box::new(Self::new())
     }
 
-    /// Checks for loop or while(true) without break or return.
+    /// Checks for loop or while(true) without any exit.
     fn check_infinite_loop(
         &self,
         exp: &ExpData,
@@ -38,9 +44,9 @@ impl InfiniteLoopDetectorVisitor {
         diags: &mut Vec<Diagnostic<FileId>>,
     ) {
         if let ExpData::Loop(_, body) = exp {
-            if !self.contains_break_or_return(body) {
+            if !self.has_exit(body, env) {
                 let message =
-                    "Potential infinite loop detected. No 'break' or 'return' found in the loop.";
+                    "Potential infinite loop detected. No `break`, `return` or `abort` found in the loop.";
                 add_diagnostic_and_emit(
                     &env.get_node_loc(exp.node_id()),
                     message,
@@ -52,20 +58,188 @@ impl InfiniteLoopDetectorVisitor {
         }
     }
 
-    /// Recursively checks if the loop body contains a break or return statement.
-    fn contains_break_or_return(&self, exp: &Exp) -> bool {
-        let mut contains_break_or_return = false;
-        exp.visit_pre_post(&mut |is_pre_visit, exp: &ExpData| {
-            if !is_pre_visit {
-                match exp {
-                    ExpData::LoopCont(_, false) => contains_break_or_return = true,
-                    ExpData::Return(_, _) => contains_break_or_return = true,
-                    _ => (),
+    /// Checks for `while (cond)` loops whose condition variables are never modified in the body.
+    fn check_unmodified_condition(
+        &self,
+        exp: &ExpData,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let ExpData::Loop(_, body) = exp else {
+            return;
+        };
+        let ExpData::IfElse(_, cond, loop_body, else_body) = body.as_ref() else {
+            return;
+        };
+        if !matches!(else_body.as_ref(), ExpData::LoopCont(_, false))
+            || self.has_exit(loop_body, env)
+        {
+            return;
+        }
+        // Calls and global storage reads may change between iterations without the body
+        // assigning anything.
+        let mut has_side_inputs = false;
+        cond.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            if post {
+                has_side_inputs |= matches!(
+                    e,
+                    ExpData::Call(
+                        _,
+                        Operation::MoveFunction(..)
+                            | Operation::Exists(_)
+                            | Operation::BorrowGlobal(_),
+                        _
+                    ) | ExpData::Invoke(..)
+                );
+            }
+            true
+        });
+        let params = func_env.get_parameters();
+        let param_symbols: Vec<Symbol> = params.iter().map(|param| param.0).collect();
+        let cond_vars = cond.free_vars_and_used_params(&param_symbols);
+        if has_side_inputs || cond_vars.is_empty() {
+            return;
+        }
+        let modified = self.collect_modified_vars(loop_body, &param_symbols, env);
+        if cond_vars.iter().any(|var| modified.contains(var)) {
+            return;
+        }
+        let names = cond_vars
+            .iter()
+            .map(|var| format!("`{}`", var.display(env.symbol_pool())))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let message = format!(
+            "Loop condition never changes: the loop body does not modify {}, so the loop either never runs or never ends.",
+            names
+        );
+        add_diagnostic_and_emit(
+            &env.get_node_loc(cond.node_id()),
+            &message,
+            codespan_reporting::diagnostic::Severity::Warning,
+            env,
+            diags,
+        );
+    }
+
+    /// Returns true if the loop body can leave the loop: through a `break` which is not inside a
+    /// nested loop, a `return`, an `abort` or a call to a function which always aborts.
+    fn has_exit(&self, body: &Exp, env: &GlobalEnv) -> bool {
+        let mut nested_loops = 0;
+        let mut has_exit = false;
+        body.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            match e {
+                ExpData::Loop(..) => {
+                    if post {
+                        nested_loops -= 1;
+                    } else {
+                        nested_loops += 1;
+                    }
+                },
+                ExpData::LoopCont(_, false) if nested_loops == 0 => has_exit = true,
+                ExpData::Return(..) | ExpData::Call(_, Operation::Abort, _) => has_exit = true,
+                ExpData::Call(_, Operation::MoveFunction(mid, fid), _) if post => {
+                    has_exit |= self.is_diverging(mid.qualified(*fid), env, &mut BTreeSet::new());
+                },
+                _ => {},
+            }
+            !has_exit
+        });
+        has_exit
+    }
+
+    /// Returns true if every execution of the function aborts.
+    fn is_diverging(
+        &self,
+        fun_id: QualifiedId<FunId>,
+        env: &GlobalEnv,
+        visited: &mut BTreeSet<QualifiedId<FunId>>,
+    ) -> bool {
+        if !visited.insert(fun_id) {
+            return false;
+        }
+        let func_env = env.get_function(fun_id);
+        match func_env.get_def() {
+            Some(def) if !func_env.is_native() => self.always_aborts(def, env, visited),
+            _ => false,
+        }
+    }
+
+    /// Returns true if every evaluation of the expression aborts.
+    fn always_aborts(
+        &self,
+        exp: &ExpData,
+        env: &GlobalEnv,
+        visited: &mut BTreeSet<QualifiedId<FunId>>,
+    ) -> bool {
+        match exp {
+            ExpData::Call(_, Operation::Abort, _) => true,
+            // The right operand is not always evaluated.
+            ExpData::Call(_, Operation::And | Operation::Or, args) => args
+                .first()
+                .map_or(false, |arg| self.always_aborts(arg, env, visited)),
+            ExpData::Call(_, Operation::MoveFunction(mid, fid), args) => {
+                args.iter().any(|arg| self.always_aborts(arg, env, visited))
+                    || self.is_diverging(mid.qualified(*fid), env, visited)
+            },
+            ExpData::Call(_, _, args) => {
+                args.iter().any(|arg| self.always_aborts(arg, env, visited))
+            },
+            ExpData::Sequence(_, exps) => {
+                exps.iter().any(|exp| self.always_aborts(exp, env, visited))
+            },
+            ExpData::Block(_, _, binding, body) => {
+                binding
+                    .as_ref()
+                    .map_or(false, |binding| self.always_aborts(binding, env, visited))
+                    || self.always_aborts(body, env, visited)
+            },
+            ExpData::IfElse(_, cond, then, els) => {
+                self.always_aborts(cond, env, visited)
+                    || (self.always_aborts(then, env, visited)
+                        && self.always_aborts(els, env, visited))
+            },
+            _ => false,
+        }
+    }
+
+    /// Collects the locals and parameters the expression may modify: assigned, borrowed mutably,
+    /// written through, or passed on as a mutable reference.
+    fn collect_modified_vars(
+        &self,
+        exp: &Exp,
+        param_symbols: &[Symbol],
+        env: &GlobalEnv,
+    ) -> BTreeSet<Symbol> {
+        let mut modified = BTreeSet::new();
+        exp.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            if post {
+                match e {
+                    ExpData::Assign(_, pattern, _) => {
+                        modified.extend(pattern.vars().into_iter().map(|(_, sym)| sym));
+                    },
+                    ExpData::Mutate(_, lhs, _) => {
+                        modified.extend(lhs.free_vars_and_used_params(param_symbols));
+                    },
+                    ExpData::Call(_, Operation::Borrow(_), args) => {
+                        for arg in args {
+                            modified.extend(arg.free_vars_and_used_params(param_symbols));
+                        }
+                    },
+                    ExpData::Call(_, Operation::MoveFunction(..), args) => {
+                        for arg in args {
+                            if env.get_node_type(arg.node_id()).is_mutable_reference() {
+                                modified.extend(arg.free_vars_and_used_params(param_symbols));
+                            }
+                        }
+                    },
+                    _ => {},
                 }
-            };
+            }
             true
         });
-        contains_break_or_return
+        modified
     }
 }
 
@@ -73,11 +247,12 @@ impl ExpressionAnalysisVisitor for InfiniteLoopDetectorVisitor {
     fn post_visit_expression(
         &mut self,
         exp: &ExpData,
-        _func_env: &FunctionEnv,
+        func_env: &FunctionEnv,
         env: &GlobalEnv,
         _: &LintConfig,
         diags: &mut Vec<Diagnostic<FileId>>,
     ) {
         self.check_infinite_loop(exp, env, diags);
+        self.check_unmodified_condition(exp, func_env, env, diags);
     }
 }
@@ -0,0 +1,262 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Detect misuse of `init_module`. Mirrors the signature checks of the framework's extended
+//! checks (private, only signer parameters, no return values) and additionally warns when
+//! `init_module` (or a function it calls within the package) uses `randomness`, depends on
+//! `timestamp::now_seconds` being initialized, or publishes resources under a signer which is not
+//! the deployer or derived from it.
+use crate::lint::{
+    utils::{
+        add_diagnostic_and_emit, get_package_call_closure, is_framework_function,
+        is_framework_module,
+    },
+    visitor::ExpressionAnalysisVisitor,
+};
+use codespan::FileId;
+use codespan_reporting::diagnostic::Diagnostic;
+use move_model::{
+    ast::{ExpData, Operation},
+    model::{FunctionEnv, GlobalEnv, ModuleEnv, NodeId, Parameter, Visibility},
+    symbol::Symbol,
+    ty::{PrimitiveType, Type},
+};
+use std::collections::BTreeSet;
+
+const INIT_MODULE_FUN: &str = "init_module";
+
+#[derive(Debug)]
+pub struct InitModuleVisitor;
+
+impl Default for InitModuleVisitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InitModuleVisitor {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
+        Box::new(Self::new())
+    }
+
+    /// Checks the visibility, parameters and return values of `init_module`.
+    fn check_signature(
+        &self,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        if func_env.visibility() != Visibility::Private || func_env.is_entry() {
+            let message = "`init_module` function should be private and not an entry function.";
+            add_diagnostic_and_emit(
+                &func_env.get_id_loc(),
+                message,
+                codespan_reporting::diagnostic::Severity::Warning,
+                env,
+                diags,
+            );
+        }
+        for Parameter(name, ty, loc) in func_env.get_parameters() {
+            let is_signer = matches!(ty.skip_reference(), Type::Primitive(PrimitiveType::Signer));
+            if !is_signer {
+                let message = format!(
+                    "`init_module` parameter `{}` is not a signer. `init_module` can only take signers as parameters.",
+                    name.display(env.symbol_pool())
+                );
+                add_diagnostic_and_emit(
+                    &loc,
+                    &message,
+                    codespan_reporting::diagnostic::Severity::Warning,
+                    env,
+                    diags,
+                );
+            }
+        }
+        if func_env.get_parameter_count() > 1 {
+            let message =
+                "`init_module` is only called with the deployer signer and should take a single signer parameter.";
+            add_diagnostic_and_emit(
+                &func_env.get_id_loc(),
+                message,
+                codespan_reporting::diagnostic::Severity::Warning,
+                env,
+                diags,
+            );
+        }
+        if func_env.get_return_count() > 0 {
+            let message = "`init_module` function cannot return values.";
+            add_diagnostic_and_emit(
+                &func_env.get_id_loc(),
+                message,
+                codespan_reporting::diagnostic::Severity::Warning,
+                env,
+                diags,
+            );
+        }
+    }
+
+    /// Checks calls made by `init_module` and by the package functions it reaches.
+    fn check_calls(
+        &self,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        for fun_id in get_package_call_closure(func_env) {
+            let caller = env.get_function(fun_id);
+            if let Some(def) = caller.get_def() {
+                def.visit_pre_post(&mut |post: bool, exp: &ExpData| {
+                    if post {
+                        if let ExpData::Call(node_id, Operation::MoveFunction(mid, fid), _) = exp {
+                            let callee = env.get_function(mid.qualified(*fid));
+                            self.check_callee(&callee, *node_id, env, diags);
+                        }
+                    }
+                    true
+                });
+            }
+        }
+    }
+
+    fn check_callee(
+        &self,
+        callee: &FunctionEnv,
+        node_id: NodeId,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let message = if is_framework_module(&callee.module_env, "randomness") {
+            "`init_module` should not use `randomness`; it is not available while a module is being published."
+        } else if is_framework_function(callee, "timestamp", &["now_seconds", "now_microseconds"]) {
+            "`init_module` depends on `timestamp` being initialized, which is not the case when the package is published at genesis."
+        } else {
+            return;
+        };
+        add_diagnostic_and_emit(
+            &env.get_node_loc(node_id),
+            message,
+            codespan_reporting::diagnostic::Severity::Warning,
+            env,
+            diags,
+        );
+    }
+
+    /// Warns when `init_module` publishes a resource under a signer which is neither the
+    /// deployer nor derived from it, like the signer of a resource account or an object created
+    /// by the deployer.
+    fn check_publish_target(
+        &self,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let def = match func_env.get_def() {
+            Some(def) => def,
+            None => return,
+        };
+        let deployer_params: BTreeSet<usize> = func_env
+            .get_parameters()
+            .iter()
+            .enumerate()
+            .filter(|(_, Parameter(_, ty, _))| {
+                matches!(ty.skip_reference(), Type::Primitive(PrimitiveType::Signer))
+            })
+            .map(|(idx, _)| idx)
+            .take(1)
+            .collect();
+        let derived = self.collect_derived_locals(&def, &deployer_params);
+        def.visit_pre_post(&mut |post: bool, exp: &ExpData| {
+            if post {
+                if let ExpData::Call(node_id, Operation::MoveTo, args) = exp {
+                    let from_deployer = args
+                        .first()
+                        .map_or(false, |arg| self.uses_deployer(arg, &deployer_params, &derived));
+                    if !from_deployer {
+                        let message = "`init_module` publishes this resource under a signer not derived from the deployer. Resources are usually expected under the deployer's address or an account or object it creates.";
+                        add_diagnostic_and_emit(
+                            &env.get_node_loc(*node_id),
+                            message,
+                            codespan_reporting::diagnostic::Severity::Warning,
+                            env,
+                            diags,
+                        );
+                    }
+                }
+            }
+            true
+        });
+    }
+
+    /// Collects the locals whose value is computed from the deployer signer, such as the signer
+    /// and capability returned by `account::create_resource_account(deployer, ..)`.
+    fn collect_derived_locals(
+        &self,
+        def: &ExpData,
+        deployer_params: &BTreeSet<usize>,
+    ) -> BTreeSet<Symbol> {
+        let mut derived = BTreeSet::new();
+        loop {
+            let mut changed = false;
+            def.visit_pre_post(&mut |post: bool, exp: &ExpData| {
+                if post {
+                    let (pattern, value) = match exp {
+                        ExpData::Block(_, pattern, Some(value), _) => (pattern, value),
+                        ExpData::Assign(_, pattern, value) => (pattern, value),
+                        _ => return true,
+                    };
+                    if self.uses_deployer(value, deployer_params, &derived) {
+                        for (_, sym) in pattern.vars() {
+                            changed |= derived.insert(sym);
+                        }
+                    }
+                }
+                true
+            });
+            if !changed {
+                return derived;
+            }
+        }
+    }
+
+    /// Returns true if the expression reads the deployer signer parameter of `init_module` or a
+    /// local derived from it.
+    fn uses_deployer(
+        &self,
+        exp: &ExpData,
+        deployer_params: &BTreeSet<usize>,
+        derived: &BTreeSet<Symbol>,
+    ) -> bool {
+        let mut uses_deployer = false;
+        exp.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            if post {
+                uses_deployer |= match e {
+                    ExpData::Temporary(_, idx) => deployer_params.contains(idx),
+                    ExpData::LocalVar(_, sym) => derived.contains(sym),
+                    _ => false,
+                };
+            }
+            !uses_deployer
+        });
+        uses_deployer
+    }
+}
+
+impl ExpressionAnalysisVisitor for InitModuleVisitor {
+    fn visit_module(
+        &mut self,
+        module: &ModuleEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let init_module_sym = env.symbol_pool().make(INIT_MODULE_FUN);
+        if let Some(func_env) = module.find_function(init_module_sym) {
+            self.check_signature(&func_env, env, diags);
+            self.check_calls(&func_env, env, diags);
+            self.check_publish_target(&func_env, env, diags);
+        }
+    }
+}
@@ -3,17 +3,27 @@
 
 //! `MeaninglessMathOperationsVisitor` detects and warns about operations in Move programs that have no effect, such as adding zero.
 //! It aims to improve code clarity by identifying operations that can be simplified or removed.
+//! It also detects operations whose operands are the same value, which are tautologies like
+//! `x == x`, contradictions like `x < x` or `c && !c`, or have a fixed result like `a - a`.
+//! Operands are compared in normalized form, looking through locals which are only copies of or
+//! references to another local, like `y` after `let y = x;`.
 use crate::lint::{
-    utils::{add_diagnostic_and_emit, LintConfig},
+    utils::{
+        add_diagnostic_and_emit, is_side_effect_free, negate_normalized_exp, normalize_exp,
+        LintConfig, NormalizedExp,
+    },
     visitor::ExpressionAnalysisVisitor,
 };
 use codespan::FileId;
 use codespan_reporting::diagnostic::Diagnostic;
 use move_model::{
-    ast::{Exp, ExpData, Operation, Value},
+    ast::{Exp, ExpData, Operation, Pattern, Value},
     model::{FunctionEnv, GlobalEnv},
+    symbol::Symbol,
+    ty::ReferenceKind,
 };
 use num_bigint::BigInt;
+use std::collections::{BTreeMap, BTreeSet};
 pub struct MeaninglessMathOperationsVisitor;
 
 impl Default for MeaninglessMathOperationsVisitor {
@@ -40,7 +50,7 @@ impl MeaninglessMathOperationsVisitor {
     ) {
         if let ExpData::Call(_, oper, args) = exp {
             if self.is_meaningless_operation(oper, args) {
-                let message = "Detected a unnecessary mathematical operation.";
+                let message = "Detected an unnecessary mathematical operation.";
                 add_diagnostic_and_emit(
                     &env.get_node_loc(exp.node_id()),
                     message,
@@ -75,9 +85,213 @@ impl MeaninglessMathOperationsVisitor {
             _ => false,
         })
     }
+
+    /// Checks for operations and `if`/`else` whose operands are the same value.
+    fn check_self_operation(
+        &self,
+        exp: &ExpData,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        aliases: &BTreeMap<Symbol, NormalizedExp>,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let message = match exp {
+            ExpData::Call(_, oper, args) if args.len() == 2 => {
+                if !args.iter().all(|arg| is_side_effect_free(arg, env)) {
+                    return;
+                }
+                let lhs = self.resolve_aliases(normalize_exp(&args[0], func_env), aliases);
+                let rhs = self.resolve_aliases(normalize_exp(&args[1], func_env), aliases);
+                match self.get_self_operation_message(oper, &lhs, &rhs) {
+                    Some(message) => message,
+                    None => return,
+                }
+            },
+            ExpData::IfElse(_, cond, then, els) => {
+                if !is_side_effect_free(cond, env) {
+                    return;
+                }
+                let cond = self.resolve_aliases(normalize_exp(cond, func_env), aliases);
+                let then = self.resolve_aliases(normalize_exp(then, func_env), aliases);
+                let els = self.resolve_aliases(normalize_exp(els, func_env), aliases);
+                if then != cond || els != cond {
+                    return;
+                }
+                "Detected an if/else whose branches are both its condition, so it always evaluates to the condition."
+            },
+            _ => return,
+        };
+        add_diagnostic_and_emit(
+            &env.get_node_loc(exp.node_id()),
+            message,
+            codespan_reporting::diagnostic::Severity::Warning,
+            env,
+            diags,
+        );
+    }
+
+    /// Returns the message for an operation on the same value, or on a boolean and its negation.
+    fn get_self_operation_message(
+        &self,
+        oper: &Operation,
+        lhs: &NormalizedExp,
+        rhs: &NormalizedExp,
+    ) -> Option<&'static str> {
+        if lhs == rhs {
+            return match oper {
+                Operation::Eq | Operation::Le | Operation::Ge => Some(
+                    "Detected a comparison of a value with itself, which is always true.",
+                ),
+                Operation::Neq | Operation::Lt | Operation::Gt => Some(
+                    "Detected a comparison of a value with itself, which is always false.",
+                ),
+                Operation::Sub | Operation::Xor | Operation::Mod => Some(
+                    "Detected an operation of a value with itself, which always results in 0.",
+                ),
+                Operation::Div => Some(
+                    "Detected a division of a value by itself, which always results in 1 or aborts for 0.",
+                ),
+                Operation::And | Operation::Or | Operation::BitAnd | Operation::BitOr => Some(
+                    "Detected an operation of a value with itself, which always results in the value.",
+                ),
+                _ => None,
+            };
+        }
+        if &negate_normalized_exp(lhs) != rhs {
+            return None;
+        }
+        match oper {
+            Operation::And => Some(
+                "Detected a conjunction of a condition with its negation, which is always false.",
+            ),
+            Operation::Or => Some(
+                "Detected a disjunction of a condition with its negation, which is always true.",
+            ),
+            _ => None,
+        }
+    }
+
+    /// Collects the locals bound once to a copy of or a reference to another local, when neither
+    /// is ever assigned or borrowed mutably, with the value they stand for.
+    fn collect_aliases(
+        &self,
+        body: &ExpData,
+        func_env: &FunctionEnv,
+    ) -> BTreeMap<Symbol, NormalizedExp> {
+        let mut bound: BTreeSet<Symbol> = func_env.get_parameters().iter().map(|p| p.0).collect();
+        let mut rebound = BTreeSet::new();
+        let mut modified = BTreeSet::new();
+        let mut candidates = vec![];
+        body.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            if post {
+                return true;
+            }
+            match e {
+                ExpData::Block(_, pattern, binding, _) => {
+                    for (_, sym) in pattern.vars() {
+                        if !bound.insert(sym) {
+                            rebound.insert(sym);
+                        }
+                    }
+                    if let (Pattern::Var(_, sym), Some(binding)) = (pattern, binding) {
+                        candidates.push((*sym, normalize_exp(binding, func_env)));
+                    }
+                },
+                ExpData::Assign(_, pattern, _) => {
+                    modified.extend(pattern.vars().into_iter().map(|(_, sym)| sym));
+                },
+                ExpData::Mutate(_, lhs, _) => {
+                    if let Some(sym) = self.get_local(lhs, func_env) {
+                        modified.insert(sym);
+                    }
+                },
+                ExpData::Call(_, Operation::Borrow(ReferenceKind::Mutable), args) => {
+                    if let Some(sym) = args.first().and_then(|arg| self.get_local(arg, func_env)) {
+                        modified.insert(sym);
+                    }
+                },
+                _ => {},
+            }
+            true
+        });
+        let is_stable = |sym: &Symbol| !rebound.contains(sym) && !modified.contains(sym);
+        candidates
+            .into_iter()
+            .filter(|(sym, value)| {
+                let source = match value {
+                    NormalizedExp::Var(source) => source,
+                    NormalizedExp::Node(name, args) if name.starts_with("Borrow(") => {
+                        match args.as_slice() {
+                            [NormalizedExp::Var(source)] => source,
+                            _ => return false,
+                        }
+                    },
+                    _ => return false,
+                };
+                is_stable(sym) && is_stable(source)
+            })
+            .collect()
+    }
+
+    fn get_local(&self, exp: &ExpData, func_env: &FunctionEnv) -> Option<Symbol> {
+        match normalize_exp(exp, func_env) {
+            NormalizedExp::Var(sym) => Some(sym),
+            _ => None,
+        }
+    }
+
+    /// Replaces aliases by the value they stand for, and dereferences of references to a value by
+    /// the value.
+    fn resolve_aliases(
+        &self,
+        exp: NormalizedExp,
+        aliases: &BTreeMap<Symbol, NormalizedExp>,
+    ) -> NormalizedExp {
+        match exp {
+            NormalizedExp::Var(sym) => match aliases.get(&sym) {
+                Some(value) => self.resolve_aliases(value.clone(), aliases),
+                None => NormalizedExp::Var(sym),
+            },
+            NormalizedExp::Node(name, args) => {
+                let args: Vec<NormalizedExp> = args
+                    .into_iter()
+                    .map(|arg| self.resolve_aliases(arg, aliases))
+                    .collect();
+                if name.starts_with("Deref") {
+                    if let [NormalizedExp::Node(inner_name, inner_args)] = args.as_slice() {
+                        if inner_name.starts_with("Borrow(") && inner_args.len() == 1 {
+                            return inner_args[0].clone();
+                        }
+                    }
+                }
+                NormalizedExp::Node(name, args)
+            },
+            _ => exp,
+        }
+    }
 }
 
 impl ExpressionAnalysisVisitor for MeaninglessMathOperationsVisitor {
+    fn visit_function_custom(
+        &mut self,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        _: &LintConfig,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let body = match func_env.get_def() {
+            Some(body) => body,
+            None => return,
+        };
+        let aliases = self.collect_aliases(&body, func_env);
+        body.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            if post {
+                self.check_self_operation(e, func_env, env, &aliases, diags);
+            }
+            true
+        });
+    }
+
     fn post_visit_expression(
         &mut self,
         exp: &ExpData,
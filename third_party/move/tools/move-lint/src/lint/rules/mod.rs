@@ -1,23 +1,31 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod abort_code;
 pub mod absurd_extreme_comparisons;
 pub mod bool_comparison;
+pub mod cast_truncation;
 pub mod check_redundant_boolean_expressions;
 pub mod combinable_bool_conditions;
 pub mod complex_inline_function;
 pub mod constant_naming;
 pub mod deep_nesting;
+pub mod division_by_zero;
+pub mod duplicated_code;
 pub mod empty_loop;
 pub mod event_attribute_ability;
+pub mod event_emission;
 pub mod exceed_blocks;
 pub mod exceed_dependencies;
 pub mod exceed_fields;
 pub mod exceed_params;
 pub mod explicit_self_assignments;
+pub mod front_running;
 pub mod getter_method_field_match;
 pub mod ifs_same_cond;
+pub mod ignored_return_value;
 pub mod infinite_loop_detector;
+pub mod init_module;
 pub mod meaningless_math_operations;
 pub mod multiplication_before_division;
 pub mod needless_bool;
@@ -26,13 +34,24 @@ pub mod overflow_multiplication_detector;
 pub mod randomness_public_entry;
 pub mod redundant_deref_ref;
 pub mod redundant_ref_deref;
+pub mod reentrancy_dispatch;
+pub mod resource_group;
 pub mod return_at_end_of_block;
 pub mod shift_overflow;
 pub mod sorted_imports;
+pub mod spot_price_oracle;
+pub mod subtraction_underflow;
+pub mod timestamp_dependence;
+pub mod unbounded_iteration;
 pub mod unconditional_exit_loop;
 pub mod unmodified_mutable_argument;
 pub mod unnecessary_mutable_reference;
 pub mod unnecessary_type_conversion;
 pub mod unnecessary_while_true;
+pub mod unreachable_code;
 pub mod unused_borrow_global_mut;
+pub mod unused_declarations;
+pub mod unused_locals;
 pub mod use_mul_div;
+pub mod view_function_purity;
+pub mod visibility_overexposure;
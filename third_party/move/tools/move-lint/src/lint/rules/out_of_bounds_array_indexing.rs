@@ -2,19 +2,43 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! This lint detects and warns about out-of-bounds indexing attempts on arrays in Move programs.
-//! It specifically checks for accesses beyond the length of arrays via `vector::borrow` and `vector::borrow_mut` functions.
+//! It tracks the length of vectors held in locals from `vector[...]` literals and `vector::empty`
+//! through `push_back`, `pop_back` and `remove`, and reports `vector::borrow`, `borrow_mut`,
+//! `remove` and `swap` with a constant index at or beyond the known length, as well as `pop_back`
+//! on a vector which is known to be empty. Independently of known lengths, it reports indices
+//! which are only guarded by `i <= vector::length(&v)`, the classic off-by-one in loop bounds, as
+//! the index may then equal the length.
 use crate::lint::{
-    utils::{add_diagnostic_and_emit, LintConfig},
+    utils::{add_diagnostic_and_emit, is_framework_module, LintConfig},
     visitor::ExpressionAnalysisVisitor,
 };
 use codespan::FileId;
 use codespan_reporting::diagnostic::Diagnostic;
 use move_model::{
     ast::{Exp, ExpData, Operation, Pattern, Value},
-    model::{FunId, FunctionEnv, GlobalEnv, ModuleId},
+    model::{FunctionEnv, GlobalEnv, NodeId},
     symbol::Symbol,
+    ty::ReferenceKind,
 };
 use num_bigint::BigInt;
+use std::collections::BTreeMap;
+
+/// Vector functions taking an index as their second argument.
+const INDEXING_FUNCTIONS: [&str; 4] = ["borrow", "borrow_mut", "remove", "swap"];
+/// Vector functions which may grow a vector.
+const GROWING_FUNCTIONS: [&str; 4] = ["push_back", "append", "insert", "reverse_append"];
+
+/// Known lengths of the vectors held in locals at a program point. Vectors without an entry may
+/// have any length.
+type State = BTreeMap<Symbol, usize>;
+
+/// The index side of a `index <= vector::length(&v)` guard.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Index {
+    Var(Symbol),
+    Constant(BigInt),
+}
+
 pub struct OutOfBoundsArrayIndexingVisitor;
 
 impl Default for OutOfBoundsArrayIndexingVisitor {
@@ -31,122 +55,461 @@ impl OutOfBoundsArrayIndexingVisitor {
     pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
         Box::new(Self::new())
     }
+}
 
-    fn check_out_of_bounds_indexing(
-        &self,
-        exp: &ExpData,
-        env: &GlobalEnv,
-        arr_length: usize,
-        assigned_symbol: &Symbol,
-        diags: &mut Vec<Diagnostic<FileId>>,
-    ) {
-        if let ExpData::Call(_, Operation::MoveFunction(mid, fid), args) = exp {
-            let func_env = self.get_function_env(env, *mid, *fid);
-            if self.is_vector_borrow(&func_env, env) {
-                self.check_vector_borrow_args(args, env, arr_length, assigned_symbol, exp, diags);
+/// Walks a function body in evaluation order, keeping track of vector lengths.
+struct IndexingChecker<'a> {
+    env: &'a GlobalEnv,
+    params: Vec<Symbol>,
+    /// Locals holding the length of a vector, e.g. `let n = vector::length(&v)`.
+    length_locals: BTreeMap<Symbol, Symbol>,
+    diags: &'a mut Vec<Diagnostic<FileId>>,
+}
+
+impl IndexingChecker<'_> {
+    fn get_var(&self, exp: &ExpData) -> Option<Symbol> {
+        match exp {
+            ExpData::LocalVar(_, sym) => Some(*sym),
+            ExpData::Temporary(_, index) => self.params.get(*index).copied(),
+            _ => None,
+        }
+    }
+
+    /// Returns the vector a vector function argument refers to, i.e. `v` in `&v`, `&mut v` or a
+    /// reference held in `v`.
+    fn get_vector_var(&self, exp: &ExpData) -> Option<Symbol> {
+        match exp {
+            ExpData::Call(_, Operation::Borrow(_), args) => self.get_var(&args[0]),
+            _ => self.get_var(exp),
+        }
+    }
+
+    /// Returns the name of the vector function called, if the call is to `std::vector`.
+    fn get_vector_function(&self, exp: &ExpData) -> Option<String> {
+        match exp {
+            ExpData::Call(_, Operation::MoveFunction(mid, fid), _) => {
+                let func_env = self.env.get_function(mid.qualified(*fid));
+                is_framework_module(&func_env.module_env, "vector").then(|| func_env.get_name_str())
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the vector whose length an expression reads, directly or through a local.
+    fn get_length_of(&self, exp: &ExpData) -> Option<Symbol> {
+        match exp {
+            ExpData::Call(_, _, args)
+                if self.get_vector_function(exp).as_deref() == Some("length") =>
+            {
+                self.get_vector_var(&args[0])
+            },
+            ExpData::LocalVar(_, sym) => self.length_locals.get(sym).copied(),
+            _ => None,
+        }
+    }
+
+    fn get_index(&self, exp: &ExpData) -> Option<Index> {
+        match exp {
+            ExpData::Value(_, Value::Number(num)) => Some(Index::Constant(num.clone())),
+            _ => self.get_var(exp).map(Index::Var),
+        }
+    }
+
+    /// Returns the length of the vector an expression evaluates to, if known.
+    fn get_length(&self, exp: &ExpData, state: &State) -> Option<usize> {
+        match exp {
+            ExpData::Call(_, Operation::Vector, args) => Some(args.len()),
+            ExpData::Value(_, Value::Vector(elems)) => Some(elems.len()),
+            ExpData::Value(_, Value::ByteArray(bytes)) => Some(bytes.len()),
+            ExpData::Value(_, Value::AddressArray(addrs)) => Some(addrs.len()),
+            ExpData::Call(..) => match self.get_vector_function(exp).as_deref() {
+                Some("empty") => Some(0),
+                Some("singleton") => Some(1),
+                _ => None,
+            },
+            ExpData::LocalVar(_, sym) => state.get(sym).copied(),
+            _ => None,
+        }
+    }
+
+    /// Collects the locals bound to the length of a vector and never reassigned.
+    fn collect_length_locals(&mut self, body: &Exp) {
+        let mut bindings: BTreeMap<Symbol, Option<Symbol>> = BTreeMap::new();
+        body.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            if post {
+                let (pattern, value) = match e {
+                    ExpData::Block(_, pattern, Some(value), _) => (pattern, value),
+                    ExpData::Assign(_, pattern, value) => (pattern, value),
+                    _ => return true,
+                };
+                let vector = match pattern {
+                    Pattern::Var(..) if matches!(e, ExpData::Block(..)) => {
+                        self.get_length_of(value)
+                    },
+                    _ => None,
+                };
+                for (_, sym) in pattern.vars() {
+                    let merged = if bindings.contains_key(&sym) {
+                        None
+                    } else {
+                        vector
+                    };
+                    bindings.insert(sym, merged);
+                }
             }
+            true
+        });
+        self.length_locals = bindings
+            .into_iter()
+            .filter_map(|(sym, vector)| vector.map(|vector| (sym, vector)))
+            .collect();
+    }
+
+    fn report(&mut self, node_id: NodeId, message: &str) {
+        add_diagnostic_and_emit(
+            &self.env.get_node_loc(node_id),
+            message,
+            codespan_reporting::diagnostic::Severity::Warning,
+            self.env,
+            self.diags,
+        );
+    }
+
+    /// Checks a call to a vector function against the known length of its vector and updates
+    /// the length.
+    fn check_vector_call(&mut self, node_id: NodeId, name: &str, args: &[Exp], state: &mut State) {
+        let vector = match args.first().and_then(|arg| self.get_vector_var(arg)) {
+            Some(vector) => vector,
+            None => return,
+        };
+        let length = match state.get(&vector) {
+            Some(length) => *length,
+            None => return,
+        };
+        if INDEXING_FUNCTIONS.contains(&name) {
+            for index in &args[1..] {
+                if let ExpData::Value(_, Value::Number(index)) = index.as_ref() {
+                    if *index >= BigInt::from(length) {
+                        let message = format!(
+                            "Array index out of bounds detected in vector::{}. Index {} is not below the vector length {}.",
+                            name, index, length
+                        );
+                        self.report(node_id, &message);
+                    }
+                }
+            }
+        }
+        match name {
+            "push_back" => {
+                state.insert(vector, length + 1);
+            },
+            "pop_back" | "remove" if length == 0 => {
+                if name == "pop_back" {
+                    self.report(
+                        node_id,
+                        "Array index out of bounds detected in vector::pop_back. The vector is always empty here.",
+                    );
+                }
+                state.remove(&vector);
+            },
+            "pop_back" | "remove" => {
+                state.insert(vector, length - 1);
+            },
+            "borrow" | "borrow_mut" | "swap" | "length" | "is_empty" | "contains" | "index_of" => {
+            },
+            _ => {
+                state.remove(&vector);
+            },
         }
     }
 
-    /// Retrieves the function environment for the given module and function ID.
-    fn get_function_env<'a>(
-        &self,
-        env: &'a GlobalEnv,
-        module_id: ModuleId,
-        function_id: FunId,
-    ) -> FunctionEnv<'a> {
-        let module_env = env.get_module(module_id);
-        module_env.into_function(function_id)
+    /// Checks the code guarded by a condition for indices bounded with `<=` by the length of the
+    /// vector they index.
+    fn check_guard(&mut self, cond: &ExpData, guarded: &Exp) {
+        match cond {
+            ExpData::Call(_, Operation::And, args) => {
+                for arg in args {
+                    self.check_guard(arg, guarded);
+                }
+            },
+            ExpData::Call(_, Operation::Le, args) | ExpData::Call(_, Operation::Ge, args)
+                if args.len() == 2 =>
+            {
+                let (index, length) = match cond {
+                    ExpData::Call(_, Operation::Le, _) => (&args[0], &args[1]),
+                    _ => (&args[1], &args[0]),
+                };
+                if let (Some(index), Some(vector)) =
+                    (self.get_index(index), self.get_length_of(length))
+                {
+                    if !self.grows(guarded, vector) {
+                        self.check_guarded_indexing(guarded, &index, vector);
+                    }
+                }
+            },
+            _ => {},
+        }
     }
 
-    /// Checks the arguments of a vector borrow operation for out-of-bounds access.
-    fn check_vector_borrow_args(
-        &self,
-        args: &Vec<Exp>,
-        env: &GlobalEnv,
-        arr_length: usize,
-        assigned_symbol: &Symbol,
-        exp: &ExpData,
-        diags: &mut Vec<Diagnostic<FileId>>,
-    ) {
-        if args.len() > 1 {
-            if let ExpData::Value(_, Value::Number(index)) = args[1].as_ref() {
-                if let ExpData::Call(_, Operation::Borrow(_), sub_args) = args[0].as_ref() {
-                    if let ExpData::LocalVar(_, symbol) = sub_args[0].as_ref() {
-                        if symbol == assigned_symbol
-                            && self.is_index_out_of_bounds(index, arr_length)
+    /// Returns true if the expression may grow the vector.
+    fn grows(&self, exp: &Exp, vector: Symbol) -> bool {
+        let mut grows = false;
+        exp.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            if post {
+                match e {
+                    ExpData::Assign(_, pattern, _) => {
+                        grows |= pattern.vars().iter().any(|(_, sym)| *sym == vector);
+                    },
+                    ExpData::Call(_, _, args) => {
+                        if let Some(name) = self.get_vector_function(e) {
+                            grows |= GROWING_FUNCTIONS.contains(&name.as_str())
+                                && args.first().and_then(|arg| self.get_vector_var(arg))
+                                    == Some(vector);
+                        }
+                    },
+                    _ => {},
+                }
+            }
+            true
+        });
+        grows
+    }
+
+    /// Checks the code guarded by a `<=` check on the index. Returns false once a nested condition
+    /// on the index leaves the code on one of its branches, as the code following it is then
+    /// bounded more tightly, e.g. by `if (i == n) break`.
+    fn check_guarded_indexing(&mut self, exp: &ExpData, index: &Index, vector: Symbol) -> bool {
+        match exp {
+            // A nested condition on the index variable may bound it more tightly.
+            ExpData::IfElse(_, cond, then_exp, else_exp) => {
+                if !self.check_guarded_indexing(cond, index, vector) {
+                    return false;
+                }
+                let mentions_index = match index {
+                    Index::Var(var) => cond.free_vars_and_used_params(&self.params).contains(var),
+                    Index::Constant(_) => false,
+                };
+                if mentions_index {
+                    return !self.diverges(then_exp) && !self.diverges(else_exp);
+                }
+                self.check_guarded_indexing(then_exp, index, vector);
+                self.check_guarded_indexing(else_exp, index, vector);
+                true
+            },
+            ExpData::Loop(..) | ExpData::Lambda(..) => true,
+            _ => {
+                if let ExpData::Call(node_id, _, args) = exp {
+                    if let Some(name) = self.get_vector_function(exp) {
+                        if INDEXING_FUNCTIONS.contains(&name.as_str())
+                            && args.len() >= 2
+                            && self.get_vector_var(&args[0]) == Some(vector)
+                            && args[1..]
+                                .iter()
+                                .any(|arg| self.get_index(arg).as_ref() == Some(index))
                         {
-                            self.emit_out_of_bounds_warning(exp, env, diags);
+                            let message = format!(
+                                "Array index out of bounds detected in vector::{}. The index is only checked with `<=` against the vector length, so it can equal the length; use `<` instead.",
+                                name
+                            );
+                            self.report(*node_id, &message);
                         }
                     }
                 }
-            }
+                self.get_children(exp)
+                    .into_iter()
+                    .all(|child| self.check_guarded_indexing(child, index, vector))
+            },
         }
     }
 
-    /// Checks if the given index is out of bounds for the array length.
-    fn is_index_out_of_bounds(&self, index: &BigInt, arr_length: usize) -> bool {
-        *index >= BigInt::from(arr_length)
+    /// Returns true if control never continues after the expression, i.e. it aborts, returns or
+    /// leaves or restarts a loop.
+    fn diverges(&self, exp: &ExpData) -> bool {
+        match exp {
+            ExpData::Call(_, Operation::Abort, _) | ExpData::Return(..) | ExpData::LoopCont(..) => {
+                true
+            },
+            ExpData::Sequence(_, exps) => exps.iter().any(|exp| self.diverges(exp)),
+            ExpData::Block(_, _, _, body) => self.diverges(body),
+            ExpData::IfElse(_, _, then_exp, else_exp) => {
+                self.diverges(then_exp) && self.diverges(else_exp)
+            },
+            _ => false,
+        }
     }
 
-    /// Emits a warning for an out-of-bounds access attempt.
-    fn emit_out_of_bounds_warning(
-        &self,
-        exp: &ExpData,
-        env: &GlobalEnv,
-        diags: &mut Vec<Diagnostic<FileId>>,
-    ) {
-        let message = "Array index out of bounds detected in vector::borrow.";
-        add_diagnostic_and_emit(
-            &env.get_node_loc(exp.node_id()),
-            message,
-            codespan_reporting::diagnostic::Severity::Warning,
-            env,
-            diags,
-        );
+    fn get_children<'e>(&self, exp: &'e ExpData) -> Vec<&'e Exp> {
+        match exp {
+            ExpData::Call(_, _, args) => args.iter().collect(),
+            ExpData::Sequence(_, exps) => exps.iter().collect(),
+            ExpData::Block(_, _, binding, body) => binding.iter().chain(Some(body)).collect(),
+            ExpData::Assign(_, _, value) | ExpData::Return(_, value) => vec![value],
+            ExpData::Mutate(_, lhs, rhs) => vec![lhs, rhs],
+            _ => vec![],
+        }
+    }
+
+    /// Returns the vectors a loop body may modify: assigned or borrowed mutably.
+    fn collect_modified_vectors(&self, exp: &Exp) -> Vec<Symbol> {
+        let mut modified = vec![];
+        exp.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            if post {
+                match e {
+                    ExpData::Assign(_, pattern, _) => {
+                        modified.extend(pattern.vars().into_iter().map(|(_, sym)| sym));
+                    },
+                    ExpData::Call(_, Operation::Borrow(ReferenceKind::Mutable), args) => {
+                        modified.extend(args.iter().filter_map(|arg| self.get_var(arg)));
+                    },
+                    _ => {},
+                }
+            }
+            true
+        });
+        modified
     }
 
-    /// Checks if the function call is `vector::borrow`.
-    fn is_vector_borrow(&self, func_env: &FunctionEnv, global_env: &GlobalEnv) -> bool {
-        // Assuming 'vector' is a standard library module, you would need to adjust these accordingly
-        if func_env.module_env.self_address() == &global_env.get_stdlib_address()
-            && global_env
-                .symbol_pool()
-                .string(func_env.module_env.get_name().name())
-                .to_string()
-                == *"vector".to_string()
-        {
-            return func_env.get_name_str() == "borrow" || func_env.get_name_str() == "borrow_mut";
+    fn assign(&self, pattern: &Pattern, value: Option<&Exp>, state: &mut State) {
+        for (_, sym) in pattern.vars() {
+            state.remove(&sym);
+        }
+        if let (Pattern::Var(_, sym), Some(value)) = (pattern, value) {
+            if let Some(length) = self.get_length(value, state) {
+                state.insert(*sym, length);
+            }
+        }
+    }
+
+    /// Analyzes an expression, updating the known lengths. Returns false if control never
+    /// continues after the expression, i.e. it aborts, returns or leaves a loop.
+    fn analyze(&mut self, exp: &ExpData, state: &mut State) -> bool {
+        match exp {
+            ExpData::Call(_, Operation::Abort, args) => {
+                for arg in args {
+                    self.analyze(arg, state);
+                }
+                false
+            },
+            ExpData::Call(node_id, oper, args) => {
+                let name = self.get_vector_function(exp);
+                for arg in args {
+                    // Borrowing a vector for a vector function is accounted for by the call.
+                    let is_vector_arg = name.is_some()
+                        && matches!(arg.as_ref(), ExpData::Call(_, Operation::Borrow(_), _));
+                    if !is_vector_arg && !self.analyze(arg, state) {
+                        return false;
+                    }
+                }
+                match (name, oper) {
+                    (Some(name), _) => self.check_vector_call(*node_id, &name, args, state),
+                    (None, Operation::Borrow(ReferenceKind::Mutable)) => {
+                        for arg in args {
+                            if let Some(var) = self.get_var(arg) {
+                                state.remove(&var);
+                            }
+                        }
+                    },
+                    _ => {},
+                }
+                true
+            },
+            ExpData::Invoke(_, target, args) => {
+                self.analyze(target, state) && args.iter().all(|arg| self.analyze(arg, state))
+            },
+            ExpData::Lambda(_, _, body) => {
+                self.analyze(body, &mut state.clone());
+                true
+            },
+            ExpData::Return(_, exp) => {
+                self.analyze(exp, state);
+                false
+            },
+            ExpData::LoopCont(..) => false,
+            ExpData::Sequence(_, exps) => exps.iter().all(|exp| self.analyze(exp, state)),
+            ExpData::Block(_, pattern, binding, body) => {
+                if let Some(binding) = binding {
+                    if !self.analyze(binding, state) {
+                        return false;
+                    }
+                }
+                // Restore shadowed variables once the block ends.
+                let shadowed: Vec<_> = pattern
+                    .vars()
+                    .into_iter()
+                    .map(|(_, sym)| (sym, state.get(&sym).copied()))
+                    .collect();
+                self.assign(pattern, binding.as_ref(), state);
+                let continues = self.analyze(body, state);
+                for (sym, length) in shadowed {
+                    match length {
+                        Some(length) => state.insert(sym, length),
+                        None => state.remove(&sym),
+                    };
+                }
+                continues
+            },
+            ExpData::Assign(_, pattern, rhs) => {
+                if !self.analyze(rhs, state) {
+                    return false;
+                }
+                self.assign(pattern, Some(rhs), state);
+                true
+            },
+            ExpData::Mutate(_, lhs, rhs) => self.analyze(rhs, state) && self.analyze(lhs, state),
+            ExpData::IfElse(_, cond, then_exp, else_exp) => {
+                if !self.analyze(cond, state) {
+                    return false;
+                }
+                self.check_guard(cond, then_exp);
+                let mut then_state = state.clone();
+                let then_continues = self.analyze(then_exp, &mut then_state);
+                let mut else_state = state.clone();
+                let else_continues = self.analyze(else_exp, &mut else_state);
+                *state = match (then_continues, else_continues) {
+                    (true, true) => then_state
+                        .into_iter()
+                        .filter(|(sym, length)| else_state.get(sym) == Some(length))
+                        .collect(),
+                    (true, false) => then_state,
+                    (false, true) => else_state,
+                    (false, false) => return false,
+                };
+                true
+            },
+            ExpData::Loop(_, body) => {
+                // Vectors modified in the loop may have any length at its start and end.
+                for sym in self.collect_modified_vectors(body) {
+                    state.remove(&sym);
+                }
+                self.analyze(body, &mut state.clone());
+                true
+            },
+            _ => true,
         }
-        false
     }
 }
 
 impl ExpressionAnalysisVisitor for OutOfBoundsArrayIndexingVisitor {
-    fn post_visit_expression(
+    fn visit_function_custom(
         &mut self,
-        exp: &ExpData,
-        _func_env: &FunctionEnv,
+        func_env: &FunctionEnv,
         env: &GlobalEnv,
         _: &LintConfig,
         diags: &mut Vec<Diagnostic<FileId>>,
     ) {
-        if let ExpData::Block(_, Pattern::Var(_, symbol), some_binding_exp, _) = exp {
-            let binding_exp = some_binding_exp.as_ref().expect("binding_exp");
-            if let ExpData::Call(_, Operation::Vector, arr_length) = binding_exp.as_ref() {
-                exp.visit_pre_post(&mut |is_pre_visit, exp: &ExpData| {
-                    if !is_pre_visit {
-                        self.check_out_of_bounds_indexing(
-                            exp,
-                            env,
-                            arr_length.len(),
-                            symbol,
-                            diags,
-                        );
-                    };
-                    true
-                });
-            }
+        if let Some(def) = func_env.get_def() {
+            let mut checker = IndexingChecker {
+                env,
+                params: func_env
+                    .get_parameters()
+                    .into_iter()
+                    .map(|param| param.0)
+                    .collect(),
+                length_locals: BTreeMap::new(),
+                diags,
+            };
+            checker.collect_length_locals(def);
+            checker.analyze(def, &mut State::new());
         }
     }
 }
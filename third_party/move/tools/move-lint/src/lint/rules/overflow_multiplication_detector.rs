@@ -1,26 +1,20 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-//! `OverflowMultiplicationDetectorVisitor` identifies and warns about potential overflow in multiplication operations in Move programs.
-//! It leverages declared variable values and type information to predict overflow possibilities.
+//! `OverflowMultiplicationDetectorVisitor` identifies and warns about multiplications and additions in Move programs
+//! which always overflow. It runs the interval analysis over the bytecode of each function, so values flowing from
+//! constants, locals and guards are taken into account for every integer type from `u8` to `u256`.
 use crate::lint::{
-    utils::{add_diagnostic_and_emit, LintConfig},
+    interval_analysis::{for_each_instruction_with_intervals, get_type_max},
+    utils::add_diagnostic_and_emit,
     visitor::ExpressionAnalysisVisitor,
 };
 use codespan::FileId;
 use codespan_reporting::diagnostic::Diagnostic;
-use move_model::{
-    ast::{Exp, ExpData, Operation, Pattern, Value},
-    model::{FunctionEnv, GlobalEnv},
-    symbol::Symbol,
-    ty::{PrimitiveType, Type},
-};
-use num::ToPrimitive;
-use num_bigint::BigInt;
-use std::collections::BTreeMap;
-pub struct OverflowMultiplicationDetectorVisitor {
-    declared_vars: BTreeMap<Symbol, BigInt>,
-}
+use move_model::model::{FunctionEnv, GlobalEnv};
+use move_stackless_bytecode::stackless_bytecode::{Bytecode, Operation};
+
+pub struct OverflowMultiplicationDetectorVisitor;
 
 impl Default for OverflowMultiplicationDetectorVisitor {
     fn default() -> Self {
@@ -30,93 +24,62 @@ impl Default for OverflowMultiplicationDetectorVisitor {
 
 impl OverflowMultiplicationDetectorVisitor {
     pub fn new() -> Self {
-        Self {
-            declared_vars: BTreeMap::new(),
-        }
+        Self {}
     }
 
     pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
         Box::new(Self::new())
     }
+}
 
-    pub fn insert_declared_var(&mut self, symbol: Symbol, exp: &BigInt) {
-        self.declared_vars.insert(symbol, exp.clone());
+impl ExpressionAnalysisVisitor for OverflowMultiplicationDetectorVisitor {
+    fn requires_bytecode_inspection(&self) -> bool {
+        true
     }
 
-    /// Checks for multiplications that can overflow.
-    fn check_overflow_multiplication(
-        &self,
-        exp: &ExpData,
+    fn visit_function_with_bytecode(
+        &mut self,
+        func_env: &FunctionEnv,
         env: &GlobalEnv,
         diags: &mut Vec<Diagnostic<FileId>>,
     ) {
-        if let ExpData::Call(_, Operation::Mul, args) = exp {
-            if args.len() == 2 {
-                let lhs_exp = &args[0];
-                let rhs_exp = &args[1];
-
-                if let (Some(lhs_value), Some(rhs_value)) = (
-                    self.get_numeric_value(lhs_exp),
-                    self.get_numeric_value(rhs_exp),
+        for_each_instruction_with_intervals(func_env, |target, instr, state| {
+            if let Bytecode::Call(attr_id, dsts, op @ (Operation::Mul | Operation::Add), srcs, _) =
+                instr
+            {
+                let (dst, lhs, rhs) = match (dsts.as_slice(), srcs.as_slice()) {
+                    ([dst], [lhs, rhs]) => (*dst, *lhs, *rhs),
+                    _ => return,
+                };
+                let ty = target.get_local_type(dst);
+                let (type_max, lhs, rhs) = match (
+                    get_type_max(ty),
+                    state.range(lhs, target),
+                    state.range(rhs, target),
                 ) {
-                    let lhs_type = env.get_node_type(lhs_exp.node_id());
-                    let rhs_type = env.get_node_type(rhs_exp.node_id());
-                    if self.could_overflow(lhs_value, rhs_value, &lhs_type, &rhs_type) {
-                        let message = "Potential multiplication overflow detected.";
-                        add_diagnostic_and_emit(
-                            &env.get_node_loc(exp.node_id()),
-                            message,
-                            codespan_reporting::diagnostic::Severity::Warning,
-                            env,
-                            diags,
-                        );
-                    }
+                    (Some(type_max), Some(lhs), Some(rhs)) => (type_max, lhs, rhs),
+                    _ => return,
+                };
+                let (operation, result) = if matches!(op, Operation::Mul) {
+                    ("multiplication", lhs.mul(&rhs))
+                } else {
+                    ("addition", lhs.add(&rhs))
+                };
+                if result.lo > type_max {
+                    let message = format!(
+                        "Potential {} overflow detected. The result never fits into `{}`, so this operation always aborts.",
+                        operation,
+                        ty.display(&env.get_type_display_ctx())
+                    );
+                    add_diagnostic_and_emit(
+                        &target.get_bytecode_loc(*attr_id),
+                        &message,
+                        codespan_reporting::diagnostic::Severity::Warning,
+                        env,
+                        diags,
+                    );
                 }
             }
-        }
-    }
-
-    /// Retrieves the numeric value from an expression, if it is a constant.
-    fn get_numeric_value(&self, exp: &Exp) -> Option<u128> {
-        if let ExpData::LocalVar(_, symbol) = exp.as_ref() {
-            self.declared_vars
-                .get(symbol)
-                .and_then(|value| value.to_u128())
-        } else {
-            None
-        }
-    }
-
-    /// Checks if the multiplication of two numbers could overflow based on their types.
-    fn could_overflow(&self, lhs: u128, rhs: u128, lhs_type: &Type, rhs_type: &Type) -> bool {
-        let max_value = match (lhs_type, rhs_type) {
-            (Type::Primitive(PrimitiveType::U8), Type::Primitive(PrimitiveType::U8)) => {
-                u8::MAX as u128
-            },
-            (Type::Primitive(PrimitiveType::U64), Type::Primitive(PrimitiveType::U64)) => {
-                u64::MAX as u128
-            },
-            _ => return false,
-        };
-        lhs.checked_mul(rhs)
-            .map_or(true, |result| result > max_value)
-    }
-}
-
-impl ExpressionAnalysisVisitor for OverflowMultiplicationDetectorVisitor {
-    fn post_visit_expression(
-        &mut self,
-        exp: &ExpData,
-        _func_env: &FunctionEnv,
-        env: &GlobalEnv,
-        _: &LintConfig,
-        diags: &mut Vec<Diagnostic<FileId>>,
-    ) {
-        if let ExpData::Block(_, Pattern::Var(_, symbol), Some(binding_exp), _) = exp {
-            if let ExpData::Value(_, Value::Number(num)) = binding_exp.as_ref() {
-                self.insert_declared_var(*symbol, num);
-            }
-        }
-        self.check_overflow_multiplication(exp, env, diags);
+        });
     }
 }
@@ -0,0 +1,267 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Detect reentrancy-style hazards around dispatchable fungible asset hooks. Calls into
+//! `dispatchable_fungible_asset::withdraw/deposit/transfer`, or into any function taking a
+//! `function_info::FunctionInfo`, may run user supplied code in the middle of the caller.
+//! This lint warns when a `borrow_global_mut` reference is still used after such a call, and
+//! when global state is written both before and after the call, leaving invariants partially
+//! updated while the hook runs. Both are decided on paths of the control flow graph, so a use or
+//! write on a branch which does not go through the call is not reported.
+use crate::lint::{
+    utils::{add_diagnostic_and_emit, is_framework_function, is_framework_struct_type},
+    visitor::ExpressionAnalysisVisitor,
+};
+use codespan::FileId;
+use codespan_reporting::diagnostic::Diagnostic;
+use move_binary_format::file_format::CodeOffset;
+use move_model::{
+    ast::TempIndex,
+    model::{FunctionEnv, GlobalEnv},
+};
+use move_stackless_bytecode::{
+    function_target::FunctionTarget,
+    stackless_bytecode::{AttrId, Bytecode, Operation},
+    stackless_bytecode_generator::StacklessBytecodeGenerator,
+    stackless_control_flow_graph::{BlockContent, StacklessControlFlowGraph},
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+const DISPATCH_FUNCTIONS: [&str; 4] = [
+    "withdraw",
+    "deposit",
+    "transfer",
+    "transfer_assert_minimum_deposit",
+];
+
+#[derive(Debug)]
+pub struct ReentrancyDispatchVisitor;
+
+impl Default for ReentrancyDispatchVisitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReentrancyDispatchVisitor {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
+        Box::new(Self::new())
+    }
+
+    /// Returns true if the call may execute a user defined dispatch hook.
+    fn is_dispatch_call(&self, callee: &FunctionEnv, env: &GlobalEnv) -> bool {
+        is_framework_function(callee, "dispatchable_fungible_asset", &DISPATCH_FUNCTIONS)
+            || callee
+                .get_parameter_types()
+                .iter()
+                .any(|ty| is_framework_struct_type(ty, env, "function_info", "FunctionInfo"))
+    }
+
+    /// Returns true if the bytecode writes to global storage through a tracked reference
+    /// or by publishing/removing a resource.
+    fn is_global_write(
+        &self,
+        bytecode: &Bytecode,
+        global_mut_refs: &BTreeMap<usize, AttrId>,
+    ) -> bool {
+        match bytecode {
+            Bytecode::Call(_, _, Operation::WriteRef, srcs, _) => srcs
+                .first()
+                .map_or(false, |src| global_mut_refs.contains_key(src)),
+            Bytecode::Call(_, _, Operation::MoveTo(..) | Operation::MoveFrom(..), _, _) => true,
+            _ => false,
+        }
+    }
+
+    /// Records the temporaries holding mutable references derived from `borrow_global_mut`.
+    fn track_global_mut_refs(
+        &self,
+        bytecode: &Bytecode,
+        func_target: &FunctionTarget,
+        global_mut_refs: &mut BTreeMap<usize, AttrId>,
+    ) {
+        match bytecode {
+            Bytecode::Call(attr_id, dests, Operation::BorrowGlobal(..), _, _) => {
+                for dest in dests {
+                    if func_target.get_local_type(*dest).is_mutable_reference() {
+                        global_mut_refs.insert(*dest, *attr_id);
+                    }
+                }
+            },
+            Bytecode::Assign(_, dest, src, _) => {
+                if let Some(attr_id) = global_mut_refs.get(src).cloned() {
+                    global_mut_refs.insert(*dest, attr_id);
+                }
+            },
+            Bytecode::Call(
+                _,
+                dests,
+                Operation::BorrowField(..) | Operation::Function(..),
+                srcs,
+                _,
+            ) => {
+                let origin = srcs
+                    .iter()
+                    .find_map(|src| global_mut_refs.get(src).cloned());
+                if let Some(attr_id) = origin {
+                    for dest in dests {
+                        if func_target.get_local_type(*dest).is_mutable_reference() {
+                            global_mut_refs.insert(*dest, attr_id);
+                        }
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+
+    /// Computes the successors of each instruction from the control flow graph.
+    fn get_instruction_successors(&self, code: &[Bytecode]) -> Vec<Vec<CodeOffset>> {
+        let cfg = StacklessControlFlowGraph::new_forward(code);
+        let mut successors = vec![vec![]; code.len()];
+        for block_id in cfg.blocks() {
+            if let BlockContent::Basic { lower, upper } = *cfg.content(block_id) {
+                for offset in lower..upper {
+                    successors[offset as usize].push(offset + 1);
+                }
+                for succ in cfg.successors(block_id) {
+                    if let BlockContent::Basic { lower, .. } = *cfg.content(*succ) {
+                        successors[upper as usize].push(lower);
+                    }
+                }
+            }
+        }
+        successors
+    }
+
+    /// Returns true if an instruction satisfying `found` can be reached from the instruction at
+    /// `from`. With a `temp`, only paths on which it is not redefined are followed.
+    fn reaches(
+        &self,
+        code: &[Bytecode],
+        successors: &[Vec<CodeOffset>],
+        from: CodeOffset,
+        temp: Option<TempIndex>,
+        found: impl Fn(CodeOffset) -> bool,
+    ) -> bool {
+        let mut visited = BTreeSet::new();
+        let mut worklist = successors[from as usize].clone();
+        while let Some(offset) = worklist.pop() {
+            if !visited.insert(offset) {
+                continue;
+            }
+            if found(offset) {
+                return true;
+            }
+            if temp.map_or(false, |temp| code[offset as usize].dests().contains(&temp)) {
+                continue;
+            }
+            worklist.extend(successors[offset as usize].iter().cloned());
+        }
+        false
+    }
+
+    fn check_dispatch_hazards(
+        &self,
+        func_target: &FunctionTarget,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let code = func_target.get_bytecode();
+        let successors = self.get_instruction_successors(code);
+        // References derived from `borrow_global_mut`, with the instructions defining them.
+        let mut global_mut_refs = BTreeMap::new();
+        let mut ref_defs: BTreeMap<TempIndex, Vec<CodeOffset>> = BTreeMap::new();
+        for (offset, bytecode) in code.iter().enumerate() {
+            self.track_global_mut_refs(bytecode, func_target, &mut global_mut_refs);
+            for dest in bytecode.dests() {
+                if global_mut_refs.contains_key(&dest) {
+                    ref_defs.entry(dest).or_default().push(offset as CodeOffset);
+                }
+            }
+        }
+        let writes: Vec<CodeOffset> = (0..code.len() as CodeOffset)
+            .filter(|offset| self.is_global_write(&code[*offset as usize], &global_mut_refs))
+            .collect();
+        let uses = |offset: CodeOffset, temp: TempIndex| {
+            let bytecode = &code[offset as usize];
+            !bytecode.is_spec_only() && bytecode.sources().contains(&temp)
+        };
+        for (offset, bytecode) in code.iter().enumerate() {
+            let dispatch = offset as CodeOffset;
+            if let Bytecode::Call(attr_id, _, Operation::Function(mid, fid, _), srcs, _) = bytecode
+            {
+                let callee = env.get_function(mid.qualified(*fid));
+                if !self.is_dispatch_call(&callee, env) {
+                    continue;
+                }
+                // A reference is held across the call if a definition of it reaches the call
+                // and it is passed to the call or used on a path after it.
+                let held_across = ref_defs.iter().any(|(temp, defs)| {
+                    let reaches_call = defs.iter().any(|def| {
+                        self.reaches(code, &successors, *def, Some(*temp), |o| o == dispatch)
+                    });
+                    reaches_call
+                        && (srcs.contains(temp)
+                            || self.reaches(code, &successors, dispatch, Some(*temp), |o| {
+                                uses(o, *temp)
+                            }))
+                });
+                let written_before = writes
+                    .iter()
+                    .any(|write| self.reaches(code, &successors, *write, None, |o| o == dispatch));
+                let written_after =
+                    self.reaches(code, &successors, dispatch, None, |o| writes.contains(&o));
+                if held_across {
+                    let message = format!(
+                        "Mutable global reference is held across a call to `{}`, which may run a user defined dispatch hook.",
+                        callee.get_full_name_str()
+                    );
+                    add_diagnostic_and_emit(
+                        &func_target.get_bytecode_loc(*attr_id),
+                        &message,
+                        codespan_reporting::diagnostic::Severity::Warning,
+                        env,
+                        diags,
+                    );
+                } else if written_before && written_after {
+                    let message = format!(
+                        "Global state is updated both before and after a call to `{}`; the dispatch hook may observe partially updated state.",
+                        callee.get_full_name_str()
+                    );
+                    add_diagnostic_and_emit(
+                        &func_target.get_bytecode_loc(*attr_id),
+                        &message,
+                        codespan_reporting::diagnostic::Severity::Warning,
+                        env,
+                        diags,
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl ExpressionAnalysisVisitor for ReentrancyDispatchVisitor {
+    fn requires_bytecode_inspection(&self) -> bool {
+        true
+    }
+
+    fn visit_function_with_bytecode(
+        &mut self,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        if func_env.is_inline() || func_env.is_native() {
+            return;
+        }
+        let data = StacklessBytecodeGenerator::new(func_env).generate_function();
+        let target = FunctionTarget::new(func_env, &data);
+        self.check_dispatch_hazards(&target, env, diags);
+    }
+}
@@ -0,0 +1,302 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Check `#[resource_group]` containers and `#[resource_group_member]` structs. Mirrors the
+//! framework's extended checks, which only fail when the package is published: a container must
+//! be a struct without fields, abilities or type parameters with a valid `scope`, and a member
+//! must have the `key` ability and a `group` parameter naming an existing container whose scope
+//! includes the member.
+use crate::lint::{utils::add_diagnostic_and_emit, visitor::ExpressionAnalysisVisitor};
+use codespan::FileId;
+use codespan_reporting::diagnostic::Diagnostic;
+use move_model::{
+    ast::{Attribute, AttributeValue},
+    model::{AbilitySet, GlobalEnv, Loc, ModuleEnv, StructEnv},
+};
+
+const RESOURCE_GROUP: &str = "resource_group";
+const RESOURCE_GROUP_MEMBER: &str = "resource_group_member";
+const RESOURCE_GROUP_NAME: &str = "group";
+const RESOURCE_GROUP_SCOPE: &str = "scope";
+
+/// The scope of a resource group container, restricting which structs can be members.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ResourceGroupScope {
+    Module,
+    Address,
+    Global,
+}
+
+impl ResourceGroupScope {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "module_scope" => Some(Self::Module),
+            "address" => Some(Self::Address),
+            "global" => Some(Self::Global),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the member is allowed in a container with this scope.
+    fn contains(&self, member: &StructEnv, container: &StructEnv) -> bool {
+        match self {
+            Self::Module => member.module_env.get_id() == container.module_env.get_id(),
+            Self::Address => {
+                member.module_env.self_address() == container.module_env.self_address()
+            },
+            Self::Global => true,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ResourceGroupVisitor;
+
+impl Default for ResourceGroupVisitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResourceGroupVisitor {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
+        Box::new(Self::new())
+    }
+
+    fn find_attribute<'a>(
+        &self,
+        struct_env: &'a StructEnv,
+        attr_name: &str,
+    ) -> Option<&'a Attribute> {
+        struct_env.get_attributes().iter().find(|attr| {
+            matches!(attr, Attribute::Apply(..))
+                && struct_env.symbol_pool().string(attr.name()).as_str() == attr_name
+        })
+    }
+
+    /// Returns the value of the single `<param_name> = <value>` parameter of the attribute, or
+    /// the message describing why the parameter is invalid.
+    fn get_single_param<'a>(
+        &self,
+        attr: &'a Attribute,
+        param_name: &str,
+        env: &GlobalEnv,
+    ) -> Result<&'a AttributeValue, String> {
+        let attr_name = env.symbol_pool().string(attr.name());
+        match attr {
+            Attribute::Apply(_, _, params) if params.len() == 1 => match &params[0] {
+                Attribute::Assign(_, name, value)
+                    if env.symbol_pool().string(*name).as_str() == param_name =>
+                {
+                    Ok(value)
+                },
+                _ => Err(format!(
+                    "`#[{}]` lacks the `{}` parameter.",
+                    attr_name, param_name
+                )),
+            },
+            _ => Err(format!(
+                "`#[{}]` must have exactly one parameter, `{} = ...`.",
+                attr_name, param_name
+            )),
+        }
+    }
+
+    fn warn(&self, loc: &Loc, message: &str, env: &GlobalEnv, diags: &mut Vec<Diagnostic<FileId>>) {
+        add_diagnostic_and_emit(
+            loc,
+            message,
+            codespan_reporting::diagnostic::Severity::Warning,
+            env,
+            diags,
+        );
+    }
+
+    /// Validates a resource group container, returning its scope or the problems found.
+    fn get_container_scope(
+        &self,
+        struct_env: &StructEnv,
+        attr: &Attribute,
+        env: &GlobalEnv,
+    ) -> Result<ResourceGroupScope, Vec<(Loc, String)>> {
+        let name = struct_env.get_name().display(env.symbol_pool()).to_string();
+        let mut problems = Vec::new();
+        // Every struct without fields contains the compiler generated `dummy_field`.
+        let has_fields = struct_env.get_field_count() > 1
+            || struct_env
+                .get_fields()
+                .any(|field| env.symbol_pool().string(field.get_name()).as_str() != "dummy_field");
+        if has_fields {
+            problems.push((
+                struct_env.get_loc(),
+                format!(
+                    "Resource group container `{}` should not have fields.",
+                    name
+                ),
+            ));
+        }
+        if struct_env.get_abilities() != AbilitySet::EMPTY {
+            problems.push((
+                struct_env.get_loc(),
+                format!(
+                    "Resource group container `{}` should not have abilities.",
+                    name
+                ),
+            ));
+        }
+        if !struct_env.get_type_parameters().is_empty() {
+            problems.push((
+                struct_env.get_loc(),
+                format!(
+                    "Resource group container `{}` should not have type parameters.",
+                    name
+                ),
+            ));
+        }
+        let attr_loc = env.get_node_loc(attr.node_id());
+        let scope = match self.get_single_param(attr, RESOURCE_GROUP_SCOPE, env) {
+            Ok(AttributeValue::Name(_, _, scope_name)) => {
+                let scope_name = env.symbol_pool().string(*scope_name);
+                let scope = ResourceGroupScope::from_name(scope_name.as_str());
+                if scope.is_none() {
+                    problems.push((
+                        attr_loc,
+                        format!(
+                            "Invalid resource group scope `{}`. Expected `module_scope`, `address` or `global`.",
+                            scope_name
+                        ),
+                    ));
+                }
+                scope
+            },
+            Ok(_) => {
+                problems.push((
+                    attr_loc,
+                    "Resource group scope must be one of `module_scope`, `address` or `global`."
+                        .to_string(),
+                ));
+                None
+            },
+            Err(message) => {
+                problems.push((attr_loc, message));
+                None
+            },
+        };
+        match scope {
+            Some(scope) if problems.is_empty() => Ok(scope),
+            _ => Err(problems),
+        }
+    }
+
+    /// Reports the problems of a resource group container.
+    fn check_container(
+        &self,
+        struct_env: &StructEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        if let Some(attr) = self.find_attribute(struct_env, RESOURCE_GROUP) {
+            if let Err(problems) = self.get_container_scope(struct_env, attr, env) {
+                for (loc, message) in problems {
+                    self.warn(&loc, &message, env, diags);
+                }
+            }
+        }
+    }
+
+    /// Checks that a resource group member refers to a valid container.
+    fn check_member(
+        &self,
+        struct_env: &StructEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let attr = match self.find_attribute(struct_env, RESOURCE_GROUP_MEMBER) {
+            Some(attr) => attr,
+            None => return,
+        };
+        let name = struct_env.get_name().display(env.symbol_pool()).to_string();
+        if !struct_env.get_abilities().has_key() {
+            let message = format!(
+                "Resource group member `{}` must have the `key` ability.",
+                name
+            );
+            self.warn(&struct_env.get_loc(), &message, env, diags);
+        }
+        let attr_loc = env.get_node_loc(attr.node_id());
+        let (module_name, container_name) = match self.get_single_param(
+            attr,
+            RESOURCE_GROUP_NAME,
+            env,
+        ) {
+            Ok(AttributeValue::Name(_, Some(module_name), container_name)) => {
+                (module_name, *container_name)
+            },
+            Ok(_) => {
+                let message = "Resource group member `group` must be a fully qualified struct name, such as `0x1::object::ObjectGroup`.";
+                self.warn(&attr_loc, message, env, diags);
+                return;
+            },
+            Err(message) => {
+                self.warn(&attr_loc, &message, env, diags);
+                return;
+            },
+        };
+        let container = match env
+            .find_module(module_name)
+            .and_then(|module| module.find_struct(container_name))
+        {
+            Some(container) => container,
+            None => {
+                let message = format!(
+                    "Resource group `{}::{}` of member `{}` does not exist.",
+                    module_name.display(env),
+                    env.symbol_pool().string(container_name),
+                    name
+                );
+                self.warn(&attr_loc, &message, env, diags);
+                return;
+            },
+        };
+        let container_attr = match self.find_attribute(&container, RESOURCE_GROUP) {
+            Some(container_attr) => container_attr,
+            None => {
+                let message = format!(
+                    "`{}` is not a `#[resource_group]` container.",
+                    container.get_full_name_str()
+                );
+                self.warn(&attr_loc, &message, env, diags);
+                return;
+            },
+        };
+        // Problems of the container itself are reported on the container.
+        if let Ok(scope) = self.get_container_scope(&container, container_attr, env) {
+            if !scope.contains(struct_env, &container) {
+                let message = format!(
+                    "Resource group member `{}` is outside the scope of `{}`.",
+                    name,
+                    container.get_full_name_str()
+                );
+                self.warn(&attr_loc, &message, env, diags);
+            }
+        }
+    }
+}
+
+impl ExpressionAnalysisVisitor for ResourceGroupVisitor {
+    fn visit_module(
+        &mut self,
+        module: &ModuleEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        for struct_env in module.get_structs() {
+            self.check_container(&struct_env, env, diags);
+            self.check_member(&struct_env, env, diags);
+        }
+    }
+}
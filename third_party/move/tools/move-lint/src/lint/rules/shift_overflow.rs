@@ -1,21 +1,20 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-//! Detect potential overflow scenarios where the number of bits being shifted exceeds the bit width of
-//! the variable being shifted, which could lead to unintended behavior or loss of data. If such a
-//! potential overflow is detected, a warning is generated to alert the developer.
+//! Detect potential overflow scenarios where the number of bits being shifted is at least the bit width of
+//! the variable being shifted, which makes the shift abort. The shift amount is bounded with the interval analysis,
+//! so amounts held in locals or derived from constants are checked as well as literals. If such an overflow is
+//! detected, a warning is generated to alert the developer.
 use crate::lint::{
-    utils::{add_diagnostic_and_emit, LintConfig},
+    interval_analysis::{for_each_instruction_with_intervals, get_bit_width, get_type_max},
+    utils::add_diagnostic_and_emit,
     visitor::ExpressionAnalysisVisitor,
 };
 use codespan::FileId;
 use codespan_reporting::diagnostic::Diagnostic;
-use move_model::{
-    ast::{Exp, ExpData, Operation, Value},
-    model::{FunctionEnv, GlobalEnv, NodeId},
-    ty::{PrimitiveType, Type},
-};
-use num::ToPrimitive;
+use move_model::model::{FunctionEnv, GlobalEnv};
+use move_stackless_bytecode::stackless_bytecode::{Bytecode, Operation};
+use num_bigint::BigInt;
 
 pub struct ShiftOverflowVisitor {}
 
@@ -33,86 +32,47 @@ impl ShiftOverflowVisitor {
     pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
         Box::new(Self::new())
     }
-
-    fn check_shift_overflow(
-        &mut self,
-        exp: &ExpData,
-        env: &GlobalEnv,
-        diags: &mut Vec<Diagnostic<FileId>>,
-    ) {
-        if let Some((call_node_id, op, exp_vec)) = self.extract_call_data(exp) {
-            if let Some(number_size) = self.get_bit_width(op, env, call_node_id) {
-                if let Some(value) = self.extract_value(&exp_vec[1]) {
-                    if self.is_overflow(&value, number_size) {
-                        let message = "Potential overflow detected. The number of bits being shifted exceeds the bit width of the variable being shifted";
-                        add_diagnostic_and_emit(
-                            &env.get_node_loc(call_node_id),
-                            message,
-                            codespan_reporting::diagnostic::Severity::Warning,
-                            env,
-                            diags,
-                        );
-                    }
-                }
-            }
-        }
-    }
-
-    fn extract_call_data<'a>(
-        &self,
-        exp: &'a ExpData,
-    ) -> Option<(NodeId, &'a Operation, &'a Vec<Exp>)> {
-        if let ExpData::Block(_, _, Some(call), _) = exp {
-            if let ExpData::Call(call_node_id, op, exp_vec) = call.as_ref() {
-                return Some((*call_node_id, op, exp_vec));
-            }
-        }
-        None
-    }
-
-    fn get_bit_width(&self, op: &Operation, env: &GlobalEnv, node_id: NodeId) -> Option<u128> {
-        match op {
-            Operation::Shl | Operation::Shr => {
-                let ty = env.get_node_type(node_id);
-                match ty {
-                    Type::Primitive(PrimitiveType::U8) => Some(8),
-                    Type::Primitive(PrimitiveType::U16) => Some(16),
-                    Type::Primitive(PrimitiveType::U32) => Some(32),
-                    Type::Primitive(PrimitiveType::U64) => Some(64),
-                    Type::Primitive(PrimitiveType::U128) => Some(128),
-                    Type::Primitive(PrimitiveType::U256) => Some(256),
-                    _ => None,
-                }
-            },
-            _ => None,
-        }
-    }
-
-    fn extract_value(&self, exp_data: &ExpData) -> Option<Value> {
-        if let ExpData::Value(_, value) = exp_data {
-            Some(value.clone())
-        } else {
-            None
-        }
-    }
-
-    fn is_overflow(&self, value: &Value, bit_width: u128) -> bool {
-        match value {
-            Value::Number(v) => v.to_u128().map_or(false, |num| num > bit_width),
-            _ => false,
-        }
-    }
 }
 
 impl ExpressionAnalysisVisitor for ShiftOverflowVisitor {
-    fn post_visit_expression(
+    fn requires_bytecode_inspection(&self) -> bool {
+        true
+    }
+
+    fn visit_function_with_bytecode(
         &mut self,
-        exp: &ExpData,
-        _func_env: &FunctionEnv,
+        func_env: &FunctionEnv,
         env: &GlobalEnv,
-        _: &LintConfig,
         diags: &mut Vec<Diagnostic<FileId>>,
     ) {
-        self.check_shift_overflow(exp, env, diags);
+        for_each_instruction_with_intervals(func_env, |target, instr, state| {
+            if let Bytecode::Call(attr_id, _, Operation::Shl | Operation::Shr, srcs, _) = instr {
+                let (value, amount) = match srcs.as_slice() {
+                    [value, amount] => (*value, *amount),
+                    _ => return,
+                };
+                let bit_width = match get_type_max(target.get_local_type(value)) {
+                    Some(type_max) => get_bit_width(&type_max),
+                    None => return,
+                };
+                let amount = match state.range(amount, target) {
+                    Some(amount) => amount,
+                    None => return,
+                };
+                if amount.lo >= BigInt::from(bit_width) {
+                    let message = format!(
+                        "Potential overflow detected. The number of bits being shifted is at least the bit width of the variable being shifted ({} bits)",
+                        bit_width
+                    );
+                    add_diagnostic_and_emit(
+                        &target.get_bytecode_loc(*attr_id),
+                        &message,
+                        codespan_reporting::diagnostic::Severity::Warning,
+                        env,
+                        diags,
+                    );
+                }
+            }
+        });
     }
 }
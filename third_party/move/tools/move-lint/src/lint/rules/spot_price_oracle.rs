@@ -0,0 +1,203 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Detect prices computed from the current reserves of a pool. A division of one reserve by
+//! another gives the spot price, which anyone can move within a single transaction, e.g. with a
+//! flash loan or a large swap, so it should not be used as a price oracle. The lint considers
+//! divisions of values read from fields or functions whose name contains `reserve`, in functions
+//! whose name contains `price` or bound to locals whose name contains `price`. Functions which
+//! read time-weighted data (names containing `cumulative`, `twap` or `observation`) are skipped.
+use crate::lint::{
+    utils::{add_diagnostic_and_emit, get_package_call_closure, LintConfig},
+    visitor::ExpressionAnalysisVisitor,
+};
+use codespan::FileId;
+use codespan_reporting::diagnostic::Diagnostic;
+use move_model::{
+    ast::{Exp, ExpData, Operation},
+    model::{FunctionEnv, GlobalEnv, NodeId},
+    symbol::Symbol,
+};
+use std::collections::BTreeSet;
+
+const RESERVE_MARKER: &str = "reserve";
+const PRICE_MARKER: &str = "price";
+const TWAP_MARKERS: [&str; 3] = ["cumulative", "twap", "observation"];
+
+#[derive(Debug)]
+pub struct SpotPriceOracleVisitor;
+
+impl Default for SpotPriceOracleVisitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpotPriceOracleVisitor {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
+        Box::new(Self::new())
+    }
+
+    fn name_contains(&self, sym: Symbol, marker: &str, env: &GlobalEnv) -> bool {
+        env.symbol_pool()
+            .string(sym)
+            .to_lowercase()
+            .contains(marker)
+    }
+
+    /// Returns the name of the field or function read by an expression, if it is one.
+    fn get_read_name(&self, exp: &ExpData, env: &GlobalEnv) -> Option<Symbol> {
+        match exp {
+            ExpData::Call(_, Operation::Select(_, _, fid), _) => Some(fid.symbol()),
+            ExpData::Call(_, Operation::MoveFunction(mid, fid), _) => {
+                Some(env.get_function(mid.qualified(*fid)).get_name())
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns true if the function, or a package function it calls, reads time-weighted data.
+    fn uses_time_weighted_data(&self, func_env: &FunctionEnv, env: &GlobalEnv) -> bool {
+        get_package_call_closure(func_env)
+            .into_iter()
+            .any(|fun_id| {
+                let mut found = false;
+                if let Some(def) = env.get_function(fun_id).get_def() {
+                    def.visit_pre_post(&mut |post: bool, e: &ExpData| {
+                        if post && !found {
+                            found = self.get_read_name(e, env).map_or(false, |name| {
+                                TWAP_MARKERS
+                                    .iter()
+                                    .any(|marker| self.name_contains(name, marker, env))
+                            });
+                        }
+                        true
+                    });
+                }
+                found
+            })
+    }
+
+    /// Returns true if an expression reads a reserve, directly or through a local.
+    fn reads_reserve(&self, exp: &Exp, reserve_locals: &BTreeSet<Symbol>, env: &GlobalEnv) -> bool {
+        let mut found = false;
+        exp.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            if post && !found {
+                found = match e {
+                    ExpData::LocalVar(_, sym) => reserve_locals.contains(sym),
+                    _ => self
+                        .get_read_name(e, env)
+                        .map_or(false, |name| self.name_contains(name, RESERVE_MARKER, env)),
+                };
+            }
+            true
+        });
+        found
+    }
+
+    /// Collects the locals holding values derived from reserves.
+    fn collect_reserve_locals(&self, body: &Exp, env: &GlobalEnv) -> BTreeSet<Symbol> {
+        let mut reserve_locals = BTreeSet::new();
+        loop {
+            let mut updates = vec![];
+            body.visit_pre_post(&mut |post: bool, e: &ExpData| {
+                if post {
+                    if let ExpData::Block(_, pattern, Some(value), _)
+                    | ExpData::Assign(_, pattern, value) = e
+                    {
+                        if self.reads_reserve(value, &reserve_locals, env) {
+                            updates.extend(
+                                pattern
+                                    .vars()
+                                    .into_iter()
+                                    .map(|(_, sym)| sym)
+                                    .filter(|sym| !reserve_locals.contains(sym)),
+                            );
+                        }
+                    }
+                }
+                true
+            });
+            if updates.is_empty() {
+                return reserve_locals;
+            }
+            reserve_locals.extend(updates);
+        }
+    }
+
+    /// Collects the divisions of one reserve-derived value by another within an expression.
+    fn collect_spot_prices(
+        &self,
+        exp: &Exp,
+        reserve_locals: &BTreeSet<Symbol>,
+        env: &GlobalEnv,
+        spot_prices: &mut BTreeSet<NodeId>,
+    ) {
+        exp.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            if post {
+                if let ExpData::Call(node_id, Operation::Div, args) = e {
+                    if args
+                        .iter()
+                        .all(|arg| self.reads_reserve(arg, reserve_locals, env))
+                    {
+                        spot_prices.insert(*node_id);
+                    }
+                }
+            }
+            true
+        });
+    }
+}
+
+impl ExpressionAnalysisVisitor for SpotPriceOracleVisitor {
+    fn visit_function_custom(
+        &mut self,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        _: &LintConfig,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let body = match func_env.get_def() {
+            Some(body) if !func_env.is_test_only() => body,
+            _ => return,
+        };
+        let reserve_locals = self.collect_reserve_locals(body, env);
+        let mut spot_prices = BTreeSet::new();
+        if self.name_contains(func_env.get_name(), PRICE_MARKER, env) {
+            self.collect_spot_prices(body, &reserve_locals, env, &mut spot_prices);
+        } else {
+            body.visit_pre_post(&mut |post: bool, e: &ExpData| {
+                if post {
+                    if let ExpData::Block(_, pattern, Some(value), _)
+                    | ExpData::Assign(_, pattern, value) = e
+                    {
+                        if pattern
+                            .vars()
+                            .iter()
+                            .any(|(_, sym)| self.name_contains(*sym, PRICE_MARKER, env))
+                        {
+                            self.collect_spot_prices(value, &reserve_locals, env, &mut spot_prices);
+                        }
+                    }
+                }
+                true
+            });
+        }
+        if spot_prices.is_empty() || self.uses_time_weighted_data(func_env, env) {
+            return;
+        }
+        for node_id in spot_prices {
+            add_diagnostic_and_emit(
+                &env.get_node_loc(node_id),
+                "Price computed from the current reserves of a pool. Spot prices can be manipulated within a single transaction, e.g. with a flash loan; use a time-weighted average price or an external oracle instead.",
+                codespan_reporting::diagnostic::Severity::Warning,
+                env,
+                diags,
+            );
+        }
+    }
+}
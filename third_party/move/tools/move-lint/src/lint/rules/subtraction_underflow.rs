@@ -0,0 +1,67 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Detect subtractions which always underflow, i.e. whose right operand is always larger than
+//! the left one. Unsigned subtraction aborts on underflow, so such code can never run to
+//! completion. Operand ranges come from the interval analysis, which follows constants, locals
+//! and guards for every integer type.
+use crate::lint::{
+    interval_analysis::for_each_instruction_with_intervals, utils::add_diagnostic_and_emit,
+    visitor::ExpressionAnalysisVisitor,
+};
+use codespan::FileId;
+use codespan_reporting::diagnostic::Diagnostic;
+use move_model::model::{FunctionEnv, GlobalEnv};
+use move_stackless_bytecode::stackless_bytecode::{Bytecode, Operation};
+
+#[derive(Debug)]
+pub struct SubtractionUnderflowVisitor;
+
+impl Default for SubtractionUnderflowVisitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SubtractionUnderflowVisitor {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
+        Box::new(Self::new())
+    }
+}
+
+impl ExpressionAnalysisVisitor for SubtractionUnderflowVisitor {
+    fn requires_bytecode_inspection(&self) -> bool {
+        true
+    }
+
+    fn visit_function_with_bytecode(
+        &mut self,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        for_each_instruction_with_intervals(func_env, |target, instr, state| {
+            if let Bytecode::Call(attr_id, _, Operation::Sub, srcs, _) = instr {
+                let (lhs, rhs) = match srcs.as_slice() {
+                    [lhs, rhs] => (state.range(*lhs, target), state.range(*rhs, target)),
+                    _ => return,
+                };
+                if let (Some(lhs), Some(rhs)) = (lhs, rhs) {
+                    if lhs.hi < rhs.lo {
+                        add_diagnostic_and_emit(
+                            &target.get_bytecode_loc(*attr_id),
+                            "Subtraction underflow detected. The right operand is always larger than the left one, so this operation always aborts.",
+                            codespan_reporting::diagnostic::Severity::Warning,
+                            env,
+                            diags,
+                        );
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,234 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Detect security-sensitive branching on values of the execution environment, i.e.
+//! `timestamp::now_seconds`/`now_microseconds`, `block::get_current_block_height` and
+//! `transaction_context` values, in functions which move funds. Validators can shift the block
+//! timestamp within a small window and transaction senders choose when and what they submit,
+//! so conditions on these values should not decide who gets paid. Values are followed through
+//! locals and through package functions returning them.
+use crate::lint::{
+    utils::{add_diagnostic_and_emit, get_package_call_closure, is_framework_function, LintConfig},
+    visitor::ExpressionAnalysisVisitor,
+};
+use codespan::FileId;
+use codespan_reporting::diagnostic::Diagnostic;
+use move_model::{
+    ast::{Exp, ExpData, Operation},
+    model::{FunctionEnv, GlobalEnv},
+    symbol::Symbol,
+};
+use std::collections::BTreeMap;
+
+/// Framework functions returning values of the execution environment.
+const ENVIRONMENT_FUNCTIONS: [(&str, &[&str]); 3] = [
+    ("timestamp", &["now_seconds", "now_microseconds"]),
+    ("block", &["get_current_block_height"]),
+    (
+        "transaction_context",
+        &[
+            "get_transaction_hash",
+            "get_script_hash",
+            "generate_auid",
+            "generate_auid_address",
+            "generate_unique_address",
+        ],
+    ),
+];
+
+/// Framework functions moving coins or fungible assets, by module.
+const FUND_MOVING_FUNCTIONS: [(&str, &[&str]); 5] = [
+    (
+        "coin",
+        &["transfer", "withdraw", "deposit", "extract", "extract_all"],
+    ),
+    (
+        "aptos_account",
+        &["transfer", "transfer_coins", "deposit_coins"],
+    ),
+    (
+        "fungible_asset",
+        &[
+            "transfer",
+            "withdraw",
+            "deposit",
+            "extract",
+            "transfer_with_ref",
+            "withdraw_with_ref",
+            "deposit_with_ref",
+        ],
+    ),
+    (
+        "primary_fungible_store",
+        &[
+            "transfer",
+            "withdraw",
+            "deposit",
+            "transfer_with_ref",
+            "withdraw_with_ref",
+            "deposit_with_ref",
+        ],
+    ),
+    (
+        "dispatchable_fungible_asset",
+        &["transfer", "withdraw", "deposit"],
+    ),
+];
+
+#[derive(Debug)]
+pub struct TimestampDependenceVisitor;
+
+impl Default for TimestampDependenceVisitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimestampDependenceVisitor {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
+        Box::new(Self::new())
+    }
+
+    fn is_listed_function(&self, func_env: &FunctionEnv, list: &[(&str, &[&str])]) -> bool {
+        list.iter()
+            .any(|(module, funcs)| is_framework_function(func_env, module, funcs))
+    }
+
+    /// Returns the name of the framework function providing an environment value if `callee` is
+    /// such a function, or a package function calling one.
+    fn get_environment_source(&self, callee: &FunctionEnv, env: &GlobalEnv) -> Option<String> {
+        if self.is_listed_function(callee, &ENVIRONMENT_FUNCTIONS) {
+            return Some(format!(
+                "{}::{}",
+                callee.module_env.get_name().display(env),
+                callee.get_name_str()
+            ));
+        }
+        if !callee.module_env.is_target() {
+            return None;
+        }
+        get_package_call_closure(callee)
+            .into_iter()
+            .filter_map(|fun_id| env.get_function(fun_id).get_called_functions().cloned())
+            .flatten()
+            .map(|fun_id| env.get_function(fun_id))
+            .find(|func_env| self.is_listed_function(func_env, &ENVIRONMENT_FUNCTIONS))
+            .map(|func_env| {
+                format!(
+                    "{}::{}",
+                    func_env.module_env.get_name().display(env),
+                    func_env.get_name_str()
+                )
+            })
+    }
+
+    /// Returns the environment value an expression depends on, if any.
+    fn find_dependency(
+        &self,
+        exp: &Exp,
+        tainted: &BTreeMap<Symbol, String>,
+        env: &GlobalEnv,
+    ) -> Option<String> {
+        let mut dependency = None;
+        exp.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            if post && dependency.is_none() {
+                dependency = match e {
+                    ExpData::LocalVar(_, sym) => tainted.get(sym).cloned(),
+                    ExpData::Call(_, Operation::MoveFunction(mid, fid), _) => {
+                        self.get_environment_source(&env.get_function(mid.qualified(*fid)), env)
+                    },
+                    _ => None,
+                };
+            }
+            true
+        });
+        dependency
+    }
+
+    /// Collects the locals holding values derived from the environment.
+    fn collect_tainted_locals(&self, body: &Exp, env: &GlobalEnv) -> BTreeMap<Symbol, String> {
+        let mut tainted = BTreeMap::new();
+        loop {
+            let mut updates = vec![];
+            body.visit_pre_post(&mut |post: bool, e: &ExpData| {
+                if post {
+                    let (pattern, value) = match e {
+                        ExpData::Block(_, pattern, Some(value), _) => (pattern, value),
+                        ExpData::Assign(_, pattern, value) => (pattern, value),
+                        _ => return true,
+                    };
+                    if let Some(source) = self.find_dependency(value, &tainted, env) {
+                        for (_, sym) in pattern.vars() {
+                            if !tainted.contains_key(&sym) {
+                                updates.push((sym, source.clone()));
+                            }
+                        }
+                    }
+                }
+                true
+            });
+            if updates.is_empty() {
+                return tainted;
+            }
+            tainted.extend(updates);
+        }
+    }
+}
+
+impl ExpressionAnalysisVisitor for TimestampDependenceVisitor {
+    fn visit_function_custom(
+        &mut self,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        _: &LintConfig,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let body = match func_env.get_def() {
+            Some(body) if !func_env.is_test_only() && moves_funds(func_env) => body,
+            _ => return,
+        };
+        let tainted = self.collect_tainted_locals(body, env);
+        body.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            if post {
+                if let ExpData::IfElse(_, cond, _, _) = e {
+                    if let Some(source) = self.find_dependency(cond, &tainted, env) {
+                        let message = format!(
+                            "Condition depends on `{}` in a function that moves funds. Block timestamps, heights and transaction context values can be influenced by validators or transaction senders and should not decide fund transfers.",
+                            source
+                        );
+                        add_diagnostic_and_emit(
+                            &env.get_node_loc(cond.node_id()),
+                            &message,
+                            codespan_reporting::diagnostic::Severity::Warning,
+                            env,
+                            diags,
+                        );
+                    }
+                }
+            }
+            true
+        });
+    }
+}
+
+/// Returns true if the function is a framework function moving coins or fungible assets.
+pub fn is_fund_moving_function(func_env: &FunctionEnv) -> bool {
+    FUND_MOVING_FUNCTIONS
+        .iter()
+        .any(|(module, funcs)| is_framework_function(func_env, module, funcs))
+}
+
+/// Returns true if the function, or a package function it calls, moves coins or fungible
+/// assets.
+pub fn moves_funds(func_env: &FunctionEnv) -> bool {
+    let env = func_env.module_env.env;
+    get_package_call_closure(func_env)
+        .into_iter()
+        .filter_map(|fun_id| env.get_function(fun_id).get_called_functions().cloned())
+        .flatten()
+        .any(|fun_id| is_fund_moving_function(&env.get_function(fun_id)))
+}
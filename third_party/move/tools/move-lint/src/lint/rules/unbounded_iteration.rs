@@ -0,0 +1,452 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Detect unbounded iteration over collections stored in global storage which any caller can
+//! grow. When a public or entry function appends to a vector, `SmartVector`, `SimpleMap` or
+//! table-backed collection inside a resource, an attacker can keep growing it until any function
+//! iterating over the whole collection exceeds the gas limit. This lint follows
+//! `borrow_global` → field → `length` chains in the AST and warns about `while`/`loop` loops whose
+//! exit condition depends on such a length, and about `for_each` style iteration over such
+//! collections.
+use crate::lint::{
+    utils::{add_diagnostic_and_emit, get_package_call_closure, is_framework_function},
+    visitor::ExpressionAnalysisVisitor,
+};
+use codespan::FileId;
+use codespan_reporting::diagnostic::Diagnostic;
+use move_model::{
+    ast::{ExpData, Operation, Pattern},
+    model::{
+        FieldId, FunId, FunctionEnv, GlobalEnv, ModuleEnv, NodeId, QualifiedId, StructId,
+        Visibility,
+    },
+    symbol::Symbol,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Collection modules together with their functions which grow the collection.
+const GROW_FUNCTIONS: [(&str, &[&str]); 6] = [
+    ("vector", &["push_back", "append", "insert"]),
+    ("smart_vector", &["push_back", "append"]),
+    ("big_vector", &["push_back"]),
+    ("simple_map", &["add", "add_all", "upsert"]),
+    ("smart_table", &["add", "add_all", "upsert"]),
+    ("table_with_length", &["add", "upsert"]),
+];
+
+/// Collection modules together with their functions which visit every element.
+const ITERATE_FUNCTIONS: [(&str, &[&str]); 5] = [
+    (
+        "vector",
+        &[
+            "for_each",
+            "for_each_ref",
+            "for_each_mut",
+            "for_each_reverse",
+            "enumerate_ref",
+            "enumerate_mut",
+            "fold",
+            "foldr",
+            "map",
+            "map_ref",
+            "filter",
+            "any",
+            "all",
+            "contains",
+            "index_of",
+            "find",
+        ],
+    ),
+    (
+        "smart_vector",
+        &[
+            "for_each",
+            "for_each_ref",
+            "for_each_mut",
+            "enumerate_ref",
+            "enumerate_mut",
+            "fold",
+            "foldr",
+            "map",
+            "map_ref",
+            "filter",
+            "any",
+            "contains",
+            "index_of",
+        ],
+    ),
+    ("big_vector", &["contains", "index_of", "to_vector"]),
+    (
+        "simple_map",
+        &["keys", "values", "to_vec_pair", "contains_key"],
+    ),
+    (
+        "smart_table",
+        &[
+            "for_each_ref",
+            "for_each_mut",
+            "map_ref",
+            "any",
+            "keys",
+            "to_simple_map",
+        ],
+    ),
+];
+
+const LENGTH_MODULES: [&str; 6] = [
+    "vector",
+    "smart_vector",
+    "big_vector",
+    "simple_map",
+    "smart_table",
+    "table_with_length",
+];
+
+type StorageField = (QualifiedId<StructId>, FieldId);
+
+/// What a local variable was bound to.
+#[derive(Clone, Copy)]
+enum Origin {
+    /// A reference returned by `borrow_global`/`borrow_global_mut`.
+    Global,
+    /// A field of a resource in global storage.
+    Field(StorageField),
+    /// The length of a collection stored in global storage.
+    Length(StorageField),
+}
+
+#[derive(Debug)]
+pub struct UnboundedIterationVisitor {
+    /// Storage fields grown by the package with the function growing them, collected on first
+    /// use.
+    growable: Option<BTreeMap<StorageField, QualifiedId<FunId>>>,
+}
+
+impl Default for UnboundedIterationVisitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UnboundedIterationVisitor {
+    pub fn new() -> Self {
+        Self { growable: None }
+    }
+
+    pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
+        Box::new(Self::new())
+    }
+
+    fn is_listed_function(&self, callee: &FunctionEnv, functions: &[(&str, &[&str])]) -> bool {
+        functions
+            .iter()
+            .any(|(module_name, names)| is_framework_function(callee, module_name, names))
+    }
+
+    fn is_length_function(&self, callee: &FunctionEnv) -> bool {
+        LENGTH_MODULES
+            .iter()
+            .any(|module_name| is_framework_function(callee, module_name, &["length"]))
+    }
+
+    /// Returns true if the expression evaluates to a reference into global storage.
+    fn is_global(&self, exp: &ExpData, bindings: &BTreeMap<Symbol, Origin>) -> bool {
+        match exp {
+            ExpData::Call(_, Operation::BorrowGlobal(_), _) => true,
+            ExpData::Call(
+                _,
+                Operation::Borrow(_) | Operation::Deref | Operation::Freeze(_),
+                args,
+            ) => args
+                .first()
+                .map_or(false, |arg| self.is_global(arg, bindings)),
+            ExpData::LocalVar(_, sym) => matches!(bindings.get(sym), Some(Origin::Global)),
+            _ => false,
+        }
+    }
+
+    /// Returns the resource field the expression refers to, if it is stored in global storage.
+    fn get_storage_field(
+        &self,
+        exp: &ExpData,
+        bindings: &BTreeMap<Symbol, Origin>,
+    ) -> Option<StorageField> {
+        match exp {
+            ExpData::Call(_, Operation::Select(mid, sid, fid), args) => {
+                let base = args.first()?;
+                if self.is_global(base, bindings) {
+                    Some((mid.qualified(*sid), *fid))
+                } else {
+                    self.get_storage_field(base, bindings)
+                }
+            },
+            ExpData::Call(
+                _,
+                Operation::Borrow(_) | Operation::Deref | Operation::Freeze(_),
+                args,
+            ) => self.get_storage_field(args.first()?, bindings),
+            ExpData::LocalVar(_, sym) => match bindings.get(sym) {
+                Some(Origin::Field(field)) => Some(*field),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the storage field whose length the expression computes, if any.
+    fn get_length_of(
+        &self,
+        exp: &ExpData,
+        env: &GlobalEnv,
+        bindings: &BTreeMap<Symbol, Origin>,
+    ) -> Option<StorageField> {
+        match exp {
+            ExpData::Call(_, Operation::MoveFunction(mid, fid), args) => {
+                let callee = env.get_function(mid.qualified(*fid));
+                if self.is_length_function(&callee) {
+                    self.get_storage_field(args.first()?, bindings)
+                } else {
+                    None
+                }
+            },
+            ExpData::LocalVar(_, sym) => match bindings.get(sym) {
+                Some(Origin::Length(field)) => Some(*field),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Records what a `let` binding refers to.
+    fn bind(
+        &self,
+        pattern: &Pattern,
+        binding: &ExpData,
+        env: &GlobalEnv,
+        bindings: &mut BTreeMap<Symbol, Origin>,
+    ) {
+        if let Pattern::Var(_, sym) = pattern {
+            if self.is_global(binding, bindings) {
+                bindings.insert(*sym, Origin::Global);
+            } else if let Some(field) = self.get_storage_field(binding, bindings) {
+                bindings.insert(*sym, Origin::Field(field));
+            } else if let Some(field) = self.get_length_of(binding, env, bindings) {
+                bindings.insert(*sym, Origin::Length(field));
+            }
+        }
+    }
+
+    /// Collects the storage fields grown by functions reachable from public or entry functions
+    /// of the package, together with the function growing them.
+    fn collect_growable_fields(
+        &self,
+        env: &GlobalEnv,
+    ) -> BTreeMap<StorageField, QualifiedId<FunId>> {
+        let mut reachable = BTreeSet::new();
+        for module in env.get_target_modules() {
+            for func_env in module.get_functions() {
+                if (func_env.visibility() == Visibility::Public || func_env.is_entry())
+                    && !func_env.is_test_only()
+                {
+                    reachable.extend(get_package_call_closure(&func_env));
+                }
+            }
+        }
+        let mut growable = BTreeMap::new();
+        for fun_id in reachable {
+            let func_env = env.get_function(fun_id);
+            if let Some(def) = func_env.get_def() {
+                let mut bindings = BTreeMap::new();
+                def.visit_pre_post(&mut |post: bool, exp: &ExpData| {
+                    match exp {
+                        ExpData::Block(_, pattern, Some(binding), _) if !post => {
+                            self.bind(pattern, binding, env, &mut bindings);
+                        },
+                        ExpData::Call(_, Operation::MoveFunction(mid, fid), args) if post => {
+                            let callee = env.get_function(mid.qualified(*fid));
+                            if self.is_listed_function(&callee, &GROW_FUNCTIONS) {
+                                if let Some(field) = args
+                                    .first()
+                                    .and_then(|arg| self.get_storage_field(arg, &bindings))
+                                {
+                                    growable.entry(field).or_insert(fun_id);
+                                }
+                            }
+                        },
+                        _ => {},
+                    }
+                    true
+                });
+            }
+        }
+        growable
+    }
+
+    /// Returns true if the expression is a `break`, or a sequence ending with one.
+    fn is_break(&self, exp: &ExpData) -> bool {
+        match exp {
+            ExpData::LoopCont(_, false) => true,
+            ExpData::Sequence(_, exps) => exps.last().map_or(false, |last| self.is_break(last)),
+            _ => false,
+        }
+    }
+
+    /// Returns the growable storage field whose length appears in an exit condition of the loop,
+    /// that is the condition of a `while` or of an `if` which breaks out of the loop.
+    fn get_loop_bound(
+        &self,
+        body: &ExpData,
+        env: &GlobalEnv,
+        bindings: &BTreeMap<Symbol, Origin>,
+        growable: &BTreeMap<StorageField, QualifiedId<FunId>>,
+    ) -> Option<StorageField> {
+        let mut bound = None;
+        let mut nested_loops = 0;
+        body.visit_pre_post(&mut |post: bool, exp: &ExpData| {
+            match exp {
+                ExpData::Loop(..) | ExpData::Lambda(..) => {
+                    nested_loops += if post { -1 } else { 1 };
+                },
+                ExpData::IfElse(_, cond, then, els)
+                    if !post
+                        && nested_loops == 0
+                        && (self.is_break(then) || self.is_break(els)) =>
+                {
+                    cond.visit_pre_post(&mut |post: bool, e: &ExpData| {
+                        if post && bound.is_none() {
+                            bound = self
+                                .get_length_of(e, env, bindings)
+                                .filter(|field| growable.contains_key(field));
+                        }
+                        bound.is_none()
+                    });
+                },
+                _ => {},
+            }
+            bound.is_none()
+        });
+        bound
+    }
+
+    fn check_function(
+        &self,
+        func_env: &FunctionEnv,
+        growable: &BTreeMap<StorageField, QualifiedId<FunId>>,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let def = match func_env.get_def() {
+            Some(def) => def,
+            None => return,
+        };
+        let mut bindings = BTreeMap::new();
+        // For every enclosing loop, the growable field bounding it, if found yet.
+        let mut open_loops: Vec<Option<StorageField>> = Vec::new();
+        def.visit_pre_post(&mut |post: bool, exp: &ExpData| {
+            match exp {
+                ExpData::Block(_, pattern, Some(binding), _) if !post => {
+                    self.bind(pattern, binding, env, &mut bindings);
+                },
+                ExpData::Loop(node_id, body) => {
+                    if !post {
+                        open_loops.push(self.get_loop_bound(body, env, &bindings, growable));
+                    } else if let Some(Some(field)) = open_loops.pop() {
+                        let message = format!(
+                            "Loop is bounded by the length of `{}`, which any caller can grow through `{}`. An attacker can grow it until this function exceeds the gas limit.",
+                            self.field_name(field, env),
+                            env.get_function(growable[&field]).get_full_name_str()
+                        );
+                        add_diagnostic_and_emit(
+                            &env.get_node_loc(*node_id),
+                            &message,
+                            codespan_reporting::diagnostic::Severity::Warning,
+                            env,
+                            diags,
+                        );
+                    }
+                },
+                ExpData::Call(node_id, Operation::MoveFunction(mid, fid), args) if post => {
+                    let callee = env.get_function(mid.qualified(*fid));
+                    self.check_iterate_call(
+                        *node_id,
+                        &callee,
+                        args.first().map(|arg| arg.as_ref()),
+                        &bindings,
+                        growable,
+                        env,
+                        diags,
+                    );
+                },
+                _ => {},
+            }
+            true
+        });
+    }
+
+    /// Warns about `for_each` style iteration over a growable storage field.
+    fn check_iterate_call(
+        &self,
+        node_id: NodeId,
+        callee: &FunctionEnv,
+        collection: Option<&ExpData>,
+        bindings: &BTreeMap<Symbol, Origin>,
+        growable: &BTreeMap<StorageField, QualifiedId<FunId>>,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        if !self.is_listed_function(callee, &ITERATE_FUNCTIONS) {
+            return;
+        }
+        let field = match collection.and_then(|exp| self.get_storage_field(exp, bindings)) {
+            Some(field) if growable.contains_key(&field) => field,
+            _ => return,
+        };
+        let message = format!(
+            "`{}` iterates over `{}`, which any caller can grow through `{}`. An attacker can grow it until this function exceeds the gas limit.",
+            callee.get_full_name_str(),
+            self.field_name(field, env),
+            env.get_function(growable[&field]).get_full_name_str()
+        );
+        add_diagnostic_and_emit(
+            &env.get_node_loc(node_id),
+            &message,
+            codespan_reporting::diagnostic::Severity::Warning,
+            env,
+            diags,
+        );
+    }
+
+    fn field_name(&self, (struct_id, field_id): StorageField, env: &GlobalEnv) -> String {
+        let struct_env = env.get_struct(struct_id);
+        format!(
+            "{}.{}",
+            struct_env.get_name().display(env.symbol_pool()),
+            struct_env
+                .get_field(field_id)
+                .get_name()
+                .display(env.symbol_pool())
+        )
+    }
+}
+
+impl ExpressionAnalysisVisitor for UnboundedIterationVisitor {
+    fn visit_module(
+        &mut self,
+        module: &ModuleEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        if self.growable.is_none() {
+            self.growable = Some(self.collect_growable_fields(env));
+        }
+        let growable = self.growable.as_ref().expect("growable fields");
+        if growable.is_empty() {
+            return;
+        }
+        for func_env in module.get_functions() {
+            if !func_env.is_test_only() {
+                self.check_function(&func_env, growable, env, diags);
+            }
+        }
+    }
+}
@@ -2,145 +2,201 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! Lint to check for functions that take mutable references but don't actually mutate anything.
-use crate::lint::{
-    utils::{add_diagnostic_and_emit, get_var_info_from_func_param},
-    visitor::ExpressionAnalysisVisitor,
-};
+//! The check runs on stackless bytecode annotated by the borrow analysis, which tells for every
+//! reference the references it was derived from. A parameter counts as modified if it, or any
+//! reference derived from it through field borrows, `vector::borrow_mut` or function results, is
+//! written to, passed to a callee taking a mutable reference, such as `vector::push_back`, or
+//! returned to the caller as a mutable reference.
+use crate::lint::{utils::add_diagnostic_and_emit, visitor::ExpressionAnalysisVisitor};
 use codespan::FileId;
 use codespan_reporting::diagnostic::Diagnostic;
+use move_binary_format::file_format::CodeOffset;
 use move_model::{
-    ast::{Exp, ExpData, Operation},
-    model::{FunctionEnv, GlobalEnv, Parameter},
+    ast::TempIndex,
+    model::{FunctionEnv, GlobalEnv},
 };
+use move_stackless_bytecode::{
+    borrow_analysis::{BorrowAnalysisProcessor, BorrowAnnotation},
+    function_target::FunctionTarget,
+    function_target_pipeline::{FunctionTargetPipeline, FunctionTargetsHolder, FunctionVariant},
+    livevar_analysis::LiveVarAnalysisProcessor,
+    stackless_bytecode::{BorrowNode, Bytecode, Operation},
+};
+use std::collections::{BTreeMap, BTreeSet};
 
-#[derive(Debug)]
-pub struct UnmodifiedMutableArgumentLint;
-
-impl Default for UnmodifiedMutableArgumentLint {
-    fn default() -> Self {
-        Self::new()
-    }
+#[derive(Debug, Default)]
+pub struct UnmodifiedMutableArgumentLint {
+    /// Function targets of the whole environment annotated with borrow information, built on
+    /// first use since callee summaries are needed to follow references through calls.
+    targets: Option<FunctionTargetsHolder>,
 }
 
 impl UnmodifiedMutableArgumentLint {
     pub fn new() -> Self {
-        Self {}
+        Self { targets: None }
     }
 
     pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
         Box::new(Self::new())
     }
 
-    /// Main function to check mutable parameters which are never modified.
-    fn check_unmodified_mut_arguments(
-        &self,
-        func_env: &FunctionEnv,
-        env: &GlobalEnv,
-        diags: &mut Vec<Diagnostic<FileId>>,
-    ) {
-        for param in func_env.get_parameters().iter() {
-            if param.1.is_mutable_reference() && !self.is_argument_modified(param, func_env) {
-                let message = format!(
-                    "Mutable parameter `{}` is never modified in function `{}`.",
-                    param.0.display(func_env.symbol_pool()),
-                    func_env.get_name().display(func_env.symbol_pool())
-                );
-                add_diagnostic_and_emit(
-                    &func_env.get_loc(),
-                    &message,
-                    codespan_reporting::diagnostic::Severity::Warning,
-                    env,
-                    diags,
-                );
+    /// Runs the borrow analysis on all functions of the environment.
+    fn build_targets(&self, env: &GlobalEnv) -> FunctionTargetsHolder {
+        let mut targets = FunctionTargetsHolder::default();
+        for module_env in env.get_modules() {
+            for func_env in module_env.get_functions() {
+                targets.add_target(&func_env);
             }
         }
+        let mut pipeline = FunctionTargetPipeline::default();
+        pipeline.add_processor(LiveVarAnalysisProcessor::new());
+        pipeline.add_processor(BorrowAnalysisProcessor::new());
+        pipeline.run(env, &mut targets);
+        targets
     }
 
-    /// Checks if a mutable parameter is modified within the function body.
-    fn is_argument_modified(&self, param: &Parameter, func_env: &FunctionEnv) -> bool {
-        if let Some(func_body) = func_env.get_def().as_ref() {
-            let param_name = param.0.display(func_env.symbol_pool()).to_string();
-            let mut is_modified = false;
-
-            func_body.visit_pre_post(
-                &mut (|up: bool, exp: &ExpData| {
-                    if !up && !is_modified {
-                        self.update_usage_status(exp, &param_name, func_env, &mut is_modified);
-                    };
-                    true
-                }),
-            );
-
-            is_modified
-        } else {
-            false
-        }
-    }
-
-    /// Helper function to update the usage status of a parameter based on the expression.
-    fn update_usage_status(
+    /// Collects, for every reference, the references it was derived from. A derived reference
+    /// writes back to its parents when it dies, which the borrow annotation records. References
+    /// which never die in the function, such as returned ones, are linked to the references
+    /// they are borrowed from directly.
+    fn collect_parents(
         &self,
-        exp: &ExpData,
-        param_name: &str,
-        func_env: &FunctionEnv,
-        is_modified: &mut bool,
-    ) {
-        match exp {
-            ExpData::Mutate(_, lhs, _) => {
-                if let ExpData::Call(_, _, vec_exp) = lhs.as_ref() {
-                    self.check_exp_vector(vec_exp, param_name, func_env, is_modified)
+        target: &FunctionTarget,
+        annotation: &BorrowAnnotation,
+    ) -> BTreeMap<TempIndex, BTreeSet<TempIndex>> {
+        let mut parents: BTreeMap<TempIndex, BTreeSet<TempIndex>> = BTreeMap::new();
+        for instr in target.get_bytecode() {
+            match instr {
+                Bytecode::Assign(_, dest, src, _)
+                    if target.get_local_type(*dest).is_mutable_reference() =>
+                {
+                    parents.entry(*dest).or_default().insert(*src);
+                },
+                Bytecode::Call(_, dests, Operation::BorrowField(..), srcs, _) => {
+                    parents.entry(dests[0]).or_default().insert(srcs[0]);
+                },
+                Bytecode::Call(_, dests, Operation::Function(..), srcs, _) => {
+                    // A mutable reference returned by a callee, like `vector::borrow_mut`, may
+                    // be borrowed from any of the mutable references passed to it.
+                    let mut_srcs = srcs
+                        .iter()
+                        .filter(|src| target.get_local_type(**src).is_mutable_reference());
+                    for dest in dests
+                        .iter()
+                        .filter(|dest| target.get_local_type(**dest).is_mutable_reference())
+                    {
+                        parents.entry(*dest).or_default().extend(mut_srcs.clone());
+                    }
+                },
+                _ => {},
+            }
+        }
+        for offset in 0..target.get_bytecode().len() {
+            let info = match annotation.get_borrow_info_at(offset as CodeOffset) {
+                Some(info) => info,
+                None => continue,
+            };
+            for (_, trees) in info.before.dying_nodes(&info.after) {
+                for action in trees.into_iter().flatten() {
+                    if let BorrowNode::Reference(parent) = action.dst {
+                        parents.entry(action.src).or_default().insert(parent);
+                    }
                 }
-            },
-            ExpData::Call(_, Operation::MoveFunction(_, _), exp_vec) => {
-                self.check_exp_vector(exp_vec, param_name, func_env, is_modified)
-            },
-            ExpData::Call(_, Operation::Select(_, _, _), exp_vec) => {
-                self.check_exp_vector(exp_vec, param_name, func_env, is_modified);
-            },
-            _ => (),
+            }
         }
+        parents
     }
 
-    /// Checks expressions in vectors like arguments to function calls or mutates.
-    fn check_exp_vector(
-        &self,
-        exp_vec: &Vec<Exp>,
-        param_name: &str,
-        func_env: &FunctionEnv,
-        is_modified: &mut bool,
-    ) {
-        for exp in exp_vec {
-            match exp.as_ref() {
-                ExpData::Temporary(_, index) => {
-                    if let Some(param) =
-                        get_var_info_from_func_param(*index, &func_env.get_parameters())
+    /// Collects the references which are written to, handed to a callee for mutation or returned
+    /// to the caller, who may then write through them.
+    fn collect_written_refs(&self, target: &FunctionTarget) -> BTreeSet<TempIndex> {
+        let mut written = BTreeSet::new();
+        for instr in target.get_bytecode() {
+            match instr {
+                Bytecode::Call(_, _, Operation::WriteRef, srcs, _) => {
+                    written.insert(srcs[0]);
+                },
+                Bytecode::Call(_, dests, Operation::Function(..), srcs, _) => {
+                    // Callees returning a mutable reference, like `vector::borrow_mut`, only
+                    // derive a new reference, whose own uses decide whether anything is written.
+                    if dests
+                        .iter()
+                        .any(|dest| target.get_local_type(*dest).is_mutable_reference())
                     {
-                        if param.0.display(func_env.symbol_pool()).to_string() == param_name {
-                            *is_modified = true;
-                        }
+                        continue;
                     }
+                    written.extend(
+                        srcs.iter()
+                            .filter(|src| target.get_local_type(**src).is_mutable_reference()),
+                    );
                 },
-                ExpData::Call(_, _, exp_vec) => {
-                    self.check_exp_vector(exp_vec, param_name, func_env, is_modified)
+                Bytecode::Ret(_, srcs) => {
+                    written.extend(
+                        srcs.iter()
+                            .filter(|src| target.get_local_type(**src).is_mutable_reference()),
+                    );
                 },
-                _ => (),
+                _ => {},
             }
         }
+        written
+    }
+
+    /// Returns the parameters reached from the written references through their parents.
+    fn get_modified_params(&self, target: &FunctionTarget) -> BTreeSet<TempIndex> {
+        let parents = match target.get_annotations().get::<BorrowAnnotation>() {
+            Some(annotation) => self.collect_parents(target, annotation),
+            None => BTreeMap::new(),
+        };
+        let mut visited = BTreeSet::new();
+        let mut worklist: Vec<TempIndex> = self.collect_written_refs(target).into_iter().collect();
+        while let Some(temp) = worklist.pop() {
+            if visited.insert(temp) {
+                worklist.extend(parents.get(&temp).into_iter().flatten());
+            }
+        }
+        visited
+            .into_iter()
+            .filter(|temp| *temp < target.get_parameter_count())
+            .collect()
     }
 }
 
 impl ExpressionAnalysisVisitor for UnmodifiedMutableArgumentLint {
-    fn visit_module(
+    fn requires_bytecode_inspection(&self) -> bool {
+        true
+    }
+
+    fn visit_function_with_bytecode(
         &mut self,
-        _module: &move_model::model::ModuleEnv,
-        _env: &GlobalEnv,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
         diags: &mut Vec<Diagnostic<FileId>>,
     ) {
-        for func_env in _module.get_functions() {
-            if func_env.is_native() {
-                return;
-            };
-            self.check_unmodified_mut_arguments(&func_env, _env, diags);
+        if func_env.is_native() || func_env.is_inline() {
+            return;
+        }
+        if self.targets.is_none() {
+            self.targets = Some(self.build_targets(env));
+        }
+        let targets = self.targets.as_ref().expect("function targets");
+        let target = targets.get_target(func_env, &FunctionVariant::Baseline);
+        let modified = self.get_modified_params(&target);
+        for (idx, param) in func_env.get_parameters().iter().enumerate() {
+            if param.1.is_mutable_reference() && !modified.contains(&idx) {
+                let message = format!(
+                    "Mutable parameter `{}` is never modified in function `{}`.",
+                    param.0.display(func_env.symbol_pool()),
+                    func_env.get_name().display(func_env.symbol_pool())
+                );
+                add_diagnostic_and_emit(
+                    &func_env.get_loc(),
+                    &message,
+                    codespan_reporting::diagnostic::Severity::Warning,
+                    env,
+                    diags,
+                );
+            }
         }
     }
 }
@@ -0,0 +1,214 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Lint to check for statements which can never be executed because a previous statement of the
+//! same block never completes: an `abort`, a `return`, an `assert!(false, ..)`, a `loop` without a
+//! `break` or an `if` whose branches all never complete. The statement is reported with the
+//! unreachable code labelled as a secondary span. Except for statements which only diverge
+//! because of a constant condition, which the bytecode analysis does not evaluate, the finding is
+//! confirmed by the compiler's unreachable code analysis on the generated stackless bytecode.
+use crate::lint::{
+    utils::{add_diagnostic_with_labels_and_emit, get_compiler_targets, get_sequence_statements},
+    visitor::ExpressionAnalysisVisitor,
+};
+use codespan::{FileId, Span};
+use codespan_reporting::diagnostic::Diagnostic;
+use move_binary_format::file_format::CodeOffset;
+use move_compiler_v2::pipeline::unreachable_code_analysis::ReachableStateAnnotation;
+use move_model::{
+    ast::{Exp, ExpData, Operation, Value},
+    model::{FunctionEnv, GlobalEnv, Loc},
+};
+use move_stackless_bytecode::{
+    function_target::FunctionTarget, function_target_pipeline::FunctionVariant,
+    stackless_bytecode::Bytecode,
+};
+use std::collections::BTreeSet;
+
+#[derive(Default)]
+pub struct UnreachableCodeVisitor;
+
+impl UnreachableCodeVisitor {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
+        Box::new(Self::new())
+    }
+
+    /// Returns how the expression never completes, if it never does, and whether this depends on
+    /// a constant condition.
+    fn get_divergence(&self, exp: &ExpData) -> Option<(&'static str, bool)> {
+        match exp {
+            ExpData::Call(_, Operation::Abort, _) => Some(("`abort`", false)),
+            ExpData::Return(..) => Some(("`return`", false)),
+            ExpData::Loop(_, body) if !self.has_break(body) => {
+                Some(("`loop` without `break`", false))
+            },
+            ExpData::IfElse(_, cond, then, els) => match cond.as_ref() {
+                ExpData::Value(_, Value::Bool(false)) if self.is_empty(then) => self
+                    .get_divergence(els)
+                    .map(|_| ("`assert!(false, ..)`", true)),
+                ExpData::Value(_, Value::Bool(value)) => {
+                    let taken = if *value { then } else { els };
+                    self.get_divergence(taken).map(|(kind, _)| (kind, true))
+                },
+                _ => {
+                    let (_, then_constant) = self.get_divergence(then)?;
+                    let (_, els_constant) = self.get_divergence(els)?;
+                    Some((
+                        "`if` whose branches all never complete",
+                        then_constant || els_constant,
+                    ))
+                },
+            },
+            ExpData::Sequence(_, exps) => exps.iter().find_map(|exp| self.get_divergence(exp)),
+            ExpData::Block(_, _, binding, body) => binding
+                .as_ref()
+                .and_then(|binding| self.get_divergence(binding))
+                .or_else(|| self.get_divergence(body)),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the loop body contains a `break` of this loop, not of a nested one.
+    fn has_break(&self, body: &Exp) -> bool {
+        let mut nested_loops = 0;
+        let mut has_break = false;
+        body.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            match e {
+                ExpData::Loop(..) => {
+                    if post {
+                        nested_loops -= 1;
+                    } else {
+                        nested_loops += 1;
+                    }
+                },
+                ExpData::LoopCont(_, false) if nested_loops == 0 => has_break = true,
+                _ => {},
+            }
+            !has_break
+        });
+        has_break
+    }
+
+    fn is_empty(&self, exp: &ExpData) -> bool {
+        match exp {
+            ExpData::Sequence(_, exps) => exps.is_empty(),
+            ExpData::Call(_, Operation::Tuple, args) => args.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Returns true if the generated bytecode has instructions within the region and all of them
+    /// are definitely not reachable.
+    fn is_unreachable_in_bytecode(&self, target: &FunctionTarget, region: &Loc) -> bool {
+        let annotation = match target.get_annotations().get::<ReachableStateAnnotation>() {
+            Some(annotation) => annotation,
+            None => return false,
+        };
+        let mut found = false;
+        for (offset, bc) in target.get_bytecode().iter().enumerate() {
+            if matches!(
+                bc,
+                Bytecode::Label(..) | Bytecode::Jump(..) | Bytecode::Nop(..)
+            ) {
+                continue;
+            }
+            if !region.is_enclosing(&target.get_bytecode_loc(bc.get_attr_id())) {
+                continue;
+            }
+            if !annotation.is_definitely_not_reachable(offset as CodeOffset) {
+                return false;
+            }
+            found = true;
+        }
+        found
+    }
+
+    fn check_statements(
+        &self,
+        statements: &[Exp],
+        target: &FunctionTarget,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        for (idx, statement) in statements.iter().enumerate() {
+            let (kind, is_constant) = match self.get_divergence(statement) {
+                Some(divergence) => divergence,
+                None => continue,
+            };
+            // A trailing `()` of a block ending with `;` is not code of its own.
+            let mut rest = &statements[idx + 1..];
+            while let Some((last, init)) = rest.split_last() {
+                if !self.is_empty(last) {
+                    break;
+                }
+                rest = init;
+            }
+            let (first, last) = match (rest.first(), rest.last()) {
+                (Some(first), Some(last)) => (first, last),
+                _ => return,
+            };
+            let first_loc = env.get_node_loc(first.node_id());
+            let last_loc = env.get_node_loc(last.node_id());
+            let region = Loc::new(
+                first_loc.file_id(),
+                Span::new(first_loc.span().start(), last_loc.span().end()),
+            );
+            if is_constant || self.is_unreachable_in_bytecode(target, &region) {
+                let message = format!(
+                    "Unreachable code: the code following this {} is never executed.",
+                    kind
+                );
+                add_diagnostic_with_labels_and_emit(
+                    &env.get_node_loc(statement.node_id()),
+                    &message,
+                    &[(region, "unreachable code".to_string())],
+                    codespan_reporting::diagnostic::Severity::Warning,
+                    env,
+                    diags,
+                );
+            }
+            // Everything after the first statement which never completes is covered.
+            return;
+        }
+    }
+}
+
+impl ExpressionAnalysisVisitor for UnreachableCodeVisitor {
+    fn requires_bytecode_inspection(&self) -> bool {
+        true
+    }
+
+    fn visit_function_with_bytecode(
+        &mut self,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        if func_env.is_native() || func_env.is_inline() {
+            return;
+        }
+        let body = match func_env.get_def() {
+            Some(body) => body,
+            None => return,
+        };
+        let targets = get_compiler_targets(env);
+        if !targets.has_target(func_env, &FunctionVariant::Baseline) {
+            return;
+        }
+        let target = targets.get_target(func_env, &FunctionVariant::Baseline);
+        let mut nested = BTreeSet::new();
+        body.visit_pre_post(&mut |post: bool, exp: &ExpData| {
+            if let ExpData::Sequence(node_id, _) = exp {
+                if !post && !nested.contains(node_id) {
+                    let statements = get_sequence_statements(exp, &mut nested);
+                    self.check_statements(&statements, &target, env, diags);
+                }
+            }
+            true
+        });
+    }
+}
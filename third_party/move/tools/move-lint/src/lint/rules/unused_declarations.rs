@@ -0,0 +1,309 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Detect unused declarations: private functions which are never called, structs which are never
+//! constructed or referenced in the package, constants which are never read and `use`
+//! declarations whose aliases are never referenced. Items marked `#[test_only]` or
+//! `#[deprecated]` are skipped. Unused `E*` error constants are reported by `abort_code`.
+use super::constant_naming::is_error_constant_name;
+use crate::lint::{
+    utils::{add_diagnostic_and_emit, get_used_constant_names},
+    visitor::ExpressionAnalysisVisitor,
+};
+use codespan::FileId;
+use codespan_reporting::diagnostic::Diagnostic;
+use move_model::{
+    ast::Attribute,
+    model::{GlobalEnv, Loc, ModuleEnv, ModuleId, QualifiedId, StructId, Visibility},
+    symbol::Symbol,
+    ty::Type,
+};
+use std::collections::BTreeSet;
+
+const DEPRECATED_ATTRIBUTE: &str = "deprecated";
+const VIEW_ATTRIBUTE: &str = "view";
+const RESOURCE_GROUP_ATTRIBUTE: &str = "resource_group";
+const INIT_MODULE_FUN: &str = "init_module";
+
+#[derive(Debug)]
+pub struct UnusedDeclarationsVisitor {
+    /// Structs referenced anywhere in the package, collected on first use.
+    used_structs: Option<BTreeSet<QualifiedId<StructId>>>,
+}
+
+impl Default for UnusedDeclarationsVisitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UnusedDeclarationsVisitor {
+    pub fn new() -> Self {
+        Self { used_structs: None }
+    }
+
+    pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
+        Box::new(Self::new())
+    }
+
+    fn has_attribute(&self, attributes: &[Attribute], name: &str, env: &GlobalEnv) -> bool {
+        attributes
+            .iter()
+            .any(|attr| env.symbol_pool().string(attr.name()).as_str() == name)
+    }
+
+    fn warn(&self, loc: &Loc, message: &str, env: &GlobalEnv, diags: &mut Vec<Diagnostic<FileId>>) {
+        add_diagnostic_and_emit(
+            loc,
+            message,
+            codespan_reporting::diagnostic::Severity::Warning,
+            env,
+            diags,
+        );
+    }
+
+    /// Warns about private functions which are never called, other than by themselves.
+    fn check_private_functions(
+        &self,
+        module: &ModuleEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        for func_env in module.get_functions() {
+            let attributes = func_env.get_attributes();
+            if func_env.visibility() != Visibility::Private
+                || func_env.is_entry()
+                || func_env.is_test_only()
+                || func_env.get_name_str() == INIT_MODULE_FUN
+                || self.has_attribute(attributes, DEPRECATED_ATTRIBUTE, env)
+                || self.has_attribute(attributes, VIEW_ATTRIBUTE, env)
+            {
+                continue;
+            }
+            let is_called = func_env.get_calling_functions().map_or(true, |callers| {
+                callers
+                    .iter()
+                    .any(|caller| *caller != func_env.get_qualified_id())
+            });
+            if !is_called {
+                let message = format!(
+                    "Private function `{}` is never called.",
+                    func_env.get_name_str()
+                );
+                self.warn(&func_env.get_id_loc(), &message, env, diags);
+            }
+        }
+    }
+
+    /// Collects the structs referenced by any type in the package: field types, function
+    /// signatures and the types of all expressions.
+    fn collect_used_structs(&self, env: &GlobalEnv) -> BTreeSet<QualifiedId<StructId>> {
+        let mut used = BTreeSet::new();
+        let mut add_type = |ty: &Type, owner: Option<QualifiedId<StructId>>| {
+            ty.visit(&mut |t| {
+                if let Type::Struct(mid, sid, _) = t {
+                    let struct_id = mid.qualified(*sid);
+                    if Some(struct_id) != owner {
+                        used.insert(struct_id);
+                    }
+                }
+            });
+        };
+        for module in env.get_target_modules() {
+            for struct_env in module.get_structs() {
+                for field_env in struct_env.get_fields() {
+                    add_type(&field_env.get_type(), Some(struct_env.get_qualified_id()));
+                }
+            }
+            for func_env in module.get_functions() {
+                for ty in func_env.get_parameter_types() {
+                    add_type(&ty, None);
+                }
+                add_type(&func_env.get_result_type(), None);
+                if let Some(def) = func_env.get_def() {
+                    for node_id in def.node_ids() {
+                        add_type(&env.get_node_type(node_id), None);
+                        for ty in env.get_node_instantiation(node_id) {
+                            add_type(&ty, None);
+                        }
+                    }
+                }
+            }
+        }
+        used
+    }
+
+    /// Warns about structs which are never constructed or referenced in the package.
+    fn check_structs(
+        &self,
+        module: &ModuleEnv,
+        used: &BTreeSet<QualifiedId<StructId>>,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        for struct_env in module.get_structs() {
+            let attributes = struct_env.get_attributes();
+            if struct_env.is_test_only()
+                || self.has_attribute(attributes, DEPRECATED_ATTRIBUTE, env)
+                || self.has_attribute(attributes, RESOURCE_GROUP_ATTRIBUTE, env)
+                || used.contains(&struct_env.get_qualified_id())
+            {
+                continue;
+            }
+            let message = format!(
+                "Struct `{}` is never constructed or referenced.",
+                struct_env.get_name().display(env.symbol_pool())
+            );
+            self.warn(&struct_env.get_loc(), &message, env, diags);
+        }
+    }
+
+    /// Warns about constants which are never read.
+    fn check_constants(
+        &self,
+        module: &ModuleEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let used = get_used_constant_names(module);
+        for constant in module.get_named_constants() {
+            let name = env.symbol_pool().string(constant.get_name()).to_string();
+            if is_error_constant_name(&name) || used.contains(&name) {
+                continue;
+            }
+            let message = format!("Constant `{}` is never read.", name);
+            self.warn(&constant.get_loc(), &message, env, diags);
+        }
+    }
+
+    /// Collects the functions and structs referenced by the module, as resolved by the model,
+    /// identified by their module and name.
+    fn collect_referenced_members(
+        &self,
+        module: &ModuleEnv,
+        env: &GlobalEnv,
+    ) -> BTreeSet<(ModuleId, Symbol)> {
+        let mut referenced = BTreeSet::new();
+        let mut add_type = |ty: &Type| {
+            ty.visit(&mut |t| {
+                if let Type::Struct(mid, sid, _) = t {
+                    referenced.insert((*mid, env.get_struct(mid.qualified(*sid)).get_name()));
+                }
+            });
+        };
+        for struct_env in module.get_structs() {
+            for field_env in struct_env.get_fields() {
+                add_type(&field_env.get_type());
+            }
+        }
+        let mut called = BTreeSet::new();
+        for func_env in module.get_functions() {
+            for ty in func_env.get_parameter_types() {
+                add_type(&ty);
+            }
+            add_type(&func_env.get_result_type());
+            if let Some(def) = func_env.get_def() {
+                for node_id in def.node_ids() {
+                    add_type(&env.get_node_type(node_id));
+                    for ty in env.get_node_instantiation(node_id) {
+                        add_type(&ty);
+                    }
+                }
+                called.extend(def.called_funs());
+            }
+        }
+        referenced.extend(
+            called
+                .into_iter()
+                .map(|fun_id| (fun_id.module_id, env.get_function(fun_id).get_name())),
+        );
+        referenced
+    }
+
+    /// Warns about `use` declarations whose module alias or member aliases are never referenced.
+    /// A member alias is used if the module references the member. A module alias is used if
+    /// the module references a member of it which is not imported by name.
+    fn check_use_decls(
+        &self,
+        module: &ModuleEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let referenced = self.collect_referenced_members(module, env);
+        let self_sym = env.symbol_pool().make("Self");
+        let imported_members: BTreeSet<(ModuleId, Symbol)> = module
+            .get_use_decls()
+            .iter()
+            .filter_map(|use_decl| Some((use_decl.module_id?, &use_decl.members)))
+            .flat_map(|(module_id, members)| {
+                members
+                    .iter()
+                    .filter(|(_, member, _)| *member != self_sym)
+                    .map(move |(_, member, _)| (module_id, *member))
+            })
+            .collect();
+        let is_module_referenced = |module_id: ModuleId| {
+            referenced
+                .iter()
+                .any(|member| member.0 == module_id && !imported_members.contains(member))
+        };
+        for use_decl in module.get_use_decls() {
+            // Unresolved modules are reported by the compiler.
+            let module_id = match use_decl.module_id {
+                Some(module_id) => module_id,
+                None => continue,
+            };
+            let module_alias = use_decl
+                .alias
+                .unwrap_or_else(|| use_decl.module_name.name());
+            if use_decl.members.is_empty() {
+                if !is_module_referenced(module_id) {
+                    let message = format!(
+                        "Module `{}` is imported but never used.",
+                        use_decl.module_name.display_full(env)
+                    );
+                    self.warn(&use_decl.loc, &message, env, diags);
+                }
+                continue;
+            }
+            for (member_loc, member, alias) in &use_decl.members {
+                let (name, is_used) = if *member == self_sym {
+                    (
+                        alias.unwrap_or(module_alias),
+                        is_module_referenced(module_id),
+                    )
+                } else {
+                    (
+                        alias.unwrap_or(*member),
+                        referenced.contains(&(module_id, *member)),
+                    )
+                };
+                if !is_used {
+                    let message = format!(
+                        "`{}` is imported but never used.",
+                        name.display(env.symbol_pool())
+                    );
+                    self.warn(member_loc, &message, env, diags);
+                }
+            }
+        }
+    }
+}
+
+impl ExpressionAnalysisVisitor for UnusedDeclarationsVisitor {
+    fn visit_module(
+        &mut self,
+        module: &ModuleEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        if self.used_structs.is_none() {
+            self.used_structs = Some(self.collect_used_structs(env));
+        }
+        let used_structs = self.used_structs.as_ref().expect("used structs");
+        self.check_private_functions(module, env, diags);
+        self.check_structs(module, used_structs, env, diags);
+        self.check_constants(module, env, diags);
+        self.check_use_decls(module, env, diags);
+    }
+}
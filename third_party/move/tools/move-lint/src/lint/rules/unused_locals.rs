@@ -0,0 +1,168 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Lint to check for locals whose values are never used. The compiler's optimizations remove such
+//! stores silently, so the check regenerates the stackless bytecode of the package with the
+//! compiler and runs its live variable analysis on it. It reports locals which are assigned but
+//! never read, and assignments whose value is overwritten or dropped before any use. Locals whose
+//! name starts with `_` are skipped. It also flags `let _ = f(..)` where `f` is side effect free
+//! and returns a value, since such a call has no effect at all.
+use crate::lint::{
+    utils::{
+        add_diagnostic_and_emit, get_compiler_targets, is_side_effect_free_function, LintConfig,
+    },
+    visitor::ExpressionAnalysisVisitor,
+};
+use codespan::FileId;
+use codespan_reporting::diagnostic::Diagnostic;
+use move_binary_format::file_format::CodeOffset;
+use move_compiler_v2::pipeline::livevar_analysis_processor::LiveVarAnnotation;
+use move_model::{
+    ast::{ExpData, Operation, Pattern, TempIndex},
+    model::{FunctionEnv, GlobalEnv},
+};
+use move_stackless_bytecode::{
+    function_target::FunctionTarget, function_target_pipeline::FunctionVariant,
+};
+use std::collections::BTreeSet;
+
+#[derive(Default)]
+pub struct UnusedLocalsVisitor;
+
+impl UnusedLocalsVisitor {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
+        Box::new(Self::new())
+    }
+
+    fn check_dead_stores(
+        &self,
+        target: &FunctionTarget,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let annotation = match target.get_annotations().get::<LiveVarAnnotation>() {
+            Some(annotation) => annotation,
+            None => return,
+        };
+        let code = target.get_bytecode();
+        let read: BTreeSet<TempIndex> = code
+            .iter()
+            .filter(|bc| !bc.is_spec_only())
+            .flat_map(|bc| bc.sources())
+            .collect();
+        let func_loc = func_env.get_loc();
+        let mut reported = BTreeSet::new();
+        for (offset, bc) in code.iter().enumerate() {
+            if bc.is_spec_only() {
+                continue;
+            }
+            let info = match annotation.get_live_var_info_at(offset as CodeOffset) {
+                Some(info) => info,
+                None => continue,
+            };
+            for dest in bc.dests() {
+                let name = match target.get_local_name_opt(dest) {
+                    Some(name) if !name.starts_with('_') => name,
+                    _ => continue,
+                };
+                if info.after.contains_key(&dest) {
+                    continue;
+                }
+                // Stores in code inlined from other functions belong to those functions.
+                let loc = target.get_bytecode_loc(bc.get_attr_id());
+                if !func_loc.is_enclosing(&loc) {
+                    continue;
+                }
+                let message = if !read.contains(&dest) {
+                    if !reported.insert(dest) {
+                        continue;
+                    }
+                    format!(
+                        "Local `{}` is assigned but never read. Consider removing it or prefixing its name with `_`.",
+                        name
+                    )
+                } else {
+                    format!(
+                        "Value assigned to `{}` is never read: it is overwritten or dropped before any use.",
+                        name
+                    )
+                };
+                add_diagnostic_and_emit(
+                    &loc,
+                    &message,
+                    codespan_reporting::diagnostic::Severity::Warning,
+                    env,
+                    diags,
+                );
+            }
+        }
+    }
+}
+
+impl ExpressionAnalysisVisitor for UnusedLocalsVisitor {
+    fn visit_function_custom(
+        &mut self,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        _: &LintConfig,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let body = match func_env.get_def() {
+            Some(body) => body,
+            None => return,
+        };
+        body.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            if post {
+                if let ExpData::Block(_, Pattern::Wildcard(_), Some(binding), _) = e {
+                    if let ExpData::Call(node_id, Operation::MoveFunction(mid, fid), _) =
+                        binding.as_ref()
+                    {
+                        let callee = env.get_function(mid.qualified(*fid));
+                        if !env.get_node_type(*node_id).is_unit()
+                            && is_side_effect_free_function(&callee)
+                        {
+                            let message = format!(
+                                "The result of `{}` is discarded with `let _`, and the call has no side effects. Consider removing the call or using its result.",
+                                callee.get_full_name_str()
+                            );
+                            add_diagnostic_and_emit(
+                                &env.get_node_loc(*node_id),
+                                &message,
+                                codespan_reporting::diagnostic::Severity::Warning,
+                                env,
+                                diags,
+                            );
+                        }
+                    }
+                }
+            }
+            true
+        });
+    }
+
+    fn requires_bytecode_inspection(&self) -> bool {
+        true
+    }
+
+    fn visit_function_with_bytecode(
+        &mut self,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        if func_env.is_native() || func_env.is_inline() {
+            return;
+        }
+        let targets = get_compiler_targets(env);
+        if !targets.has_target(func_env, &FunctionVariant::Baseline) {
+            return;
+        }
+        let target = targets.get_target(func_env, &FunctionVariant::Baseline);
+        self.check_dead_stores(&target, func_env, env, diags);
+    }
+}
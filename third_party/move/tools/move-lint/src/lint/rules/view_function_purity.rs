@@ -0,0 +1,315 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Detect `#[view]` functions that are not pure. A view function must not mutate global state,
+//! so this lint warns when a view function takes a signer, or when it (or any function of the
+//! package it transitively calls) uses `move_to`, `move_from`, `borrow_global_mut` or emits
+//! events. It also warns about view functions returning vectors built in a loop or an inline
+//! `for_each` lambda whose bound or iterated collection is read from global storage, without any
+//! caller-provided limit, as the result can grow without bound.
+use crate::lint::{
+    utils::{add_diagnostic_and_emit, get_package_call_closure, is_framework_function, LintConfig},
+    visitor::ExpressionAnalysisVisitor,
+};
+use codespan::FileId;
+use codespan_reporting::diagnostic::Diagnostic;
+use move_model::{
+    ast::{ExpData, Operation},
+    model::{FunctionEnv, GlobalEnv, NodeId, Parameter},
+    symbol::Symbol,
+    ty::{ReferenceKind, Type},
+};
+use std::collections::BTreeSet;
+
+const VIEW_ATTRIBUTE: &str = "view";
+/// Vector functions whose first argument is the collection being iterated in a loop.
+const ITERATE_FUNCTIONS: &[&str] = &["borrow", "borrow_mut", "pop_back", "remove", "swap_remove"];
+
+#[derive(Debug)]
+pub struct ViewFunctionPurityVisitor;
+
+impl Default for ViewFunctionPurityVisitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ViewFunctionPurityVisitor {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
+        Box::new(Self::new())
+    }
+
+    fn is_view_function(&self, func_env: &FunctionEnv) -> bool {
+        func_env.has_attribute(|attr| {
+            func_env.symbol_pool().string(attr.name()).as_str() == VIEW_ATTRIBUTE
+        })
+    }
+
+    /// Warns about signer parameters of a view function.
+    fn check_signer_params(
+        &self,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        for Parameter(name, ty, loc) in func_env.get_parameters() {
+            if ty.skip_reference().is_signer() {
+                let message = format!(
+                    "View function `{}` takes signer `{}`. View functions should not require a signer.",
+                    func_env.get_name_str(),
+                    name.display(env.symbol_pool())
+                );
+                add_diagnostic_and_emit(
+                    &loc,
+                    &message,
+                    codespan_reporting::diagnostic::Severity::Warning,
+                    env,
+                    diags,
+                );
+            }
+        }
+    }
+
+    /// Returns a description of the state mutation performed by the expression, if any.
+    fn get_mutation(&self, exp: &ExpData, env: &GlobalEnv) -> Option<&'static str> {
+        match exp {
+            ExpData::Call(_, Operation::MoveTo, _) => Some("move_to"),
+            ExpData::Call(_, Operation::MoveFrom, _) => Some("move_from"),
+            ExpData::Call(_, Operation::BorrowGlobal(ReferenceKind::Mutable), _) => {
+                Some("borrow_global_mut")
+            },
+            ExpData::Call(_, Operation::MoveFunction(mid, fid), _) => {
+                let callee = env.get_function(mid.qualified(*fid));
+                if is_framework_function(&callee, "event", &["emit", "emit_event"]) {
+                    Some("event emission")
+                } else {
+                    None
+                }
+            },
+            _ => None,
+        }
+    }
+
+    /// Warns about state mutations reachable from a view function within the package.
+    fn check_mutations(
+        &self,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        for fun_id in get_package_call_closure(func_env) {
+            let callee = env.get_function(fun_id);
+            if let Some(def) = callee.get_def() {
+                def.visit_pre_post(&mut |post: bool, exp: &ExpData| {
+                    if post {
+                        if let Some(mutation) = self.get_mutation(exp, env) {
+                            let message = if callee.get_qualified_id() == func_env.get_qualified_id() {
+                                format!(
+                                    "View function `{}` performs {}. View functions must not modify state.",
+                                    func_env.get_name_str(),
+                                    mutation
+                                )
+                            } else {
+                                format!(
+                                    "View function `{}` performs {} through `{}`. View functions must not modify state.",
+                                    func_env.get_name_str(),
+                                    mutation,
+                                    callee.get_full_name_str()
+                                )
+                            };
+                            add_diagnostic_and_emit(
+                                &env.get_node_loc(exp.node_id()),
+                                &message,
+                                codespan_reporting::diagnostic::Severity::Warning,
+                                env,
+                                diags,
+                            );
+                        }
+                    }
+                    true
+                });
+            }
+        }
+    }
+
+    /// Returns true if the expression reads global storage directly or through a local bound to
+    /// a value read from global storage.
+    fn reads_global(&self, exp: &ExpData, global_locals: &BTreeSet<Symbol>) -> bool {
+        let mut reads_global = false;
+        exp.visit_pre_post(&mut |post: bool, e: &ExpData| {
+            if post {
+                reads_global |= match e {
+                    ExpData::Call(_, Operation::BorrowGlobal(_), _) => true,
+                    ExpData::LocalVar(_, sym) => global_locals.contains(sym),
+                    _ => false,
+                };
+            }
+            !reads_global
+        });
+        reads_global
+    }
+
+    /// Collects the locals bound or assigned to values read from global storage, directly or
+    /// through other such locals.
+    fn collect_global_locals(&self, def: &ExpData) -> BTreeSet<Symbol> {
+        let mut global_locals = BTreeSet::new();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            def.visit_pre_post(&mut |post: bool, exp: &ExpData| {
+                if !post {
+                    let (pattern, value) = match exp {
+                        ExpData::Block(_, pattern, Some(binding), _) => (pattern, binding),
+                        ExpData::Assign(_, pattern, value) => (pattern, value),
+                        _ => return true,
+                    };
+                    if self.reads_global(value, &global_locals) {
+                        for (_, sym) in pattern.vars() {
+                            changed |= global_locals.insert(sym);
+                        }
+                    }
+                }
+                true
+            });
+        }
+        global_locals
+    }
+
+    /// Returns true if the loop is bounded by or iterates over a value read from global storage:
+    /// its `while` condition reads global storage, or it borrows or removes elements of a vector
+    /// read from global storage.
+    fn loops_over_global(
+        &self,
+        body: &ExpData,
+        global_locals: &BTreeSet<Symbol>,
+        env: &GlobalEnv,
+    ) -> bool {
+        if let ExpData::IfElse(_, cond, _, _) = body {
+            if self.reads_global(cond, global_locals) {
+                return true;
+            }
+        }
+        let mut iterates_global = false;
+        body.visit_pre_post(&mut |post: bool, exp: &ExpData| {
+            if post {
+                if let ExpData::Call(_, Operation::MoveFunction(mid, fid), args) = exp {
+                    let callee = env.get_function(mid.qualified(*fid));
+                    iterates_global |= is_framework_function(&callee, "vector", ITERATE_FUNCTIONS)
+                        && args
+                            .first()
+                            .map_or(false, |arg| self.reads_global(arg, global_locals));
+                }
+            }
+            !iterates_global
+        });
+        iterates_global
+    }
+
+    /// Warns about view functions returning vectors which are filled in a loop bounded by or
+    /// iterating over global storage, unless the caller can bound the result through an integer
+    /// parameter.
+    fn check_unbounded_result(
+        &self,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        let returns_vector = func_env
+            .get_result_type()
+            .flatten()
+            .iter()
+            .any(|ty| matches!(ty, Type::Vector(_)));
+        let has_limit_param = func_env
+            .get_parameter_types()
+            .iter()
+            .any(|ty| ty.is_number());
+        if !returns_vector || has_limit_param {
+            return;
+        }
+        let def = match func_env.get_def() {
+            Some(def) => def,
+            None => return,
+        };
+        let global_locals = self.collect_global_locals(&def);
+        // Lambdas passed to a function together with a value read from global storage, like
+        // `vector::for_each_ref(&registry.members, |member| ..)`, iterate over global storage.
+        let mut global_lambdas = BTreeSet::new();
+        let mut global_loops: Vec<bool> = Vec::new();
+        let mut push_in_loop: Option<NodeId> = None;
+        def.visit_pre_post(&mut |post: bool, exp: &ExpData| {
+            match exp {
+                ExpData::Loop(_, body) => {
+                    if post {
+                        global_loops.pop();
+                    } else {
+                        global_loops.push(self.loops_over_global(body, &global_locals, env));
+                    }
+                },
+                ExpData::Lambda(node_id, _, _) => {
+                    if post {
+                        global_loops.pop();
+                    } else {
+                        global_loops.push(global_lambdas.contains(node_id));
+                    }
+                },
+                ExpData::Call(_, Operation::MoveFunction(..), args) if !post => {
+                    let reads_global = args.iter().any(|arg| {
+                        !matches!(arg.as_ref(), ExpData::Lambda(..))
+                            && self.reads_global(arg, &global_locals)
+                    });
+                    if reads_global {
+                        for arg in args {
+                            if let ExpData::Lambda(node_id, _, _) = arg.as_ref() {
+                                global_lambdas.insert(*node_id);
+                            }
+                        }
+                    }
+                },
+                _ => {},
+            }
+            if let ExpData::Call(node_id, Operation::MoveFunction(mid, fid), _) = exp {
+                if post && push_in_loop.is_none() && global_loops.iter().any(|global| *global) {
+                    let callee = env.get_function(mid.qualified(*fid));
+                    if is_framework_function(&callee, "vector", &["push_back", "append"]) {
+                        push_in_loop = Some(*node_id);
+                    }
+                }
+            }
+            true
+        });
+        if let Some(node_id) = push_in_loop {
+            let message = format!(
+                "View function `{}` returns a vector built by iterating global storage without a limit parameter. The result can grow without bound.",
+                func_env.get_name_str()
+            );
+            add_diagnostic_and_emit(
+                &env.get_node_loc(node_id),
+                &message,
+                codespan_reporting::diagnostic::Severity::Warning,
+                env,
+                diags,
+            );
+        }
+    }
+}
+
+impl ExpressionAnalysisVisitor for ViewFunctionPurityVisitor {
+    fn visit_function_custom(
+        &mut self,
+        func_env: &FunctionEnv,
+        env: &GlobalEnv,
+        _: &LintConfig,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        if !self.is_view_function(func_env) {
+            return;
+        }
+        self.check_signer_params(func_env, env, diags);
+        self.check_mutations(func_env, env, diags);
+        self.check_unbounded_result(func_env, env, diags);
+    }
+}
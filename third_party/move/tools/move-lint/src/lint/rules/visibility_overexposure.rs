@@ -0,0 +1,149 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Detect functions and friend declarations which expose more than needed. A `public` function
+//! which is only ever called from modules of the same package could be `public(friend)` or
+//! `public(package)`, and one only called from its own module could be private, which keeps it
+//! out of the package's upgrade compatibility surface. Functions not called at all are
+//! considered part of the package's API. A `friend` declaration for a module which never calls
+//! any `public(friend)` function of the declaring module grants access for no reason.
+use crate::lint::{utils::add_diagnostic_and_emit, visitor::ExpressionAnalysisVisitor};
+use codespan::FileId;
+use codespan_reporting::diagnostic::Diagnostic;
+use move_model::{
+    ast::FriendDecl,
+    model::{FunctionEnv, GlobalEnv, ModuleEnv, Visibility},
+};
+
+const VIEW_ATTRIBUTE: &str = "view";
+
+#[derive(Debug)]
+pub struct VisibilityOverexposureVisitor;
+
+impl Default for VisibilityOverexposureVisitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VisibilityOverexposureVisitor {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn visitor() -> Box<dyn ExpressionAnalysisVisitor> {
+        Box::new(Self::new())
+    }
+
+    fn is_view_function(&self, func_env: &FunctionEnv) -> bool {
+        func_env.has_attribute(|attr| {
+            func_env.symbol_pool().string(attr.name()).as_str() == VIEW_ATTRIBUTE
+        })
+    }
+
+    /// Warns about public functions which are only called from within the package, or only from
+    /// within their own module.
+    fn check_public_functions(
+        &self,
+        module: &ModuleEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        for func_env in module.get_functions() {
+            if func_env.visibility() != Visibility::Public
+                || func_env.is_entry()
+                || func_env.is_test_only()
+                || self.is_view_function(&func_env)
+            {
+                continue;
+            }
+            let callers = match func_env.get_calling_functions() {
+                Some(callers) if !callers.is_empty() => callers,
+                _ => continue,
+            };
+            let message = if callers
+                .iter()
+                .all(|caller| caller.module_id == module.get_id())
+            {
+                format!(
+                    "Public function `{}` is only called from within its own module. Consider making it private.",
+                    func_env.get_name_str()
+                )
+            } else if callers
+                .iter()
+                .all(|caller| env.get_module(caller.module_id).is_target())
+            {
+                format!(
+                    "Public function `{}` is only called from within this package. Consider making it `public(friend)` or `public(package)`.",
+                    func_env.get_name_str()
+                )
+            } else {
+                continue;
+            };
+            add_diagnostic_and_emit(
+                &func_env.get_id_loc(),
+                &message,
+                codespan_reporting::diagnostic::Severity::Warning,
+                env,
+                diags,
+            );
+        }
+    }
+
+    /// Returns true if the friend module calls any `public(friend)` function of the module.
+    fn is_friend_used(
+        &self,
+        module: &ModuleEnv,
+        friend_decl: &FriendDecl,
+        env: &GlobalEnv,
+    ) -> bool {
+        let friend_module = match friend_decl.module_id {
+            Some(module_id) => env.get_module(module_id),
+            // Unresolved friends are reported by the compiler.
+            None => return true,
+        };
+        friend_module.get_functions().any(|func_env| {
+            func_env.get_called_functions().map_or(false, |called| {
+                called.iter().any(|callee| {
+                    callee.module_id == module.get_id() && env.get_function(*callee).is_friend()
+                })
+            })
+        })
+    }
+
+    /// Warns about friend declarations which are not needed.
+    fn check_friend_decls(
+        &self,
+        module: &ModuleEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        for friend_decl in module.get_friend_decls() {
+            if !self.is_friend_used(module, friend_decl, env) {
+                let message = format!(
+                    "Friend module `{}` never calls a `public(friend)` function of this module. Consider removing the friend declaration.",
+                    friend_decl.module_name.display(env)
+                );
+                add_diagnostic_and_emit(
+                    &friend_decl.loc,
+                    &message,
+                    codespan_reporting::diagnostic::Severity::Warning,
+                    env,
+                    diags,
+                );
+            }
+        }
+    }
+}
+
+impl ExpressionAnalysisVisitor for VisibilityOverexposureVisitor {
+    fn visit_module(
+        &mut self,
+        module: &ModuleEnv,
+        env: &GlobalEnv,
+        diags: &mut Vec<Diagnostic<FileId>>,
+    ) {
+        self.check_public_functions(module, env, diags);
+        self.check_friend_decls(module, env, diags);
+    }
+}
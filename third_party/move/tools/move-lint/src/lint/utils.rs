@@ -1,6 +1,7 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::lint::rules::ifs_same_cond::PURE_NATIVE_MODULES;
 use anyhow::Ok;
 use codespan::{FileId, Span};
 use codespan_reporting::{
@@ -11,12 +12,24 @@ use codespan_reporting::{
         Config,
     },
 };
+use move_compiler_v2::{
+    pipeline::{
+        livevar_analysis_processor::LiveVarAnalysisProcessor,
+        unreachable_code_analysis::UnreachableCodeProcessor,
+    },
+    run_bytecode_gen,
+};
 use move_model::{
-    ast::{ExpData, Operation},
-    model::{FunctionEnv, GlobalEnv, Parameter},
+    ast::{Exp, ExpData, Operation, Pattern},
+    model::{FunId, FunctionEnv, GlobalEnv, ModuleEnv, NodeId, Parameter, QualifiedId},
+    symbol::Symbol,
+    ty::{ReferenceKind, Type},
+};
+use move_stackless_bytecode::function_target_pipeline::{
+    FunctionTargetPipeline, FunctionTargetsHolder,
 };
 use serde::{Deserialize, Serialize};
-use std::{fs::OpenOptions, io::Read, path::Path};
+use std::{collections::BTreeSet, fs::OpenOptions, io::Read, path::Path, rc::Rc};
 use toml;
 
 // LintConfig is a struct that holds the default configuration for the linter.
@@ -24,6 +37,10 @@ use toml;
 pub struct LintConfig {
     pub statement_count: usize,
     pub usage_frequency: usize,
+    // Functions, given as `module::function` or `address::module::function`, whose result
+    // should not be ignored, in addition to the framework functions known to the linter.
+    #[serde(default)]
+    pub must_use_functions: Vec<String>,
 }
 
 impl Default for LintConfig {
@@ -31,6 +48,7 @@ impl Default for LintConfig {
         LintConfig {
             statement_count: 10,
             usage_frequency: 2,
+            must_use_functions: vec![],
         }
     }
 }
@@ -82,18 +100,38 @@ pub fn add_diagnostic_and_emit(
     severity: codespan_reporting::diagnostic::Severity,
     env: &GlobalEnv,
     diags: &mut Vec<Diagnostic<FileId>>,
+) {
+    add_diagnostic_with_labels_and_emit(loc, message, &[], severity, env, diags);
+}
+
+// Same as `add_diagnostic_and_emit`, with secondary labels pointing to related locations, like
+// the first occurrence of duplicated code.
+pub fn add_diagnostic_with_labels_and_emit(
+    loc: &move_model::model::Loc,
+    message: &str,
+    secondary: &[(move_model::model::Loc, String)],
+    severity: codespan_reporting::diagnostic::Severity,
+    env: &GlobalEnv,
+    diags: &mut Vec<Diagnostic<FileId>>,
 ) {
     let writer = StandardStream::stderr(ColorChoice::Always);
     let config = Config::default();
-    let label = Label::primary(
+    let mut labels = vec![Label::primary(
         loc.file_id(),
         loc.span().start().to_usize()..loc.span().end().to_usize(),
     )
-    .with_message(message.to_string());
+    .with_message(message.to_string())];
+    labels.extend(secondary.iter().map(|(loc, label_message)| {
+        Label::secondary(
+            loc.file_id(),
+            loc.span().start().to_usize()..loc.span().end().to_usize(),
+        )
+        .with_message(label_message.clone())
+    }));
 
     let diagnostic = Diagnostic::new(severity)
         .with_message(message)
-        .with_labels(vec![label]);
+        .with_labels(labels);
 
     diags.push(diagnostic.clone());
     emit(
@@ -133,10 +171,75 @@ pub fn add_diagnostic_and_emit_by_span(
     .expect("emit must not fail");
 }
 
+// Same as `add_diagnostic_and_emit`, for findings which have no location in the package, like a
+// module removed since the published version.
+pub fn add_unlocated_diagnostic_and_emit(
+    message: &str,
+    severity: codespan_reporting::diagnostic::Severity,
+    env: &GlobalEnv,
+    diags: &mut Vec<Diagnostic<FileId>>,
+) {
+    let writer = StandardStream::stderr(ColorChoice::Always);
+    let diagnostic = Diagnostic::new(severity).with_message(message);
+    diags.push(diagnostic.clone());
+    emit(
+        &mut writer.lock(),
+        &Config::default(),
+        &env.get_source_files(),
+        &diagnostic,
+    )
+    .expect("emit must not fail");
+}
+
 pub fn get_var_info_from_func_param(index: usize, params: &[Parameter]) -> Option<&Parameter> {
     params.get(index)
 }
 
+// Check whether the given module is the framework module `module_name` (i.e. published at the
+// stdlib address `0x1`).
+pub fn is_framework_module(module_env: &ModuleEnv, module_name: &str) -> bool {
+    let env = module_env.env;
+    module_env.self_address() == &env.get_stdlib_address()
+        && env
+            .symbol_pool()
+            .string(module_env.get_name().name())
+            .as_str()
+            == module_name
+}
+
+// Check whether the given function is one of `func_names` declared in the framework module
+// `module_name`.
+pub fn is_framework_function(
+    func_env: &FunctionEnv,
+    module_name: &str,
+    func_names: &[&str],
+) -> bool {
+    is_framework_module(&func_env.module_env, module_name)
+        && func_names.contains(&func_env.get_name_str().as_str())
+}
+
+// Check whether the given type (ignoring references) is the framework struct `module_name::struct_name`.
+pub fn is_framework_struct_type(
+    ty: &Type,
+    env: &GlobalEnv,
+    module_name: &str,
+    struct_name: &str,
+) -> bool {
+    if let Type::Struct(module_id, struct_id, _) = ty.skip_reference() {
+        let module_env = env.get_module(*module_id);
+        let struct_env = module_env.get_struct(*struct_id);
+        module_env.self_address() == &env.get_stdlib_address()
+            && env
+                .symbol_pool()
+                .string(module_env.get_name().name())
+                .as_str()
+                == module_name
+            && env.symbol_pool().string(struct_env.get_name()).as_str() == struct_name
+    } else {
+        false
+    }
+}
+
 pub fn read_config_or_default(path: &Path) -> Result<LintConfig, anyhow::Error> {
     let binding = path.join("lint.toml");
     let exist_path = Path::new(&binding);
@@ -156,3 +259,316 @@ pub fn read_config_or_default(path: &Path) -> Result<LintConfig, anyhow::Error>
         Ok(LintConfig::default())
     }
 }
+
+// Collect the functions transitively called from `func_env` (including itself) whose
+// definitions belong to the package being linted.
+pub fn get_package_call_closure(func_env: &FunctionEnv) -> BTreeSet<QualifiedId<FunId>> {
+    let env = func_env.module_env.env;
+    let mut visited = BTreeSet::new();
+    let mut worklist = vec![func_env.get_qualified_id()];
+    while let Some(fun_id) = worklist.pop() {
+        if !visited.insert(fun_id) {
+            continue;
+        }
+        if let Some(def) = env.get_function(fun_id).get_def() {
+            for callee in def.called_funs() {
+                if env.get_function(callee).module_env.is_target() {
+                    worklist.push(callee);
+                }
+            }
+        }
+    }
+    visited
+}
+
+// Collect the names of the named constants referenced in the function bodies of the module.
+// Named constants are replaced by their value in the AST, so the source text at the location
+// of each value tells whether a constant was written there.
+pub fn get_used_constant_names(module_env: &ModuleEnv) -> BTreeSet<String> {
+    let env = module_env.env;
+    let mut used = BTreeSet::new();
+    for func_env in module_env.get_functions() {
+        if let Some(def) = func_env.get_def() {
+            def.visit_pre_post(&mut |post: bool, exp: &ExpData| {
+                if post {
+                    if let ExpData::Value(node_id, _) = exp {
+                        if let std::result::Result::Ok(source) =
+                            env.get_source(&env.get_node_loc(*node_id))
+                        {
+                            used.insert(source.trim().to_string());
+                        }
+                    }
+                }
+                true
+            });
+        }
+    }
+    used
+}
+
+// NormalizedExp is a form of an expression which abstracts from the way it is written, so that
+// expressions can be compared by meaning. Parameters and locals are identified by name, the
+// operands of commutative operations are sorted, `>` and `>=` are flipped into `<` and `<=`, and
+// negated comparisons are replaced by the opposite comparison. Expressions which cannot be
+// normalized, like lambdas, are opaque and only equal to themselves.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NormalizedExp {
+    Var(Symbol),
+    Value(String),
+    Node(String, Vec<NormalizedExp>),
+    Opaque(NodeId),
+}
+
+// Normalize the expression, see `NormalizedExp`. Whether the expression has side effects is
+// not taken into account; use `is_side_effect_free` before treating two evaluations of equal
+// normalized expressions as giving the same result.
+pub fn normalize_exp(exp: &ExpData, func_env: &FunctionEnv) -> NormalizedExp {
+    let normalize_all = |exps: &[Exp]| -> Vec<NormalizedExp> {
+        exps.iter()
+            .map(|exp| normalize_exp(exp, func_env))
+            .collect()
+    };
+    let node = |name: &str, args: Vec<NormalizedExp>| NormalizedExp::Node(name.to_string(), args);
+    match exp {
+        ExpData::LocalVar(_, sym) => NormalizedExp::Var(*sym),
+        ExpData::Temporary(node_id, idx) => {
+            match get_var_info_from_func_param(*idx, &func_env.get_parameters()) {
+                Some(param) => NormalizedExp::Var(param.0),
+                None => NormalizedExp::Opaque(*node_id),
+            }
+        },
+        ExpData::Value(_, value) => NormalizedExp::Value(format!("{:?}", value)),
+        ExpData::Call(node_id, op, args) => {
+            let instantiation = func_env.module_env.env.get_node_instantiation(*node_id);
+            normalize_call(op, &instantiation, normalize_all(args))
+        },
+        ExpData::Sequence(_, exps) if exps.len() == 1 => normalize_exp(&exps[0], func_env),
+        ExpData::Sequence(_, exps) => node("sequence", normalize_all(exps)),
+        ExpData::Block(_, pattern, binding, body) => node(
+            "let",
+            vec![
+                normalize_pattern(pattern),
+                binding.as_ref().map_or_else(
+                    || node("uninitialized", vec![]),
+                    |binding| normalize_exp(binding, func_env),
+                ),
+                normalize_exp(body, func_env),
+            ],
+        ),
+        ExpData::Assign(_, pattern, value) => node(
+            "assign",
+            vec![normalize_pattern(pattern), normalize_exp(value, func_env)],
+        ),
+        ExpData::Mutate(_, lhs, rhs) => node("mutate", normalize_all(&[lhs.clone(), rhs.clone()])),
+        ExpData::IfElse(_, cond, then, els) => node(
+            "if",
+            normalize_all(&[cond.clone(), then.clone(), els.clone()]),
+        ),
+        ExpData::Loop(_, body) => node("loop", vec![normalize_exp(body, func_env)]),
+        ExpData::LoopCont(_, is_continue) => {
+            node(if *is_continue { "continue" } else { "break" }, vec![])
+        },
+        ExpData::Return(_, value) => node("return", vec![normalize_exp(value, func_env)]),
+        _ => NormalizedExp::Opaque(exp.node_id()),
+    }
+}
+
+fn normalize_pattern(pattern: &Pattern) -> NormalizedExp {
+    NormalizedExp::Node(
+        "pattern".to_string(),
+        pattern
+            .vars()
+            .into_iter()
+            .map(|(_, sym)| NormalizedExp::Var(sym))
+            .collect(),
+    )
+}
+
+fn normalize_call(
+    op: &Operation,
+    instantiation: &[Type],
+    mut args: Vec<NormalizedExp>,
+) -> NormalizedExp {
+    match op {
+        Operation::Gt | Operation::Ge if args.len() == 2 => {
+            args.swap(0, 1);
+            let flipped = if *op == Operation::Gt {
+                Operation::Lt
+            } else {
+                Operation::Le
+            };
+            return normalize_call(&flipped, instantiation, args);
+        },
+        Operation::Not if args.len() == 1 => {
+            if let NormalizedExp::Node(name, inner) = &args[0] {
+                let negated = match name.as_str() {
+                    "Not" => return inner[0].clone(),
+                    "Lt" => Some((Operation::Le, true)),
+                    "Le" => Some((Operation::Lt, true)),
+                    "Eq" => Some((Operation::Neq, false)),
+                    "Neq" => Some((Operation::Eq, false)),
+                    _ => None,
+                };
+                if let Some((negated, swap)) = negated {
+                    let mut inner = inner.clone();
+                    if swap {
+                        inner.swap(0, 1);
+                    }
+                    return normalize_call(&negated, instantiation, inner);
+                }
+            }
+        },
+        Operation::Add
+        | Operation::Mul
+        | Operation::BitAnd
+        | Operation::BitOr
+        | Operation::Xor
+        | Operation::Eq
+        | Operation::Neq
+        | Operation::And
+        | Operation::Or => args.sort(),
+        _ => {},
+    }
+    // Distinguish e.g. `exists<A>(addr)` from `exists<B>(addr)`.
+    let name = if instantiation.is_empty() {
+        format!("{:?}", op)
+    } else {
+        format!("{:?}{:?}", op, instantiation)
+    };
+    NormalizedExp::Node(name, args)
+}
+
+// Negate a normalized boolean expression, replacing comparisons by the opposite comparison like
+// `normalize_exp` does for `!`.
+pub fn negate_normalized_exp(exp: &NormalizedExp) -> NormalizedExp {
+    normalize_call(&Operation::Not, &[], vec![exp.clone()])
+}
+
+// Check whether evaluating the expression leaves locals, references and global storage
+// unchanged. Reading global storage and calling functions which only read are allowed.
+pub fn is_side_effect_free(exp: &ExpData, env: &GlobalEnv) -> bool {
+    let mut side_effect_free = true;
+    exp.visit_pre_post(&mut |post: bool, e: &ExpData| {
+        if post {
+            side_effect_free &= match e {
+                ExpData::Assign(..)
+                | ExpData::Mutate(..)
+                | ExpData::Invoke(..)
+                | ExpData::Return(..)
+                | ExpData::LoopCont(..) => false,
+                ExpData::Call(_, Operation::MoveFunction(mid, fid), _) => {
+                    is_side_effect_free_function(&env.get_function(mid.qualified(*fid)))
+                },
+                ExpData::Call(_, op, _) => {
+                    is_side_effect_free_operation(op, env, &mut BTreeSet::new())
+                },
+                _ => true,
+            };
+        }
+        side_effect_free
+    });
+    side_effect_free
+}
+
+// Check whether calling the function leaves the state of its caller and global storage
+// unchanged. Functions taking mutable references are never side effect free, while changes to
+// the function's own locals are allowed. Native functions are only side effect free in a few
+// known framework modules.
+pub fn is_side_effect_free_function(func_env: &FunctionEnv) -> bool {
+    !func_env
+        .get_parameter_types()
+        .iter()
+        .any(|ty| ty.is_mutable_reference())
+        && has_side_effect_free_body(func_env, &mut BTreeSet::new())
+}
+
+fn has_side_effect_free_body(
+    func_env: &FunctionEnv,
+    visited: &mut BTreeSet<QualifiedId<FunId>>,
+) -> bool {
+    // Recursive calls do not add any side effects.
+    if !visited.insert(func_env.get_qualified_id()) {
+        return true;
+    }
+    if func_env.is_native() {
+        return PURE_NATIVE_MODULES
+            .iter()
+            .any(|module| is_framework_module(&func_env.module_env, module));
+    }
+    let def = match func_env.get_def() {
+        Some(def) => def,
+        None => return false,
+    };
+    let env = func_env.module_env.env;
+    let mut side_effect_free = true;
+    def.visit_pre_post(&mut |post: bool, e: &ExpData| {
+        if post {
+            side_effect_free &= match e {
+                ExpData::Invoke(..) => false,
+                ExpData::Call(_, op, _) => is_side_effect_free_operation(op, env, visited),
+                _ => true,
+            };
+        }
+        side_effect_free
+    });
+    side_effect_free
+}
+
+fn is_side_effect_free_operation(
+    op: &Operation,
+    env: &GlobalEnv,
+    visited: &mut BTreeSet<QualifiedId<FunId>>,
+) -> bool {
+    match op {
+        Operation::MoveTo
+        | Operation::MoveFrom
+        | Operation::BorrowGlobal(ReferenceKind::Mutable) => false,
+        Operation::MoveFunction(mid, fid) => {
+            has_side_effect_free_body(&env.get_function(mid.qualified(*fid)), visited)
+        },
+        _ => true,
+    }
+}
+
+// Collect the statements of a sequence. A statement followed by more statements is translated
+// into a sequence whose last element is a nested sequence of the remaining statements. The nested
+// sequences are added to `nested`, so callers visiting every sequence can skip them.
+pub fn get_sequence_statements(exp: &ExpData, nested: &mut BTreeSet<NodeId>) -> Vec<Exp> {
+    let mut statements = Vec::new();
+    let mut current = exp;
+    while let ExpData::Sequence(_, exps) = current {
+        let (last, init) = match exps.split_last() {
+            Some(split) => split,
+            None => break,
+        };
+        statements.extend(init.iter().cloned());
+        current = last.as_ref();
+        match current {
+            ExpData::Sequence(node_id, _) => {
+                nested.insert(*node_id);
+            },
+            _ => statements.push(last.clone()),
+        }
+    }
+    statements
+}
+
+// The targets built by `get_compiler_targets`, stored as an extension of the environment.
+struct CompilerTargets(Rc<FunctionTargetsHolder>);
+
+// Get the unoptimized stackless bytecode of the package functions as generated by the compiler,
+// annotated with its live variable and unreachable code analyses. The bytecode is generated once
+// per environment and shared by all lints inspecting it.
+pub fn get_compiler_targets(env: &GlobalEnv) -> Rc<FunctionTargetsHolder> {
+    if let Some(targets) = env.get_extension::<CompilerTargets>() {
+        return targets.0.clone();
+    }
+    let mut targets = run_bytecode_gen(env);
+    let mut pipeline = FunctionTargetPipeline::default();
+    pipeline.add_processor(Box::new(LiveVarAnalysisProcessor::new(false)));
+    pipeline.add_processor(Box::new(UnreachableCodeProcessor {}));
+    pipeline.run(env, &mut targets);
+    let targets = Rc::new(targets);
+    env.set_extension(CompilerTargets(targets.clone()));
+    targets
+}
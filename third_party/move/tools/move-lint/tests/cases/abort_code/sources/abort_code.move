@@ -0,0 +1,34 @@
+module NamedAddr::Detector {
+    use std::error;
+
+    const E_NOT_OWNER: u64 = 1;
+    const E_INSUFFICIENT_BALANCE: u64 = 2;
+    // Warning: same value as `E_INSUFFICIENT_BALANCE`.
+    const E_ZERO_AMOUNT: u64 = 2;
+    // Warning: never used.
+    const E_UNUSED: u64 = 3;
+
+    // Not an error constant.
+    const MAX_AMOUNT: u64 = 1000;
+
+    // Warning: raw numeric literal.
+    public fun check_literal(amount: u64) {
+        assert!(amount > 0, 7);
+    }
+
+    // Warning: raw numeric literal in `abort`.
+    public fun abort_literal() {
+        abort 42
+    }
+
+    // Warning: not wrapped, while other abort sites use `std::error`.
+    public fun check_owner(owner: address, caller: address) {
+        assert!(owner == caller, E_NOT_OWNER);
+    }
+
+    // No warning: named constant wrapped in an error category.
+    public fun check_balance(balance: u64, amount: u64) {
+        assert!(amount <= MAX_AMOUNT, error::invalid_argument(E_ZERO_AMOUNT));
+        assert!(balance >= amount, error::invalid_state(E_INSUFFICIENT_BALANCE));
+    }
+}
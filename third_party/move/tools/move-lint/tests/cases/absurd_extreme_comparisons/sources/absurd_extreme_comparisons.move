@@ -1,33 +1,49 @@
 module NamedAddr::Detector {
-    fun func1(x: u128) {
+    const MAX_U64: u64 = 18446744073709551615;
+    const E_TOO_SMALL: u64 = 1;
 
-        // let _u64_min = 0;
-        // let _u32_max: u32 = 4294967295;
-        // let _u32_min = 0;
-        // let _u16_max: u16 = 65535;
-        // let _u16_min = 0;
-        // let _u8_max: u8 = 255;
-        // let _u8_min = 0;
+    fun func1(x: u128) {
         let u128_max: u128 = 340282366920938463463374607431768211455;
         let u128_min = 0;
 
-        if (x > u128_max){
+        // Warning: always false.
+        if (x > u128_max) {};
 
-        };
+        // Warning: always false, operands swapped.
+        if (u128_max < x) {};
 
-        if (u128_max <  x){
+        // Warning: always false.
+        if (x < u128_min) {};
 
-        };
-        if (x < u128_min){
+        // Warning: always false, operands swapped.
+        if (u128_min > x) {};
 
-        };
+        // No warning.
+        if (x < u128_max) {};
+    }
 
-        if (u128_min > x){
+    fun func2(a: u8, b: u64, c: u256) {
+        // Warning: always true.
+        assert!(a <= 255, E_TOO_SMALL);
 
-        };
+        // Warning: always true, with a named constant.
+        assert!(MAX_U64 >= b, E_TOO_SMALL);
 
-        if (x < u128_max){
+        // Warning: always true.
+        if (c >= 0) {};
+
+        // No warning: 255 is not the maximum of `u64`.
+        if (b <= 255) {};
+    }
 
+    fun func3(n: u16) {
+        let i = 0;
+        // Warning: always true, so the loop only ends by aborting.
+        while (n <= 65535) {
+            i = i + 1;
+            assert!(i < 10, E_TOO_SMALL);
         };
+        // No warning: `i` is reassigned.
+        if (i > 0) {};
     }
 }
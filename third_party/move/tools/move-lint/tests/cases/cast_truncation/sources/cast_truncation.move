@@ -0,0 +1,39 @@
+module NamedAddr::Detector {
+    public fun always_truncates() {
+        let a: u64 = 300;
+        let _ = (a as u8); // Should trigger a warning
+    }
+
+    public fun truncates_after_guard(x: u128) {
+        assert!(x > 18446744073709551615, 1);
+        let _ = (x as u64); // Should trigger a warning
+    }
+
+    public fun fits(x: u128) {
+        if (x <= 255) {
+            let _ = (x as u8); // Should not trigger a warning
+        }
+    }
+
+    public fun widening(x: u8) {
+        let _ = (x as u256); // Should not trigger a warning
+    }
+
+    public fun unchecked_narrowing(x: u128): u64 {
+        (x as u64) // Should trigger a warning
+    }
+
+    public fun checked_narrowing(x: u128): u64 {
+        assert!(x <= 18446744073709551615, 1);
+        (x as u64) // Should not trigger a warning
+    }
+
+    public fun intermediate_result(a: u64, b: u64): u64 {
+        (((a as u128) * (b as u128)) as u64) // Should trigger a warning
+    }
+
+    public fun bounded_intermediate_result(a: u64, b: u64, c: u64): u64 {
+        let product = (a as u128) * (b as u128);
+        ((product % (c as u128)) as u64) // Should not trigger a warning
+    }
+}
@@ -0,0 +1,72 @@
+module NamedAddr::Detector {
+    const ZERO: u64 = 0;
+    const SCALE: u64 = 100;
+    const E_ZERO_DIVISOR: u64 = 1;
+
+    // Warning: the divisor is a parameter without a guard.
+    public fun unguarded(amount: u64, shares: u64): u64 {
+        amount / shares
+    }
+
+    // Warning: the divisor is a constant equal to zero.
+    public fun constant_zero(amount: u64): u64 {
+        amount / ZERO
+    }
+
+    // Warning: the divisor may be zero after subtracting.
+    public fun derived(amount: u64, total: u64, fee: u64): u64 {
+        let remaining = total - fee;
+        amount % remaining
+    }
+
+    // Warning: the local is always zero.
+    public fun zero_local(amount: u64): u64 {
+        let divisor = 0;
+        amount / divisor
+    }
+
+    public fun guarded_by_assert(amount: u64, shares: u64): u64 {
+        assert!(shares != 0, E_ZERO_DIVISOR);
+        amount / shares
+    }
+
+    public fun guarded_by_if(amount: u64, shares: u64): u64 {
+        if (shares == 0) {
+            return 0
+        };
+        amount / shares
+    }
+
+    public fun guarded_by_branch(amount: u64, shares: u64): u64 {
+        if (shares > 0) amount / shares else 0
+    }
+
+    public fun guarded_by_and(amount: u64, shares: u64): bool {
+        shares != 0 && amount / shares > 1
+    }
+
+    public fun nonzero_constant(amount: u64): u64 {
+        amount / SCALE
+    }
+
+    public fun nonzero_local(amount: u64): u64 {
+        let divisor = SCALE + 1;
+        amount % divisor
+    }
+
+    struct Pool has key {
+        total_shares: u64,
+    }
+
+    // Warning: the divisor is read from a field without a guard.
+    public fun field_unguarded(amount: u64): u64 acquires Pool {
+        let pool = borrow_global<Pool>(@NamedAddr);
+        amount / pool.total_shares
+    }
+
+    public fun field_guarded(amount: u64): u64 acquires Pool {
+        let pool = borrow_global<Pool>(@NamedAddr);
+        assert!(pool.total_shares > 0, E_ZERO_DIVISOR);
+        amount / pool.total_shares
+    }
+}
@@ -0,0 +1,61 @@
+module NamedAddr::Detector {
+    public fun identical_arms(x: u64): u64 {
+        if (x > 10) {
+            x * 2 + 1
+        } else {
+            x * 2 + 1
+        }
+    }
+
+    public fun commuted_arms(x: u64, y: u64): u64 {
+        if (x > y) {
+            x + y
+        } else {
+            y + x
+        }
+    }
+
+    public fun different_arms(x: u64): u64 {
+        if (x > 10) {
+            x * 2
+        } else {
+            x * 3
+        }
+    }
+
+    public fun chain(x: u64): u64 {
+        if (x == 1) {
+            10
+        } else if (x == 2) {
+            20
+        } else if (x == 3) {
+            10
+        } else {
+            30
+        }
+    }
+
+    public fun deposit_fee(amount: u64, rate: u64): u64 {
+        assert!(amount > 0, 1);
+        let fee = amount * rate / 10000;
+        let total = amount + fee;
+        assert!(total >= amount, 2);
+        total
+    }
+
+    public fun withdraw_fee(value: u64, bps: u64): u64 {
+        assert!(value > 0, 3);
+        let cut = value * bps / 10000;
+        let sum = value + cut;
+        assert!(sum >= value, 4);
+        sum
+    }
+
+    public fun transfer_fee(value: u64, bps: u64): u64 {
+        assert!(value > 0, 3);
+        let cut = value * bps / 10000;
+        let sum = value - cut;
+        assert!(sum <= value, 4);
+        sum
+    }
+}
@@ -0,0 +1,82 @@
+module NamedAddr::Detector {
+    use std::signer;
+    use aptos_framework::account;
+    use aptos_framework::event::{Self, EventHandle};
+
+    struct Balance has key {
+        value: u64,
+    }
+
+    struct LegacyStore has key {
+        deposits: EventHandle<DepositEvent>,
+    }
+
+    struct DepositEvent has drop, store {
+        amount: u64,
+    }
+
+    #[event]
+    struct Deposited has drop, store {
+        account: address,
+        amount: u64,
+    }
+
+    #[event]
+    struct Withdrawn has drop, store {
+        account: address,
+        amount: u64,
+    }
+
+    // Emitted type lacks the `#[event]` attribute.
+    public fun emit_without_attribute(amount: u64) {
+        event::emit(DepositEvent { amount });
+    }
+
+    // Deprecated `emit_event` with an `EventHandle`.
+    public fun emit_legacy(account: &signer, amount: u64) acquires LegacyStore {
+        let store = borrow_global_mut<LegacyStore>(signer::address_of(account));
+        event::emit_event(&mut store.deposits, DepositEvent { amount });
+    }
+
+    public fun create_legacy_store(account: &signer) {
+        move_to(account, LegacyStore { deposits: account::new_event_handle<DepositEvent>(account) });
+    }
+
+    // Modifies state without emitting an event.
+    public entry fun silent_deposit(account: &signer, amount: u64) acquires Balance {
+        let balance = borrow_global_mut<Balance>(signer::address_of(account));
+        balance.value = balance.value + amount;
+    }
+
+    // Modifies state through a helper without emitting an event.
+    public entry fun silent_reset(account: &signer) acquires Balance {
+        reset(signer::address_of(account));
+    }
+
+    fun reset(addr: address) acquires Balance {
+        borrow_global_mut<Balance>(addr).value = 0;
+    }
+
+    // No warning: emits an `#[event]` struct of this module.
+    public entry fun deposit(account: &signer, amount: u64) acquires Balance {
+        let addr = signer::address_of(account);
+        let balance = borrow_global_mut<Balance>(addr);
+        balance.value = balance.value + amount;
+        event::emit(Deposited { account: addr, amount });
+    }
+
+    // No warning: the event is emitted by a helper.
+    public entry fun register(account: &signer) {
+        move_to(account, Balance { value: 0 });
+        notify(signer::address_of(account));
+    }
+
+    fun notify(addr: address) {
+        event::emit(Deposited { account: addr, amount: 0 });
+    }
+
+    // No warning: read-only entry function.
+    public entry fun check(addr: address) acquires Balance {
+        assert!(borrow_global<Balance>(addr).value > 0, 1);
+    }
+}
@@ -0,0 +1,62 @@
+module NamedAddr::Detector {
+    use std::signer;
+    use aptos_framework::aptos_coin::AptosCoin;
+    use aptos_framework::coin;
+
+    struct Claimed has key {}
+
+    struct Registered has key {}
+
+    struct Pool has key {
+        reserve: u64,
+    }
+
+    fun pay(from: &signer, to: address) {
+        coin::transfer<AptosCoin>(from, to, 100);
+    }
+
+    // Warning: the first caller marks the reward as claimed and gets paid.
+    public entry fun claim_first(treasury: &signer, winner: &signer) {
+        let addr = signer::address_of(treasury);
+        if (!exists<Claimed>(addr)) {
+            move_to(treasury, Claimed {});
+            coin::transfer<AptosCoin>(treasury, signer::address_of(winner), 100);
+        }
+    }
+
+    // Warning: the same check written with `assert!` and a package helper paying out.
+    public entry fun claim_first_asserted(treasury: &signer, winner: address) {
+        let claimed = exists<Claimed>(signer::address_of(treasury));
+        assert!(!claimed, 1);
+        move_to(treasury, Claimed {});
+        pay(treasury, winner);
+    }
+
+    // No warning: the flag is per user and no funds are moved.
+    public entry fun register(account: &signer) {
+        if (!exists<Registered>(signer::address_of(account))) {
+            move_to(account, Registered {});
+        }
+    }
+
+    // Warning: the minimum output is never compared against.
+    public fun swap(pool_addr: address, amount_in: u64, min_amount_out: u64): u64 acquires Pool {
+        let pool = borrow_global_mut<Pool>(pool_addr);
+        let amount_out = amount_in / 2;
+        pool.reserve = pool.reserve - amount_out;
+        amount_out
+    }
+
+    // Warning: the slippage parameter is ignored.
+    public entry fun swap_with_slippage(pool_addr: address, amount_in: u64, max_slippage: u64) acquires Pool {
+        swap(pool_addr, amount_in, 0);
+    }
+
+    // No warning: the output is checked against the minimum.
+    public fun swap_checked(pool_addr: address, amount_in: u64, min_amount_out: u64): u64 acquires Pool {
+        let minimum = min_amount_out;
+        let amount_out = swap(pool_addr, amount_in, 0);
+        assert!(amount_out >= minimum, 2);
+        amount_out
+    }
+}
@@ -38,4 +38,60 @@ module NamedAddr::Detector {
  
     }
 
+    fun read_only(x: u64): u64 {
+        x + 1
+    }
+
+    fun bump(counter: &mut u64): u64 {
+        *counter = *counter + 1;
+        *counter
+    }
+
+    public fun func2(a: u64, b: u64, counter: &mut u64): u64 {
+        let result = 0;
+        let c = a;
+        if (a < b) {
+            result = 1;
+        } else if (a == b) {
+            result = 2;
+        } else if (b > a) {
+            result = 3;
+        };
+
+        if (a + b == 10) {
+            result = result + 1;
+        };
+        if (10 == b + a) {
+            result = result + 2;
+        };
+
+        if (read_only(a) > 3) {
+            result = 4;
+        };
+        read_only(b);
+        if (3 < read_only(a)) {
+            result = 5;
+        };
+
+        if (c > 5) {
+            c = c - 1;
+        };
+        if (c > 5) {};
+
+        if (bump(counter) > 1) {
+        } else if (bump(counter) > 1) {
+        };
+
+        if (a == 0) {
+            result = 6;
+        } else {
+            result = 6;
+        };
+        if (a == 1) {
+            result = 7;
+        } else {
+            result = 8;
+        };
+        result + c
+    }
 }
\ No newline at end of file
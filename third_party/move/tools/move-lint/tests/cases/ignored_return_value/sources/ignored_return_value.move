@@ -0,0 +1,43 @@
+module NamedAddr::Detector {
+    use std::option::{Self, Option};
+    use std::vector;
+    use aptos_framework::aptos_coin::AptosCoin;
+    use aptos_framework::coin;
+
+    const E_OVERFLOW: u64 = 1;
+
+    public fun checked_add(x: u64, y: u64): u64 {
+        assert!(x <= 18446744073709551615 - y, E_OVERFLOW);
+        x + y
+    }
+
+    public fun ignored_remove(v: &mut vector<u64>) {
+        vector::remove(v, 0);
+    }
+
+    public fun ignored_extract(o: &mut Option<u64>): u64 {
+        option::extract(o);
+        0
+    }
+
+    public fun ignored_contains(v: &vector<u64>, x: u64) {
+        vector::contains(v, &x);
+    }
+
+    public fun ignored_configured(x: u64, y: u64): u64 {
+        checked_add(x, y);
+        x
+    }
+
+    public fun withdraw_and_destroy(account: &signer, amount: u64) {
+        coin::destroy_zero(coin::withdraw<AptosCoin>(account, amount));
+    }
+
+    public fun explicitly_discarded(v: &mut vector<u64>) {
+        let _ = vector::remove(v, 0);
+    }
+
+    public fun used_result(v: &mut vector<u64>): u64 {
+        vector::remove(v, 0)
+    }
+}
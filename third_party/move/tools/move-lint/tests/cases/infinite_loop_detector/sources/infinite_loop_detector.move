@@ -13,4 +13,51 @@ module NamedAddr::Detector {
             counter = counter + 1;
         }
     }
+
+    fun fail() {
+        abort 42
+    }
+
+    public fun loop_exits_with_abort(x: u64) {
+        loop { // No warning: the assertion eventually fails
+            assert!(x > 10, 1);
+            x = x - 1;
+        }
+    }
+
+    public fun loop_exits_with_diverging_call(x: u64) {
+        loop { // No warning: `fail` always aborts
+            if (x == 0) fail();
+            x = x - 1;
+        }
+    }
+
+    public fun nested_break_does_not_exit_outer() {
+        loop { // Should trigger a warning: the break only leaves the inner loop
+            loop {
+                break
+            }
+        }
+    }
+
+    public fun unmodified_condition(n: u64) {
+        let i = 0;
+        let sum = 0;
+        while (i < n) { // Should trigger a warning: neither `i` nor `n` changes
+            sum = sum + 1;
+        };
+    }
+
+    public fun modified_condition(n: u64) {
+        let i = 0;
+        while (i < n) { // Correct usage
+            i = i + 1;
+        };
+    }
+
+    public fun modified_through_reference(counter: &mut u64) {
+        while (*counter < 10) { // Correct usage
+            *counter = *counter + 1;
+        };
+    }
 }
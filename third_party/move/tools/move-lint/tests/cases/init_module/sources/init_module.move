@@ -0,0 +1,51 @@
+module NamedAddr::Detector {
+    use aptos_framework::randomness;
+    use aptos_framework::timestamp;
+
+    struct Config has key {
+        created_at: u64,
+        seed: u64,
+    }
+
+    public entry fun init_module(deployer: &signer, amount: u64) {
+        let seed = randomness::u64_integer();
+        move_to(deployer, Config { created_at: now(), seed });
+        let _ = amount;
+    }
+
+    fun now(): u64 {
+        timestamp::now_seconds()
+    }
+}
+
+module NamedAddr::Detector2 {
+    use aptos_framework::account;
+
+    struct Registry has key {
+        count: u64,
+    }
+
+    // Should not warn: the resource account is created by the deployer.
+    fun init_module(deployer: &signer) {
+        let (resource_signer, _cap) = account::create_resource_account(deployer, b"seed");
+        move_to(&resource_signer, Registry { count: 0 });
+    }
+}
+
+module NamedAddr::Detector3 {
+    use aptos_framework::account::{Self, SignerCapability};
+
+    struct Caps has key {
+        cap: SignerCapability,
+    }
+
+    struct Registry has key {
+        count: u64,
+    }
+
+    // Should warn: the signer comes from a capability stored under another address.
+    fun init_module(_deployer: &signer) acquires Caps {
+        let other = account::create_signer_with_capability(&borrow_global<Caps>(@0xbeef).cap);
+        move_to(&other, Registry { count: 0 });
+    }
+}
@@ -32,4 +32,45 @@ module NamedAddr::Detector {
     public fun divide_by_zero(x: u64): u64 {
         x / 0 // Should trigger a warning, but also note that this is a runtime error
     }
+
+    // Operations of a value with itself
+    public fun compare_with_itself(x: u64): bool {
+        x == x // Should trigger a warning
+    }
+
+    public fun not_equal_to_itself(v: vector<u64>): bool {
+        v != v // Should trigger a warning
+    }
+
+    public fun subtract_itself(a: u64): u64 {
+        a - a // Should trigger a warning
+    }
+
+    public fun divide_by_itself(x: u64): u64 {
+        x / x // Should trigger a warning
+    }
+
+    public fun compare_with_alias(x: u64): bool {
+        let y = x;
+        y > x // Should trigger a warning
+    }
+
+    public fun compare_with_reference(x: u64): bool {
+        let r = &x;
+        *r <= x // Should trigger a warning
+    }
+
+    public fun condition_and_negation(x: u64, y: u64): bool {
+        x < y || x >= y // Should trigger a warning
+    }
+
+    public fun if_with_condition_branches(c: bool): bool {
+        if (c) c else c // Should trigger a warning
+    }
+
+    public fun compare_with_modified_copy(x: u64): bool {
+        let y = x;
+        x = x + 1;
+        y == x
+    }
 }
@@ -12,4 +12,83 @@ module NamedAddr::Detector {
         let arr = vector[1, 2, 3, 4, 5];
         vector::borrow(&arr, 10);
     }
+
+    // Warning: the vector only has two elements after the pushes.
+    public fun pushed_indexing() {
+        let arr = vector::empty<u64>();
+        vector::push_back(&mut arr, 1);
+        vector::push_back(&mut arr, 2);
+        vector::borrow(&arr, 2);
+    }
+
+    // Warning: the element at index 2 was popped.
+    public fun popped_indexing() {
+        let arr = vector[1, 2, 3];
+        vector::pop_back(&mut arr);
+        vector::borrow_mut(&mut arr, 2);
+    }
+
+    // Warning: the vector is empty.
+    public fun pop_empty() {
+        let arr = vector::empty<u64>();
+        vector::pop_back(&mut arr);
+    }
+
+    // No warning: the vector is modified by another function.
+    public fun modified_elsewhere() {
+        let arr = vector::empty<u64>();
+        fill(&mut arr);
+        vector::borrow(&arr, 3);
+    }
+
+    fun fill(arr: &mut vector<u64>) {
+        vector::push_back(arr, 1);
+    }
+
+    // Warning: `i` can equal the length of `v`.
+    public fun off_by_one(v: &vector<u64>): u64 {
+        let sum = 0;
+        let i = 0;
+        while (i <= vector::length(v)) {
+            sum = sum + *vector::borrow(v, i);
+            i = i + 1;
+        };
+        sum
+    }
+
+    // Warning: the length is read into a local first.
+    public fun off_by_one_with_local(v: vector<u64>): u64 {
+        let sum = 0;
+        let i = 0;
+        let len = vector::length(&v);
+        while (i <= len) {
+            sum = sum + *vector::borrow(&v, i);
+            i = i + 1;
+        };
+        sum
+    }
+
+    // No warning: the loop bound is exclusive.
+    public fun correct_loop(v: &vector<u64>): u64 {
+        let sum = 0;
+        let i = 0;
+        while (i < vector::length(v)) {
+            sum = sum + *vector::borrow(v, i);
+            i = i + 1;
+        };
+        sum
+    }
+
+    // No warning: the loop is left before the index reaches the length.
+    public fun early_exit(v: &vector<u64>): u64 {
+        let sum = 0;
+        let i = 0;
+        let n = vector::length(v);
+        while (i <= n) {
+            if (i == n) break;
+            sum = sum + *vector::borrow(v, i);
+            i = i + 1;
+        };
+        sum
+    }
 }
@@ -1,4 +1,6 @@
 module NamedAddr::Detector {
+    const LARGE: u128 = 340282366920938463463374607431768211455;
+
     public fun safe_multiplication() {
         let a: u64 = 10;
         let b: u64 = 20;
@@ -10,4 +12,27 @@ module NamedAddr::Detector {
         let b: u64 = 2_000_000_000_000_000;
         let _ = a * b; // Should trigger a warning
     }
+
+    public fun overflow_u8_addition() {
+        let a: u8 = 200;
+        let b = a + 100; // Should trigger a warning
+        let _ = b;
+    }
+
+    public fun overflow_constant(x: u128) {
+        let large = LARGE;
+        let _ = large + 1; // Should trigger a warning
+        let _ = x * 2; // Should not trigger a warning
+    }
+
+    public fun overflow_after_guard(x: u64) {
+        assert!(x > 10_000_000_000, 1);
+        let _ = x * 10_000_000_000; // Should trigger a warning
+    }
+
+    public fun guarded_multiplication(x: u64) {
+        if (x < 1000) {
+            let _ = x * 1000; // Should not trigger a warning
+        }
+    }
 }
@@ -0,0 +1,46 @@
+module NamedAddr::Detector {
+    use aptos_framework::dispatchable_fungible_asset;
+    use aptos_framework::fungible_asset::{FungibleAsset, FungibleStore};
+    use aptos_framework::object::Object;
+    use aptos_framework::primary_fungible_store;
+
+    struct Vault has key {
+        total: u64,
+        withdrawn: u64,
+    }
+
+    // Should warn: `vault` is still used after the hook may have run.
+    public fun withdraw_held(user: &signer, store: Object<FungibleStore>, amount: u64): FungibleAsset acquires Vault {
+        let vault = borrow_global_mut<Vault>(@NamedAddr);
+        vault.total = vault.total - amount;
+        let fa = dispatchable_fungible_asset::withdraw(user, store, amount);
+        vault.withdrawn = vault.withdrawn + amount;
+        fa
+    }
+
+    // Should warn: state is split into a write before and a write after the hook.
+    public fun deposit_partial(store: Object<FungibleStore>, fa: FungibleAsset, amount: u64) acquires Vault {
+        borrow_global_mut<Vault>(@NamedAddr).total = borrow_global<Vault>(@NamedAddr).total + amount;
+        dispatchable_fungible_asset::deposit(store, fa);
+        borrow_global_mut<Vault>(@NamedAddr).withdrawn = 0;
+    }
+
+    // Should not warn: all state updates happen before the hook.
+    public fun withdraw_ok(user: &signer, store: Object<FungibleStore>, amount: u64): FungibleAsset acquires Vault {
+        let vault = borrow_global_mut<Vault>(@NamedAddr);
+        vault.total = vault.total - amount;
+        vault.withdrawn = vault.withdrawn + amount;
+        dispatchable_fungible_asset::withdraw(user, store, amount)
+    }
+
+    // Should not warn: `vault` is only used on the branch which does not call the hook.
+    public fun deposit_or_record(store: Object<FungibleStore>, fa: FungibleAsset, amount: u64, direct: bool) acquires Vault {
+        let vault = borrow_global_mut<Vault>(@NamedAddr);
+        if (direct) {
+            dispatchable_fungible_asset::deposit(store, fa);
+        } else {
+            vault.total = vault.total + amount;
+            primary_fungible_store::deposit(@NamedAddr, fa);
+        }
+    }
+}
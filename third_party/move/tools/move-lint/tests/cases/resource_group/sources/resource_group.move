@@ -0,0 +1,63 @@
+module NamedAddr::Detector {
+    // Valid container.
+    #[resource_group(scope = global)]
+    struct ValidGroup {}
+
+    // Container with fields and abilities.
+    #[resource_group(scope = global)]
+    struct GroupWithFields has drop {
+        value: u64,
+    }
+
+    // Container with an unknown scope.
+    #[resource_group(scope = package)]
+    struct InvalidScopeGroup {}
+
+    // Container missing the scope parameter.
+    #[resource_group]
+    struct NoScopeGroup {}
+
+    // Container restricted to this module.
+    #[resource_group(scope = module_scope)]
+    struct ModuleGroup {}
+
+    struct NotAGroup {}
+
+    // Valid member.
+    #[resource_group_member(group = NamedAddr::Detector::ValidGroup)]
+    struct ValidMember has key {
+        value: u64,
+    }
+
+    // Member without the key ability.
+    #[resource_group_member(group = NamedAddr::Detector::ValidGroup)]
+    struct NoKeyMember has store {
+        value: u64,
+    }
+
+    // Member of a struct which is not a resource group.
+    #[resource_group_member(group = NamedAddr::Detector::NotAGroup)]
+    struct WrongContainerMember has key {
+        value: u64,
+    }
+
+    // Member of a group which does not exist.
+    #[resource_group_member(group = NamedAddr::Detector::MissingGroup)]
+    struct MissingContainerMember has key {
+        value: u64,
+    }
+}
+
+module NamedAddr::Other {
+    // Member outside the scope of its container.
+    #[resource_group_member(group = NamedAddr::Detector::ModuleGroup)]
+    struct OutOfScopeMember has key {
+        value: u64,
+    }
+
+    // Valid member from another module of the same address.
+    #[resource_group_member(group = NamedAddr::Detector::ValidGroup)]
+    struct OtherMember has key {
+        value: u64,
+    }
+}
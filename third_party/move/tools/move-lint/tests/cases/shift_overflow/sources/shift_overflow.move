@@ -10,10 +10,17 @@ module NamedAddr::Detector {
         let _u128 = (x as u128);
         let _u256 = (x as u256);
 
-        let _b = _u8 << 8;
-        let _b = _u16 << 16;
-        let _b = _u32 << 32;
-        let _b = _u128 << 128;
+        let _b = _u8 << 8; // <Issue:5>
+        let _b = _u16 << 16; // <Issue:5>
+        let _b = _u32 << 32; // <Issue:5>
+        let _b = _u128 << 128; // <Issue:5>
         let _b = _u256 << 128;
     }
-}
\ No newline at end of file
+
+    public fun shift_by_local(x: u32) {
+        let amount = 16;
+        let _b = x << amount;
+        let amount = amount * 2;
+        let _b = x >> amount; // <Issue:5>
+    }
+}
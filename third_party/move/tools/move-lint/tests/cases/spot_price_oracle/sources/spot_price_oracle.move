@@ -0,0 +1,37 @@
+module NamedAddr::Detector {
+    struct Pool has key {
+        reserve_x: u64,
+        reserve_y: u64,
+        price_cumulative_last: u128,
+        last_update: u64,
+    }
+
+    // Warning: spot price of the pool.
+    public fun get_price(pool_addr: address): u64 acquires Pool {
+        let pool = borrow_global<Pool>(pool_addr);
+        pool.reserve_y * 1_000_000 / pool.reserve_x
+    }
+
+    // Warning: the spot price is bound to a `price` local.
+    public fun collateral_value(pool_addr: address, amount: u64): u64 acquires Pool {
+        let pool = borrow_global<Pool>(pool_addr);
+        let reserve_x = pool.reserve_x;
+        let reserve_y = pool.reserve_y;
+        let price = reserve_y / reserve_x;
+        amount * price
+    }
+
+    // No warning: swap output is not a price.
+    public fun get_amount_out(pool_addr: address, amount_in: u64): u64 acquires Pool {
+        let pool = borrow_global<Pool>(pool_addr);
+        amount_in * pool.reserve_y / (pool.reserve_x + amount_in)
+    }
+
+    // No warning: time-weighted average price.
+    public fun get_twap_price(pool_addr: address, cumulative_start: u128, elapsed: u64): u128 acquires Pool {
+        let pool = borrow_global<Pool>(pool_addr);
+        let reserve_ratio = (pool.reserve_y as u128) / (pool.reserve_x as u128);
+        let price = (pool.price_cumulative_last - cumulative_start) / (elapsed as u128);
+        price + reserve_ratio * 0
+    }
+}
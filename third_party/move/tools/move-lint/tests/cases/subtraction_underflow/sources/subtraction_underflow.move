@@ -0,0 +1,25 @@
+module NamedAddr::Detector {
+    const MIN_BALANCE: u64 = 100;
+
+    public fun always_underflows() {
+        let a: u64 = 10;
+        let b: u64 = 20;
+        let _ = a - b; // Should trigger a warning
+    }
+
+    public fun underflow_with_constant(x: u64) {
+        if (x < 50) {
+            let _ = x - MIN_BALANCE; // Should trigger a warning
+        }
+    }
+
+    public fun may_underflow(x: u64, y: u64) {
+        let _ = x - y; // Should not trigger a warning
+    }
+
+    public fun guarded(x: u64) {
+        if (x >= MIN_BALANCE) {
+            let _ = x - MIN_BALANCE; // Should not trigger a warning
+        }
+    }
+}
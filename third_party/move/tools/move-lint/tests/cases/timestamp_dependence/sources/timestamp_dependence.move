@@ -0,0 +1,54 @@
+module NamedAddr::Detector {
+    use aptos_framework::aptos_coin::AptosCoin;
+    use aptos_framework::block;
+    use aptos_framework::coin;
+    use aptos_framework::timestamp;
+    use aptos_framework::transaction_context;
+
+    const LOTTERY_END: u64 = 1_700_000_000;
+
+    fun now(): u64 {
+        timestamp::now_seconds()
+    }
+
+    // Warning: the payout depends on the block timestamp.
+    public entry fun claim_if_even(account: &signer, to: address) {
+        if (timestamp::now_seconds() % 2 == 0) {
+            coin::transfer<AptosCoin>(account, to, 100);
+        }
+    }
+
+    // Warning: the local is derived from the block height.
+    public entry fun claim_by_height(account: &signer, to: address) {
+        let height = block::get_current_block_height();
+        assert!(height % 10 == 0, 1);
+        coin::transfer<AptosCoin>(account, to, 100);
+    }
+
+    // Warning: the timestamp is read through a package helper.
+    public entry fun claim_after_end(account: &signer, to: address) {
+        if (now() > LOTTERY_END) {
+            coin::transfer<AptosCoin>(account, to, 100);
+        }
+    }
+
+    // Warning: the transaction hash is chosen by the sender.
+    public entry fun claim_by_hash(account: &signer, to: address) {
+        let hash = transaction_context::get_transaction_hash();
+        if (*std::vector::borrow(&hash, 0) == 0) {
+            coin::transfer<AptosCoin>(account, to, 100);
+        }
+    }
+
+    // No warning: no funds are moved.
+    public fun is_expired(): bool {
+        timestamp::now_seconds() > LOTTERY_END
+    }
+
+    // No warning: the condition does not depend on the environment.
+    public entry fun claim(account: &signer, to: address, amount: u64) {
+        if (amount > 0) {
+            coin::transfer<AptosCoin>(account, to, amount);
+        }
+    }
+}
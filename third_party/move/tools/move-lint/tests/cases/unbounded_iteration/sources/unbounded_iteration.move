@@ -0,0 +1,107 @@
+module NamedAddr::Detector {
+    use std::vector;
+    use aptos_std::simple_map::{Self, SimpleMap};
+
+    struct Registry has key {
+        members: vector<address>,
+        scores: SimpleMap<address, u64>,
+    }
+
+    struct Admins has key {
+        admins: vector<address>,
+    }
+
+    // Any caller can grow `Registry.members` and `Registry.scores`.
+    public entry fun join(account: address, score: u64) acquires Registry {
+        let registry = borrow_global_mut<Registry>(@NamedAddr);
+        vector::push_back(&mut registry.members, account);
+        simple_map::add(&mut registry.scores, account, score);
+    }
+
+    // `Admins.admins` is only grown by a private function which is never called publicly.
+    fun add_admin(admin: address) acquires Admins {
+        vector::push_back(&mut borrow_global_mut<Admins>(@NamedAddr).admins, admin);
+    }
+
+    // Warning: loop bounded by the length of a growable collection.
+    public fun count_members(): u64 acquires Registry {
+        let registry = borrow_global<Registry>(@NamedAddr);
+        let i = 0;
+        let count = 0;
+        while (i < vector::length(&registry.members)) {
+            count = count + 1;
+            i = i + 1;
+        };
+        count
+    }
+
+    // Warning: length cached in a local before the loop.
+    public fun last_member(): address acquires Registry {
+        let members = &borrow_global<Registry>(@NamedAddr).members;
+        let len = vector::length(members);
+        let i = 0;
+        let last = @0x0;
+        loop {
+            if (i >= len) break;
+            last = *vector::borrow(members, i);
+            i = i + 1;
+        };
+        last
+    }
+
+    // Warning: `for_each` style iteration over a growable collection.
+    public fun is_member(account: address): bool acquires Registry {
+        vector::contains(&borrow_global<Registry>(@NamedAddr).members, &account)
+    }
+
+    // Warning: iterating the keys of a growable map.
+    public fun all_scored(): vector<address> acquires Registry {
+        simple_map::keys(&borrow_global<Registry>(@NamedAddr).scores)
+    }
+
+    // No warning: the collection is not growable by callers.
+    public fun count_admins(): u64 acquires Admins {
+        let admins = &borrow_global<Admins>(@NamedAddr).admins;
+        let i = 0;
+        while (i < vector::length(admins)) {
+            i = i + 1;
+        };
+        i
+    }
+
+    // No warning: loop bounded by a caller provided limit.
+    public fun count_up_to(limit: u64): u64 {
+        let i = 0;
+        while (i < limit) {
+            i = i + 1;
+        };
+        i
+    }
+
+    // No warning: the length of a growable collection is only read in the body, not in the
+    // exit condition.
+    public fun sum_sizes(limit: u64): u64 acquires Registry {
+        let registry = borrow_global<Registry>(@NamedAddr);
+        let i = 0;
+        let total = 0;
+        while (i < limit) {
+            total = total + vector::length(&registry.members);
+            i = i + 1;
+        };
+        total
+    }
+
+    struct Balances has key {
+        balances: aptos_std::smart_table::SmartTable<address, u64>,
+    }
+
+    // Any caller can grow `Balances.balances`.
+    public entry fun deposit(account: address, amount: u64) acquires Balances {
+        aptos_std::smart_table::add(&mut borrow_global_mut<Balances>(@NamedAddr).balances, account, amount);
+    }
+
+    // Warning: iterating the keys of a growable table.
+    public fun all_holders(): vector<address> acquires Balances {
+        aptos_std::smart_table::keys(&borrow_global<Balances>(@NamedAddr).balances)
+    }
+}
@@ -1,8 +1,13 @@
 module NamedAddr::Detector {
+    use std::vector;
+
     struct MyStruct has key {
         value: u64,
+        items: vector<u64>,
     }
 
+    native fun native_helper(my_struct: &mut MyStruct);
+
     public fun function_do_not_modify(my_struct: &mut MyStruct): u64 {
         my_struct.value
     }
@@ -11,4 +16,39 @@ module NamedAddr::Detector {
         my_struct.value = 42;
     }
 
+    public fun function_modify_deref(counter: &mut u64): u64 {
+        let current = *counter;
+        *counter = current + 1;
+        current
+    }
+
+    public fun function_modify_derived_ref(my_struct: &mut MyStruct) {
+        let value_ref = &mut my_struct.value;
+        *value_ref = 0;
+    }
+
+    public fun function_push_back(my_struct: &mut MyStruct) {
+        vector::push_back(&mut my_struct.items, 1);
+    }
+
+    public fun function_borrow_mut(items: &mut vector<u64>) {
+        let item = vector::borrow_mut(items, 0);
+        *item = 1;
+    }
+
+    public fun function_pass_to_callee(my_struct: &mut MyStruct) {
+        function_modify_mut_arg(my_struct);
+    }
+
+    public fun function_borrow_mut_only_read(items: &mut vector<u64>): u64 {
+        *vector::borrow_mut(items, 0)
+    }
+
+    public fun function_return_field(my_struct: &mut MyStruct): &mut u64 {
+        &mut my_struct.value
+    }
+
+    public fun function_return_borrow_mut(my_struct: &mut MyStruct): &mut u64 {
+        vector::borrow_mut(&mut my_struct.items, 0)
+    }
 }
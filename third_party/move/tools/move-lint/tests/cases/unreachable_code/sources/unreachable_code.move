@@ -0,0 +1,51 @@
+module NamedAddr::Detector {
+    const E_INVALID: u64 = 1;
+
+    public fun after_abort(x: u64): u64 {
+        abort E_INVALID;
+        x + 1
+    }
+
+    public fun after_return(x: u64): u64 {
+        if (x > 10) {
+            return x;
+            x = x - 1;
+        };
+        x
+    }
+
+    public fun after_assert_false(x: u64): u64 {
+        assert!(false, E_INVALID);
+        x * 2
+    }
+
+    public fun after_infinite_loop(x: u64): u64 {
+        loop {
+            x = x + 1;
+        };
+        x
+    }
+
+    public fun after_diverging_branches(x: u64): u64 {
+        if (x > 10) {
+            abort E_INVALID
+        } else {
+            return 0
+        };
+        x
+    }
+
+    public fun loop_with_break(x: u64): u64 {
+        loop {
+            if (x > 10) break;
+            x = x + 1;
+        };
+        x
+    }
+
+    public fun abort_at_end(x: u64) {
+        if (x == 0) {
+            abort E_INVALID;
+        };
+    }
+}
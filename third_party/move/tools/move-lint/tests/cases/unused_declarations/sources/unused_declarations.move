@@ -0,0 +1,57 @@
+module NamedAddr::Detector {
+    use std::signer;
+    // Warning: never used.
+    use std::string;
+    use std::vector::{Self, length};
+    // Warning: `borrow` is never used.
+    use std::option::{Option, borrow};
+
+    const MAX_ITEMS: u64 = 10;
+    // Warning: never read.
+    const UNUSED_LIMIT: u64 = 20;
+
+    struct Inventory has key {
+        items: vector<u64>,
+        best: Option<u64>,
+    }
+
+    // Warning: never constructed or referenced.
+    struct Orphan has drop {
+        value: u64,
+    }
+
+    #[deprecated]
+    struct Legacy has drop {
+        value: u64,
+    }
+
+    public entry fun add(account: &signer, item: u64) acquires Inventory {
+        let inventory = borrow_global_mut<Inventory>(signer::address_of(account));
+        assert!(length(&inventory.items) < MAX_ITEMS, 1);
+        vector::push_back(&mut inventory.items, double(item));
+    }
+
+    fun double(value: u64): u64 {
+        value * 2
+    }
+
+    // Warning: never called.
+    fun triple(value: u64): u64 {
+        value * 3
+    }
+
+    // Warning: only calls itself.
+    fun countdown(value: u64): u64 {
+        if (value == 0) 0 else countdown(value - 1)
+    }
+
+    #[deprecated]
+    fun old_helper(): u64 {
+        1
+    }
+
+    #[test_only]
+    fun test_helper(): u64 {
+        2
+    }
+}
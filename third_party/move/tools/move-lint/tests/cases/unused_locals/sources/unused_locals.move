@@ -0,0 +1,43 @@
+module NamedAddr::Detector {
+    use std::vector;
+
+    fun compute(x: u64): u64 {
+        x * 2
+    }
+
+    public fun never_read(x: u64): u64 {
+        let unused = compute(x);
+        x + 1
+    }
+
+    public fun overwritten(x: u64): u64 {
+        let y = compute(x);
+        y = x + 1;
+        y
+    }
+
+    public fun intentionally_unused(x: u64): u64 {
+        let _ignored = compute(x);
+        x
+    }
+
+    public fun used_in_loop(n: u64): u64 {
+        let i = 0;
+        let sum = 0;
+        while (i < n) {
+            sum = sum + i;
+            i = i + 1;
+        };
+        sum
+    }
+
+    public fun discarded_pure(x: u64): u64 {
+        let _ = compute(x);
+        x
+    }
+
+    public fun discarded_with_effect(v: &mut vector<u64>): u64 {
+        let _ = vector::pop_back(v);
+        vector::length(v)
+    }
+}
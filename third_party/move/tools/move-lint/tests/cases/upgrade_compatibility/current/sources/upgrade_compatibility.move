@@ -0,0 +1,48 @@
+module NamedAddr::Detector {
+    // Removed the friend declaration of `NamedAddr::Helper`.
+
+    // Changed layout: added a field.
+    struct Config has key, store {
+        admin: address,
+        fee: u64,
+        paused: bool,
+    }
+
+    // Dropped the `copy` ability.
+    struct Receipt has drop, store {
+        amount: u64,
+    }
+
+    // Removed struct `Removed` and function `removed_function`.
+
+    // Changed return type.
+    public fun fee(config: &Config): u128 {
+        (config.fee as u128)
+    }
+
+    // Reduced visibility.
+    public(friend) fun amount(receipt: &Receipt): u64 {
+        receipt.amount
+    }
+
+    // Changed entry signature.
+    public entry fun set_fee(_account: &signer, _fee: u128) {}
+
+    // Changed view signature.
+    #[view]
+    fun current_fee(_scale: u64): u64 {
+        0
+    }
+
+    // No warning: unchanged public function.
+    public fun unchanged(value: u64): u64 {
+        value
+    }
+
+    // No warning: new functions can be added.
+    public fun added(): u64 {
+        1
+    }
+}
+
+// Removed module `NamedAddr::Helper`.
@@ -0,0 +1,43 @@
+module NamedAddr::Detector {
+    friend NamedAddr::Helper;
+
+    struct Config has key, store {
+        admin: address,
+        fee: u64,
+    }
+
+    struct Receipt has copy, drop, store {
+        amount: u64,
+    }
+
+    struct Removed has drop {}
+
+    public fun fee(config: &Config): u64 {
+        config.fee
+    }
+
+    public fun amount(receipt: &Receipt): u64 {
+        receipt.amount
+    }
+
+    public fun removed_function(): u64 {
+        0
+    }
+
+    public entry fun set_fee(_account: &signer, _fee: u64) {}
+
+    #[view]
+    fun current_fee(): u64 {
+        0
+    }
+
+    public fun unchanged(value: u64): u64 {
+        value
+    }
+}
+
+module NamedAddr::Helper {
+    public fun helper(): u64 {
+        1
+    }
+}
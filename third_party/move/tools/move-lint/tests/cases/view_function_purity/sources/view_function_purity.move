@@ -0,0 +1,72 @@
+module NamedAddr::Detector {
+    use std::vector;
+    use aptos_framework::event;
+
+    struct Counter has key {
+        value: u64,
+    }
+
+    struct Registry has key {
+        members: vector<address>,
+    }
+
+    #[event]
+    struct Viewed has drop, store {
+        value: u64,
+    }
+
+    #[view]
+    public fun get_and_bump(): u64 acquires Counter {
+        let counter = borrow_global_mut<Counter>(@NamedAddr);
+        counter.value = counter.value + 1;
+        counter.value
+    }
+
+    #[view]
+    public fun get_with_signer(account: &signer): u64 acquires Counter {
+        let _ = account;
+        borrow_global<Counter>(@NamedAddr).value
+    }
+
+    #[view]
+    public fun get_and_log(): u64 acquires Counter {
+        log(borrow_global<Counter>(@NamedAddr).value)
+    }
+
+    fun log(value: u64): u64 {
+        event::emit(Viewed { value });
+        value
+    }
+
+    #[view]
+    public fun all_members(): vector<address> acquires Registry {
+        let registry = borrow_global<Registry>(@NamedAddr);
+        let result = vector::empty();
+        let i = 0;
+        while (i < vector::length(&registry.members)) {
+            vector::push_back(&mut result, *vector::borrow(&registry.members, i));
+            i = i + 1;
+        };
+        result
+    }
+
+    #[view]
+    public fun get(): u64 acquires Counter {
+        borrow_global<Counter>(@NamedAddr).value
+    }
+
+    #[view]
+    public fun registered(addrs: vector<address>): vector<address> acquires Registry {
+        let registry = borrow_global<Registry>(@NamedAddr);
+        let result = vector::empty();
+        let i = 0;
+        while (i < vector::length(&addrs)) {
+            let addr = *vector::borrow(&addrs, i);
+            if (vector::contains(&registry.members, &addr)) {
+                vector::push_back(&mut result, addr);
+            };
+            i = i + 1;
+        };
+        result
+    }
+}
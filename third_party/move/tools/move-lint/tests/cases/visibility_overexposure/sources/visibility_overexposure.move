@@ -0,0 +1,42 @@
+module NamedAddr::Detector {
+    friend NamedAddr::Consumer;
+    friend NamedAddr::Unrelated;
+
+    // Warning: only called from within this module.
+    public fun internal_helper(value: u64): u64 {
+        value + 1
+    }
+
+    // Warning: only called from within this package.
+    public fun shared_helper(value: u64): u64 {
+        value + 2
+    }
+
+    // No warning: not called anywhere, part of the public API.
+    public fun api(value: u64): u64 {
+        value * 2
+    }
+
+    // No warning: already restricted to friends.
+    public(friend) fun friend_helper(value: u64): u64 {
+        value - 1
+    }
+
+    // No warning: entry functions are called by transactions.
+    public entry fun run() {
+        shared_helper(internal_helper(1));
+    }
+}
+
+module NamedAddr::Consumer {
+    use NamedAddr::Detector;
+
+    public entry fun consume() {
+        Detector::friend_helper(Detector::shared_helper(2));
+    }
+}
+
+// Warning in `Detector`: `Unrelated` is a friend but never calls a friend function.
+module NamedAddr::Unrelated {
+    public fun noop() {}
+}
@@ -1,11 +1,49 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{fs, path::PathBuf};
+use std::{fs, path::PathBuf, sync::Mutex};
 
 use clap::Parser;
 use codespan_reporting::term::{emit, termcolor::Buffer, Config};
-use move_lint::lint::Args;
+use move_lint::lint::{Args, LintLevel};
+
+/// `lint::main` changes the current directory while building a package, so cases are linted one
+/// at a time.
+static LINT_LOCK: Mutex<()> = Mutex::new(());
+
+/// Lints the package of a test case with all lints and returns the message and line of each
+/// diagnostic.
+fn lint_case(case: &str) -> Vec<(String, usize)> {
+    let _guard = LINT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let args = Args {
+        input_file: PathBuf::from("tests/cases").join(case),
+        level: LintLevel::All,
+        compat_against: None,
+    };
+    let (diags, files) = move_lint::lint::main(args);
+    diags
+        .iter()
+        .filter_map(|diag| {
+            let label = diag.labels.first()?;
+            let line = files.line_index(label.file_id, label.range.start as u32);
+            Some((diag.message.clone(), line.to_usize() + 1))
+        })
+        .collect()
+}
+
+/// Returns true if a diagnostic starting with `message` is reported at `line`.
+fn is_reported(diags: &[(String, usize)], message: &str, line: usize) -> bool {
+    diags
+        .iter()
+        .any(|(diag_message, diag_line)| diag_message.starts_with(message) && *diag_line == line)
+}
+
+/// Returns true if a diagnostic starting with `message` is reported anywhere.
+fn has_message(diags: &[(String, usize)], message: &str) -> bool {
+    diags
+        .iter()
+        .any(|(diag_message, _)| diag_message.starts_with(message))
+}
 
 #[test]
 fn tesqt_modules() {
@@ -14,6 +52,7 @@ fn tesqt_modules() {
     let args = Args {
         input_file: path,
         level: move_lint::lint::LintLevel::All,
+        compat_against: None,
     };
     let (diags, files) = move_lint::lint::main(args);
     // let mut writer = Buffer::no_color();
@@ -24,3 +63,738 @@ fn tesqt_modules() {
     // let rendered_diags = std::str::from_utf8(&diag_buffer).unwrap();
     // fs::write(output_path, rendered_diags);
 }
+
+#[test]
+fn test_upgrade_compatibility() {
+    let _guard = LINT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let path = PathBuf::from("tests/cases/upgrade_compatibility");
+    let args = Args {
+        input_file: path.join("current"),
+        level: move_lint::lint::LintLevel::Default,
+        compat_against: Some(path.join("published")),
+    };
+    let (diags, files) = move_lint::lint::main(args);
+    let diags: Vec<(String, Option<usize>)> = diags
+        .iter()
+        .map(|diag| {
+            let line = diag.labels.first().map(|label| {
+                files
+                    .line_index(label.file_id, label.range.start as u32)
+                    .to_usize()
+                    + 1
+            });
+            (diag.message.clone(), line)
+        })
+        .collect();
+    let is_reported_at = |message: &str, line: usize| {
+        diags.iter().any(|(diag_message, diag_line)| {
+            diag_message.starts_with(message) && *diag_line == Some(line)
+        })
+    };
+    let is_reported_anywhere = |message: &str| {
+        diags
+            .iter()
+            .any(|(diag_message, _)| diag_message.starts_with(message))
+    };
+    // Breaking changes with a location in the current package.
+    assert!(is_reported_at("The layout of struct `Config` changed.", 5));
+    assert!(is_reported_at("Struct `Receipt` dropped abilities", 12));
+    assert!(is_reported_at(
+        "Public function signature changed from `fee(",
+        19
+    ));
+    assert!(is_reported_at(
+        "Public function `amount` is no longer public.",
+        24
+    ));
+    assert!(is_reported_at(
+        "Public function signature changed from `set_fee(",
+        29
+    ));
+    assert!(is_reported_at(
+        "View function signature changed from `current_fee(): (u64)` to `current_fee(u64): (u64)`.",
+        33
+    ));
+    // Removed declarations are reported on the module or without a location.
+    assert!(is_reported_anywhere(
+        "Struct `Detector::Removed` was removed."
+    ));
+    assert!(is_reported_anywhere(
+        "Public function `Detector::removed_function` was removed."
+    ));
+    assert!(diags.iter().any(|(message, line)| {
+        message.starts_with("Friend declaration of `")
+            && message.contains("::Helper`")
+            && line.is_some()
+    }));
+    assert!(diags.iter().any(|(message, line)| {
+        message.starts_with("Module `")
+            && message.contains("::Helper` of the published package was removed.")
+            && line.is_none()
+    }));
+    // Unchanged and added functions are compatible.
+    assert!(!diags
+        .iter()
+        .any(|(message, _)| message.contains("`unchanged") || message.contains("`added")));
+}
+
+#[test]
+fn test_reentrancy_dispatch() {
+    let diags = lint_case("reentrancy_dispatch");
+    let held = "Mutable global reference is held across a call to";
+    let split = "Global state is updated both before and after a call to";
+    assert!(is_reported(&diags, held, 16));
+    assert!(is_reported(&diags, split, 24));
+    for line in [33, 40] {
+        assert!(!is_reported(&diags, held, line));
+        assert!(!is_reported(&diags, split, line));
+    }
+}
+
+#[test]
+fn test_init_module() {
+    let diags = lint_case("init_module");
+    let publish =
+        "`init_module` publishes this resource under a signer not derived from the deployer.";
+    assert!(is_reported(
+        &diags,
+        "`init_module` function should be private",
+        10
+    ));
+    assert!(is_reported(
+        &diags,
+        "`init_module` should not use `randomness`",
+        11
+    ));
+    assert!(is_reported(
+        &diags,
+        "`init_module` depends on `timestamp`",
+        17
+    ));
+    assert!(!is_reported(&diags, publish, 12));
+    assert!(!is_reported(&diags, publish, 31));
+    assert!(is_reported(&diags, publish, 49));
+}
+
+#[test]
+fn test_view_function_purity() {
+    let diags = lint_case("view_function_purity");
+    assert!(is_reported(
+        &diags,
+        "View function `get_and_bump` performs borrow_global_mut.",
+        20
+    ));
+    assert!(is_reported(
+        &diags,
+        "View function `get_with_signer` takes signer `account`.",
+        26
+    ));
+    assert!(is_reported(
+        &diags,
+        "View function `get_and_log` performs event emission through `Detector::log`.",
+        37
+    ));
+    assert!(is_reported(
+        &diags,
+        "View function `all_members` returns a vector built by iterating global storage",
+        47
+    ));
+    assert!(!has_message(&diags, "View function `get` "));
+    assert!(!has_message(&diags, "View function `registered` "));
+}
+
+#[test]
+fn test_event_emission() {
+    let diags = lint_case("event_emission");
+    assert!(is_reported(&diags, "`event::emit` called with type", 32));
+    assert!(is_reported(
+        &diags,
+        "`event::emit_event` with an `EventHandle` is deprecated.",
+        38
+    ));
+    assert!(is_reported(
+        &diags,
+        "Field `deposits` uses the deprecated `EventHandle`.",
+        11
+    ));
+    assert!(has_message(
+        &diags,
+        "Event struct `Withdrawn` is never emitted in this package."
+    ));
+    assert!(!has_message(&diags, "Event struct `Deposited`"));
+    let silent = "Entry function `{}` modifies global state but does not emit any event.";
+    for (name, reported) in [
+        ("silent_deposit", true),
+        ("silent_reset", true),
+        ("deposit", false),
+        ("register", false),
+        ("check", false),
+    ] {
+        assert_eq!(
+            has_message(&diags, &silent.replace("{}", name)),
+            reported,
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn test_unbounded_iteration() {
+    let diags = lint_case("unbounded_iteration");
+    let loop_bound = "Loop is bounded by the length of `Registry.members`, which any caller can grow through `Detector::join`.";
+    assert!(is_reported(&diags, loop_bound, 31));
+    assert!(is_reported(&diags, loop_bound, 44));
+    assert!(is_reported(
+        &diags,
+        "`vector::contains` iterates over `Registry.members`",
+        54
+    ));
+    assert!(is_reported(
+        &diags,
+        "`simple_map::keys` iterates over `Registry.scores`",
+        59
+    ));
+    assert!(!has_message(
+        &diags,
+        "Loop is bounded by the length of `Admins.admins`"
+    ));
+    for line in [75, 87] {
+        assert!(!is_reported(&diags, "Loop is bounded", line));
+    }
+    assert!(is_reported(
+        &diags,
+        "`smart_table::keys` iterates over `Balances.balances`",
+        105
+    ));
+}
+
+#[test]
+fn test_resource_group() {
+    let diags = lint_case("resource_group");
+    assert!(is_reported(
+        &diags,
+        "Resource group container `GroupWithFields` should not have fields.",
+        8
+    ));
+    assert!(is_reported(
+        &diags,
+        "Resource group container `GroupWithFields` should not have abilities.",
+        8
+    ));
+    assert!(is_reported(
+        &diags,
+        "Invalid resource group scope `package`.",
+        13
+    ));
+    assert!(is_reported(
+        &diags,
+        "`#[resource_group]` must have exactly one parameter, `scope = ...`.",
+        17
+    ));
+    assert!(is_reported(
+        &diags,
+        "Resource group member `NoKeyMember` must have the `key` ability.",
+        34
+    ));
+    assert!(is_reported(
+        &diags,
+        "`Detector::NotAGroup` is not a `#[resource_group]` container.",
+        39
+    ));
+    assert!(is_reported(
+        &diags,
+        "Resource group `Detector::MissingGroup` of member `MissingContainerMember` does not exist.",
+        45
+    ));
+    assert!(is_reported(
+        &diags,
+        "Resource group member `OutOfScopeMember` is outside the scope of `Detector::ModuleGroup`.",
+        53
+    ));
+    for valid in [4, 22, 28, 59] {
+        assert!(!diags.iter().any(|(message, line)| {
+            message.to_lowercase().contains("resource group") && *line == valid
+        }));
+    }
+}
+
+#[test]
+fn test_visibility_overexposure() {
+    let diags = lint_case("visibility_overexposure");
+    assert!(is_reported(
+        &diags,
+        "Public function `internal_helper` is only called from within its own module.",
+        6
+    ));
+    assert!(is_reported(
+        &diags,
+        "Public function `shared_helper` is only called from within this package. Consider making it `public(friend)` or `public(package)`.",
+        11
+    ));
+    assert!(!is_reported(
+        &diags,
+        "Public function `shared_helper` is only called from within its own module.",
+        11
+    ));
+    for function in ["api", "friend_helper", "run", "consume", "noop"] {
+        assert!(!has_message(
+            &diags,
+            &format!("Public function `{}` is only called", function)
+        ));
+    }
+    assert!(is_reported(&diags, "Friend module `", 3));
+    assert!(!is_reported(&diags, "Friend module `", 2));
+}
+
+#[test]
+fn test_abort_code() {
+    let diags = lint_case("abort_code");
+    let literal = "Abort code is a raw numeric literal.";
+    let unwrapped = "Abort code is not wrapped in a `std::error` category function";
+    assert!(is_reported(&diags, literal, 16));
+    assert!(is_reported(&diags, literal, 21));
+    assert!(is_reported(&diags, unwrapped, 26));
+    assert!(!is_reported(&diags, unwrapped, 31));
+    assert!(!is_reported(&diags, unwrapped, 32));
+    assert!(is_reported(
+        &diags,
+        "Error constant `E_ZERO_AMOUNT` has the same value as `E_INSUFFICIENT_BALANCE`.",
+        7
+    ));
+    assert!(is_reported(
+        &diags,
+        "Error constant `E_UNUSED` is never used.",
+        9
+    ));
+    assert!(!has_message(&diags, "Error constant `E_NOT_OWNER`"));
+    assert!(!has_message(&diags, "Error constant `MAX_AMOUNT`"));
+}
+
+#[test]
+fn test_unused_declarations() {
+    let diags = lint_case("unused_declarations");
+    assert!(diags.iter().any(|(message, line)| {
+        message.starts_with("Module `")
+            && message.ends_with("::string` is imported but never used.")
+            && *line == 4
+    }));
+    assert!(is_reported(
+        &diags,
+        "`borrow` is imported but never used.",
+        7
+    ));
+    assert!(!diags
+        .iter()
+        .any(|(message, _)| message.starts_with("Module `") && message.contains("::signer`")));
+    assert!(!has_message(&diags, "`Self` is imported but never used."));
+    assert!(!has_message(&diags, "`vector` is imported but never used."));
+    assert!(!has_message(&diags, "`length` is imported but never used."));
+    assert!(!has_message(&diags, "`Option` is imported but never used."));
+    assert!(is_reported(
+        &diags,
+        "Constant `UNUSED_LIMIT` is never read.",
+        11
+    ));
+    assert!(!has_message(&diags, "Constant `MAX_ITEMS`"));
+    assert!(is_reported(
+        &diags,
+        "Struct `Orphan` is never constructed or referenced.",
+        19
+    ));
+    assert!(!has_message(&diags, "Struct `Inventory`"));
+    assert!(!has_message(&diags, "Struct `Legacy`"));
+    assert!(is_reported(
+        &diags,
+        "Private function `triple` is never called.",
+        39
+    ));
+    assert!(is_reported(
+        &diags,
+        "Private function `countdown` is never called.",
+        44
+    ));
+    for function in ["double", "old_helper", "test_helper"] {
+        assert!(!has_message(
+            &diags,
+            &format!("Private function `{}`", function)
+        ));
+    }
+}
+
+#[test]
+fn test_division_by_zero() {
+    let diags = lint_case("division_by_zero");
+    assert!(is_reported(
+        &diags,
+        "Division by `shares`, which may be zero.",
+        8
+    ));
+    assert!(is_reported(
+        &diags,
+        "Division by `ZERO`, which is always zero.",
+        13
+    ));
+    assert!(is_reported(
+        &diags,
+        "Modulo by `remaining`, which may be zero.",
+        19
+    ));
+    assert!(is_reported(
+        &diags,
+        "Division by `divisor`, which is always zero.",
+        25
+    ));
+    assert!(is_reported(
+        &diags,
+        "Division by `pool.total_shares`, which may be zero.",
+        64
+    ));
+    for guarded in [30, 37, 41, 45, 49, 54, 70] {
+        assert!(!diags.iter().any(|(message, line)| {
+            (message.starts_with("Division by") || message.starts_with("Modulo by"))
+                && *line == guarded
+        }));
+    }
+}
+
+/// Returns how often a diagnostic starting with `message` is reported at `line`.
+fn count_reported(diags: &[(String, usize)], message: &str, line: usize) -> usize {
+    diags
+        .iter()
+        .filter(|(diag_message, diag_line)| diag_message.starts_with(message) && *diag_line == line)
+        .count()
+}
+
+#[test]
+fn test_shift_overflow() {
+    let diags = lint_case("shift_overflow");
+    let overflow = "Potential overflow detected.";
+    for line in [4, 5, 6, 13, 14, 15, 16, 24] {
+        assert_eq!(count_reported(&diags, overflow, line), 1);
+    }
+    for line in [3, 17, 22] {
+        assert_eq!(count_reported(&diags, overflow, line), 0);
+    }
+}
+
+#[test]
+fn test_overflow_multiplication_detector() {
+    let diags = lint_case("overflow_multiplication_detector");
+    for (message, line) in [
+        ("Potential multiplication overflow detected.", 13),
+        ("Potential addition overflow detected.", 18),
+        ("Potential addition overflow detected.", 24),
+        ("Potential multiplication overflow detected.", 30),
+    ] {
+        assert_eq!(count_reported(&diags, message, line), 1);
+    }
+    for line in [7, 25, 35] {
+        assert_eq!(
+            count_reported(&diags, "Potential multiplication overflow", line),
+            0
+        );
+    }
+}
+
+#[test]
+fn test_multiplication_before_division() {
+    let diags = lint_case("multiplication_before_division");
+    let message = "Multiplication should come before division";
+    assert_eq!(count_reported(&diags, message, 4), 1);
+    assert_eq!(count_reported(&diags, message, 15), 1);
+    assert_eq!(
+        diags
+            .iter()
+            .filter(|(diag_message, _)| diag_message.starts_with(message))
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn test_cast_truncation() {
+    let diags = lint_case("cast_truncation");
+    let always = "Cast truncation detected.";
+    assert!(is_reported(&diags, always, 4));
+    assert!(is_reported(&diags, always, 9));
+    assert!(is_reported(
+        &diags,
+        "Narrowing cast from `u128` to `u64` is not preceded by a bounds check.",
+        23
+    ));
+    assert!(is_reported(
+        &diags,
+        "The multiplication result computed in `u128` may not fit into `u64`.",
+        32
+    ));
+    for line in [14, 19, 28, 36, 37] {
+        assert!(!diags.iter().any(|(message, diag_line)| {
+            (message.starts_with(always)
+                || message.starts_with("Narrowing cast")
+                || message.contains("result computed in"))
+                && *diag_line == line
+        }));
+    }
+}
+
+#[test]
+fn test_timestamp_dependence() {
+    let diags = lint_case("timestamp_dependence");
+    assert!(is_reported(
+        &diags,
+        "Condition depends on `timestamp::now_seconds`",
+        16
+    ));
+    assert!(is_reported(
+        &diags,
+        "Condition depends on `block::get_current_block_height`",
+        24
+    ));
+    assert!(is_reported(
+        &diags,
+        "Condition depends on `timestamp::now_seconds`",
+        30
+    ));
+    assert!(is_reported(
+        &diags,
+        "Condition depends on `transaction_context::get_transaction_hash`",
+        38
+    ));
+    for line in [45, 50] {
+        assert!(!is_reported(&diags, "Condition depends on", line));
+    }
+}
+
+#[test]
+fn test_spot_price_oracle() {
+    let diags = lint_case("spot_price_oracle");
+    let spot_price = "Price computed from the current reserves of a pool.";
+    assert!(is_reported(&diags, spot_price, 12));
+    assert!(is_reported(&diags, spot_price, 20));
+    for line in [27, 33, 34] {
+        assert!(!is_reported(&diags, spot_price, line));
+    }
+}
+
+#[test]
+fn test_front_running() {
+    let diags = lint_case("front_running");
+    let first_come = "First-come logic on `Detector::Claimed`";
+    assert!(is_reported(&diags, first_come, 21));
+    assert!(is_reported(&diags, first_come, 30));
+    assert!(!has_message(
+        &diags,
+        "First-come logic on `Detector::Registered`"
+    ));
+    assert!(is_reported(
+        &diags,
+        "Parameter `min_amount_out` looks like",
+        43
+    ));
+    assert!(is_reported(
+        &diags,
+        "Parameter `max_slippage` looks like",
+        51
+    ));
+    assert!(!is_reported(
+        &diags,
+        "Parameter `min_amount_out` looks like",
+        56
+    ));
+}
+
+#[test]
+fn test_unmodified_mutable_argument() {
+    let diags = lint_case("unmodified_mutable_argument_lint");
+    let message = "Mutable parameter `my_struct` is never modified";
+    assert!(is_reported(&diags, message, 11));
+    for line in [15, 25, 30, 39, 47, 51] {
+        assert!(!is_reported(&diags, message, line));
+    }
+    let message = "Mutable parameter `items` is never modified";
+    assert!(is_reported(&diags, message, 43));
+    assert!(!is_reported(&diags, message, 34));
+    assert!(!is_reported(
+        &diags,
+        "Mutable parameter `counter` is never modified",
+        19
+    ));
+}
+
+#[test]
+fn test_absurd_extreme_comparisons() {
+    let diags = lint_case("absurd_extreme_comparisons");
+    for line in [10, 13, 16, 19] {
+        assert!(is_reported(&diags, "Comparison is always false", line));
+    }
+    for line in [27, 30, 33, 42] {
+        assert!(is_reported(&diags, "Comparison is always true", line));
+    }
+    assert!(is_reported(
+        &diags,
+        "Comparison is always true: `MAX_U64` is the maximum value of `u64`.",
+        30
+    ));
+    for line in [22, 36, 44, 47] {
+        assert!(!is_reported(&diags, "Comparison is always", line));
+    }
+}
+
+#[test]
+fn test_infinite_loop() {
+    let diags = lint_case("infinite_loop_detector");
+    let infinite = "Potential infinite loop detected.";
+    assert!(is_reported(&diags, infinite, 3));
+    assert!(is_reported(&diags, infinite, 36));
+    for line in [11, 22, 29] {
+        assert!(!is_reported(&diags, infinite, line));
+    }
+    let unmodified = "Loop condition never changes";
+    assert!(is_reported(&diags, unmodified, 46));
+    assert!(!is_reported(&diags, unmodified, 53));
+    assert!(!is_reported(&diags, unmodified, 59));
+}
+
+#[test]
+fn test_out_of_bounds_array_indexing() {
+    let diags = lint_case("out_of_bounds_array_indexing");
+    let out_of_bounds = "Array index out of bounds detected";
+    for line in [13, 21, 28, 34, 53, 65] {
+        assert!(is_reported(&diags, out_of_bounds, line));
+    }
+    for line in [7, 41, 76, 89] {
+        assert!(!is_reported(&diags, out_of_bounds, line));
+    }
+}
+
+#[test]
+fn test_nested_same_condition() {
+    let diags = lint_case("deep_nesting");
+    let message = "Detected consecutive if conditions with the same expression.";
+    assert_eq!(count_reported(&diags, message, 12), 1);
+    for line in [7, 9, 15, 18, 21] {
+        assert_eq!(count_reported(&diags, message, line), 0);
+    }
+}
+
+#[test]
+fn test_duplicated_code() {
+    let diags = lint_case("duplicated_code");
+    let identical = "Detected if/else with identical branches.";
+    assert!(is_reported(&diags, identical, 6));
+    assert!(is_reported(&diags, identical, 14));
+    assert!(!is_reported(&diags, identical, 22));
+    assert!(is_reported(
+        &diags,
+        "Detected identical branches in an if/else if chain.",
+        32
+    ));
+    assert!(is_reported(
+        &diags,
+        "Function `Detector::withdraw_fee` is a near duplicate of `Detector::deposit_fee`.",
+        46
+    ));
+    assert!(!has_message(&diags, "Function `Detector::transfer_fee`"));
+}
+
+#[test]
+fn test_unused_locals() {
+    let diags = lint_case("unused_locals");
+    assert!(is_reported(
+        &diags,
+        "Local `unused` is assigned but never read.",
+        9
+    ));
+    assert!(is_reported(
+        &diags,
+        "Value assigned to `y` is never read",
+        14
+    ));
+    assert!(!has_message(&diags, "Local `_ignored`"));
+    assert!(!has_message(&diags, "Local `i`"));
+    assert!(!has_message(&diags, "Local `sum`"));
+    let discarded = "The result of `Detector::compute` is discarded with `let _`";
+    assert!(is_reported(&diags, discarded, 35));
+    assert!(!has_message(&diags, "The result of `vector::pop_back`"));
+}
+
+#[test]
+fn test_unreachable_code() {
+    let diags = lint_case("unreachable_code");
+    let unreachable = "Unreachable code:";
+    for line in [5, 11, 18, 23, 30] {
+        assert!(is_reported(&diags, unreachable, line));
+    }
+    for line in [39, 40, 47, 48] {
+        assert!(!is_reported(&diags, unreachable, line));
+    }
+}
+
+#[test]
+fn test_meaningless_math_operations() {
+    let diags = lint_case("meaningless_math_operations");
+    let unnecessary = "Detected an unnecessary mathematical operation.";
+    for line in [13, 17, 21, 25, 29] {
+        assert!(is_reported(&diags, unnecessary, line));
+    }
+    assert!(!is_reported(&diags, unnecessary, 4));
+    let always_true = "Detected a comparison of a value with itself, which is always true.";
+    assert!(is_reported(&diags, always_true, 38));
+    assert!(is_reported(&diags, always_true, 60));
+    let always_false = "Detected a comparison of a value with itself, which is always false.";
+    assert!(is_reported(&diags, always_false, 42));
+    assert!(is_reported(&diags, always_false, 55));
+    assert!(is_reported(
+        &diags,
+        "Detected an operation of a value with itself",
+        46
+    ));
+    assert!(is_reported(
+        &diags,
+        "Detected a division of a value by itself",
+        50
+    ));
+    assert!(is_reported(
+        &diags,
+        "Detected a disjunction of a condition with its negation",
+        64
+    ));
+    assert!(is_reported(
+        &diags,
+        "Detected an if/else whose branches are both its condition",
+        68
+    ));
+    assert!(!is_reported(
+        &diags,
+        "Detected a comparison of a value with itself",
+        74
+    ));
+}
+
+#[test]
+fn test_ignored_return_value() {
+    let diags = lint_case("ignored_return_value");
+    let handle = "Consider handling it, or discard it explicitly with `let _ = ..`";
+    let remove = "Consider using its result or removing the call.";
+    for (callee, line, advice) in [
+        ("vector::remove", 15, handle),
+        ("option::extract", 19, handle),
+        ("vector::contains", 24, remove),
+        ("Detector::checked_add", 28, remove),
+    ] {
+        let message = format!("The result of `{}` is ignored", callee);
+        assert!(is_reported(&diags, &message, line));
+        assert!(diags
+            .iter()
+            .any(|(diag, diag_line)| *diag_line == line && diag.contains(advice)));
+    }
+    assert!(is_reported(
+        &diags,
+        "The result of `coin::withdraw` is passed straight to `coin::destroy_zero`",
+        33
+    ));
+    for line in [37, 41] {
+        assert!(!is_reported(&diags, "The result of `vector::remove`", line));
+    }
+}